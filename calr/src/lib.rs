@@ -0,0 +1,1022 @@
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fmt::{Debug, Write},
+    fs,
+    io::{self, IsTerminal},
+    path::PathBuf,
+};
+
+use ansi_term::{Colour, Style};
+use anyhow::{Result, anyhow, bail};
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+use clap::{ColorChoice, CommandFactory, Parser, ValueEnum};
+use itertools::Itertools;
+use unicode_width::UnicodeWidthChar;
+
+/// Rust version of ‘cal’
+#[derive(Debug, Parser)]
+#[command(about, version, author)]
+struct CLIArgs {
+    /// Year (1-9999)
+    #[arg(value_parser = clap::value_parser!(i32).range(1..=9999))]
+    year: Option<i32>,
+
+    /// Month name or number (1-12)
+    #[arg(short, value_parser = month_arg_parser)]
+    month: Option<u32>,
+
+    /// Show whole current year
+    #[arg(
+        short = 'y',
+        long = "year",
+        default_value_t = false,
+        conflicts_with_all = ["month", "year"],
+    )]
+    show_current_year: bool,
+
+    /// Show week numbers
+    #[arg(short = 'w', long = "week-numbers")]
+    week_numbers: bool,
+
+    /// Rule for numbering weeks (implies --week-numbers)
+    #[arg(long, value_name = "RULE", value_enum)]
+    week_rule: Option<WeekNumbering>,
+
+    /// First month of a span of months (YYYY-MM)
+    #[arg(
+        long,
+        value_name = "YYYY-MM",
+        value_parser = year_month_arg_parser,
+        requires = "to",
+        conflicts_with_all = ["month", "year", "show_current_year"],
+    )]
+    from: Option<(i32, u32)>,
+
+    /// Last month of a span of months (YYYY-MM)
+    #[arg(
+        long,
+        value_name = "YYYY-MM",
+        value_parser = year_month_arg_parser,
+        requires = "from"
+    )]
+    to: Option<(i32, u32)>,
+
+    /// Styles for calendar parts, e.g. "weekend=bold,today=reverse,header=underline+blue"
+    /// [default: $CALR_THEME or "today=reverse"]
+    #[arg(long, value_name = "SPEC", value_parser = parse_theme)]
+    theme: Option<Theme>,
+
+    /// Style the calendar with the theme; WHEN may be left out for always
+    #[arg(
+        long,
+        value_name = "WHEN",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_value_t = ColorChoice::Auto,
+        default_missing_value = "always"
+    )]
+    color: ColorChoice,
+
+    /// Fill the first and last rows with days of the adjacent months
+    #[arg(long)]
+    adjacent: bool,
+
+    /// Print the calendar as it is, even when it is too long for the
+    /// terminal
+    #[arg(long)]
+    no_pager: bool,
+
+    /// Mark days with the glyphs listed in FILE, one "YYYY-MM-DD GLYPH" per
+    /// line
+    #[arg(long, value_name = "FILE")]
+    annotate: Option<PathBuf>,
+
+    /// Mark the days of new, first quarter, full and last quarter moons
+    #[cfg(feature = "moon")]
+    #[arg(long)]
+    moon: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum WeekNumbering {
+    /// ISO 8601: weeks start on Monday, week 1 contains the first Thursday
+    Iso,
+    /// US: weeks start on Sunday, week 1 contains January 1st
+    Sunday,
+}
+
+impl WeekNumbering {
+    /// Number of the week shown on a calendar row starting at `sunday`
+    fn week_of_row(self, sunday: NaiveDate) -> u32 {
+        match self {
+            WeekNumbering::Iso => (sunday + Days::new(1)).iso_week().week(),
+            WeekNumbering::Sunday => {
+                // a row crossing the new year is already week 1 of the next one
+                let saturday = sunday + Days::new(6);
+                let jan1 = NaiveDate::from_ymd_opt(saturday.year(), 1, 1).unwrap();
+                (saturday.ordinal0() + jan1.weekday().num_days_from_sunday()) / 7 + 1
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Period {
+    Month(i32, u32),
+    Year(i32),
+    /// Inclusive range of (year, month) pairs
+    Span((i32, u32), (i32, u32)),
+}
+
+/// How each part of a month is styled. Parts without a style are printed as is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Theme {
+    /// Month name and weekday names
+    header: Style,
+    /// Saturdays and Sundays
+    weekend: Style,
+    today: Style,
+    /// Days of the previous and next months, shown with `adjacent`
+    other: Style,
+    adjacent: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            header: Style::new(),
+            weekend: Style::new(),
+            today: Style::new().reverse(),
+            other: Style::new().dimmed(),
+            adjacent: false,
+        }
+    }
+}
+
+/// What a single day slot of the month grid shows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DayCell {
+    /// A day of the month being shown
+    Day { date: NaiveDate, today: bool },
+    /// A day of the previous or next month, in a row shared with this one
+    Adjacent(NaiveDate),
+}
+
+impl DayCell {
+    /// The cell as 2 visible columns, wrapped in escape codes by `theme`,
+    /// and with `annotations` a third one for the corner glyph.
+    fn render(self, theme: &Theme, annotations: &[Box<dyn DayAnnotation>]) -> String {
+        let (date, style) = match self {
+            DayCell::Adjacent(date) if theme.adjacent => (date, theme.other),
+            DayCell::Adjacent(_) if annotations.is_empty() => return "  ".to_string(),
+            DayCell::Adjacent(_) => return "   ".to_string(),
+            DayCell::Day { date, today: true } => (date, theme.today),
+            DayCell::Day { date, today: false } => match date.weekday() {
+                Weekday::Sat | Weekday::Sun => (date, theme.weekend),
+                _ => (date, Style::new()),
+            },
+        };
+        let mut cell = style.paint(format!("{:>2}", date.day())).to_string();
+        if !annotations.is_empty() {
+            cell.push(annotation_glyph(annotations, date).unwrap_or(' '));
+        }
+        cell
+    }
+}
+
+/// Something to show in the corner of day cells, such as the phase of the
+/// moon. When there are any, every cell gets a column for them right of the
+/// day.
+trait DayAnnotation: Debug {
+    /// The glyph for `date`, one column wide, if it has one.
+    fn glyph(&self, date: NaiveDate) -> Option<char>;
+}
+
+/// The glyph of the first of `annotations` that has one for `date`.
+fn annotation_glyph(annotations: &[Box<dyn DayAnnotation>], date: NaiveDate) -> Option<char> {
+    annotations
+        .iter()
+        .find_map(|annotation| annotation.glyph(date))
+}
+
+/// Glyphs for single days, as read by `parse_glyph_map` from --annotate.
+#[derive(Debug, Default, PartialEq)]
+struct GlyphMap(HashMap<NaiveDate, char>);
+
+impl DayAnnotation for GlyphMap {
+    fn glyph(&self, date: NaiveDate) -> Option<char> {
+        self.0.get(&date).copied()
+    }
+}
+
+/// Marks the days on which the moon reaches each quarter of its cycle, in
+/// UT. Phases are computed with the largest terms of Meeus' ‘Astronomical
+/// Algorithms’, which keeps them within an hour or so of the true ones.
+#[cfg(feature = "moon")]
+#[derive(Debug)]
+struct MoonPhase;
+
+#[cfg(feature = "moon")]
+impl MoonPhase {
+    /// Mean length of a lunation, in days
+    const SYNODIC_MONTH: f64 = 29.530588861;
+    /// Julian day of the mean new moon of 2000-01-06
+    const NEW_MOON: f64 = 2451550.09766;
+    /// New, first quarter, full and last quarter moon
+    const GLYPHS: [char; 4] = ['●', '◐', '○', '◑'];
+
+    /// Julian day of the `quarter`th quarter phase after the new moon of
+    /// 2000-01-06, or before it for negative ones.
+    fn phase_time(quarter: i64) -> f64 {
+        let k = quarter as f64 / 4.0;
+        // Mean anomalies of the sun and the moon
+        let sun = (2.5534 + 29.1053567 * k).to_radians();
+        let moon = (201.5643 + 385.81693528 * k).to_radians();
+        let correction = match quarter.rem_euclid(4) {
+            0 => -0.4072 * moon.sin() + 0.17241 * sun.sin(),
+            2 => -0.40614 * moon.sin() + 0.17302 * sun.sin(),
+            _ => -0.62801 * moon.sin() + 0.17172 * sun.sin(),
+        };
+        Self::NEW_MOON + Self::SYNODIC_MONTH * k + correction
+    }
+}
+
+#[cfg(feature = "moon")]
+impl DayAnnotation for MoonPhase {
+    fn glyph(&self, date: NaiveDate) -> Option<char> {
+        let j2000 = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+        let start = 2451544.5 + (date - j2000).num_days() as f64;
+        // The corrections are less than a day, so only the phases around the
+        // mean one can fall on `date`
+        let mean = ((start - Self::NEW_MOON) / Self::SYNODIC_MONTH * 4.0).floor() as i64;
+        (mean - 1..=mean + 2)
+            .find(|&quarter| (start..start + 1.0).contains(&Self::phase_time(quarter)))
+            .map(|quarter| Self::GLYPHS[quarter.rem_euclid(4) as usize])
+    }
+}
+
+#[derive(Debug)]
+struct Args {
+    period: Period,
+    weeks: Option<WeekNumbering>,
+    theme: Theme,
+    /// In order of precedence, when several have a glyph for the same day
+    annotations: Vec<Box<dyn DayAnnotation>>,
+}
+
+impl Args {
+    /// Width of a single month, without the separator
+    fn block_width(&self) -> usize {
+        block_width(self.weeks, &self.annotations)
+    }
+}
+
+/// The command line that [`main`] parses, for generating the man page.
+pub fn command() -> clap::Command {
+    CLIArgs::command()
+}
+
+/// What the man page tells beyond --help.
+pub const MAN_DESCRIPTION: &str = "\
+Without arguments calr shows the current month, with today highlighted when \
+the output is a terminal or --color is given. A YEAR alone shows that whole year, and -m picks a \
+single month of the current year or of YEAR; --from and --to show any span of \
+months instead, three to a row.\n\n\
+Themes style the parts of the calendar with terminal attributes and colors, \
+and are read from $CALR_THEME unless --theme is given. Like the highlight of \
+today, they are only used when the output is a terminal, unless --color says \
+otherwise. Files for --annotate \
+list one date and glyph per line; empty lines and lines starting with ‘#’ are \
+skipped.\n\n\
+When the calendar has more lines than the terminal, as a long span of months \
+does, it is shown through $PAGER, or less when PAGER isn't set, unless \
+--no-pager is given or PAGER is empty. Output that isn't a terminal is never \
+paged.";
+
+pub fn main(args: impl IntoIterator<Item = OsString>) -> Result<()> {
+    let cli_args = CLIArgs::parse_from(args);
+    let args = parse_args(&cli_args)?;
+    let today = chrono::Local::now().date_naive();
+
+    // Written at once, so that a span too long for the terminal can be paged
+    let mut out = String::new();
+    match args.period {
+        Period::Month(year, month) => {
+            for line in format_month(year, month, true, today, &args) {
+                writeln!(out, "{line}")?;
+            }
+        }
+        Period::Year(year) => {
+            let months: Vec<(i32, u32)> = (1..=12).map(|month| (year, month)).collect();
+            writeln!(
+                out,
+                "{year:>width$}",
+                width = args.block_width() * 3 / 2 + 2
+            )?;
+            write_blocks(&mut out, format_blocks(&months, false, today, &args));
+        }
+        Period::Span(from, to) => {
+            let months = months_between(from, to);
+            // Only a span crossing a year boundary needs the year on each month
+            if from.0 == to.0 {
+                let row_width = args.block_width() * months.len().min(3) / 2 + 2;
+                writeln!(out, "{:>width$}", from.0, width = row_width)?;
+                write_blocks(&mut out, format_blocks(&months, false, today, &args));
+            } else {
+                write_blocks(&mut out, format_blocks(&months, true, today, &args));
+            }
+        }
+    }
+    learnr::page(&out, !cli_args.no_pager)?;
+    Ok(())
+}
+
+/// Writes blocks of months separated by empty lines.
+fn write_blocks(out: &mut String, blocks: Vec<Vec<String>>) {
+    for (idx, block_lines) in blocks.iter().enumerate() {
+        if idx > 0 {
+            out.push('\n');
+        }
+        for line in block_lines {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+}
+
+/// Lays out `months` side by side, three per block.
+fn format_blocks(
+    months: &[(i32, u32)],
+    print_year: bool,
+    today: NaiveDate,
+    args: &Args,
+) -> Vec<Vec<String>> {
+    months
+        .chunks(3)
+        .map(|chunk| {
+            let formatted: Vec<Vec<String>> = chunk
+                .iter()
+                .map(|&(year, month)| format_month(year, month, print_year, today, args))
+                .collect();
+            (0..formatted[0].len())
+                .map(|line| formatted.iter().map(|m| m[line].as_str()).collect())
+                .collect()
+        })
+        .collect()
+}
+
+/// All (year, month) pairs from `from` to `to` inclusive.
+fn months_between(from: (i32, u32), to: (i32, u32)) -> Vec<(i32, u32)> {
+    let mut months = vec![];
+    let (mut year, mut month) = from;
+    while (year, month) <= to {
+        months.push((year, month));
+        (year, month) = if month == 12 {
+            (year + 1, 1)
+        } else {
+            (year, month + 1)
+        };
+    }
+    months
+}
+
+fn parse_args(cli_args: &CLIArgs) -> Result<Args> {
+    let now = chrono::Local::now();
+    let period = match (cli_args.year, cli_args.month, cli_args.show_current_year) {
+        (_, _, true) => Period::Year(now.year()),
+        (None, None, _) => Period::Month(now.year(), now.month()),
+        (Some(year), None, _) => Period::Year(year),
+        (None, Some(month), false) => Period::Month(now.year(), month),
+        (Some(year), Some(month), false) => Period::Month(year, month),
+    };
+
+    let period = match (cli_args.from, cli_args.to) {
+        (Some(from), Some(to)) if from > to => {
+            bail!(
+                "--from {} is after --to {}",
+                show_year_month(from),
+                show_year_month(to)
+            )
+        }
+        (Some(from), Some(to)) => Period::Span(from, to),
+        _ => period,
+    };
+
+    let weeks = match (cli_args.week_numbers, cli_args.week_rule) {
+        (_, Some(rule)) => Some(rule),
+        (true, None) => Some(WeekNumbering::Iso),
+        (false, None) => None,
+    };
+
+    let theme = match &cli_args.theme {
+        Some(theme) => *theme,
+        None => match std::env::var("CALR_THEME") {
+            Ok(spec) => parse_theme(&spec).map_err(|e| anyhow!("CALR_THEME: {e}"))?,
+            Err(_) => Theme::default(),
+        },
+    };
+    let styled = match cli_args.color {
+        ColorChoice::Auto => io::stdout().is_terminal(),
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+    };
+    let theme = match styled {
+        true => theme,
+        false => Theme {
+            header: Style::new(),
+            weekend: Style::new(),
+            today: Style::new(),
+            other: Style::new(),
+            ..theme
+        },
+    };
+    let theme = Theme {
+        adjacent: cli_args.adjacent,
+        ..theme
+    };
+
+    let mut annotations: Vec<Box<dyn DayAnnotation>> = vec![];
+    if let Some(path) = &cli_args.annotate {
+        let text = fs::read_to_string(path).map_err(|e| anyhow!("{}: {e}", path.display()))?;
+        let glyphs = parse_glyph_map(&text).map_err(|e| anyhow!("{}: {e}", path.display()))?;
+        annotations.push(Box::new(glyphs));
+    }
+    #[cfg(feature = "moon")]
+    if cli_args.moon {
+        annotations.push(Box::new(MoonPhase));
+    }
+
+    Ok(Args {
+        period,
+        weeks,
+        theme,
+        annotations,
+    })
+}
+
+/// Parses lines of "YYYY-MM-DD GLYPH", skipping empty ones and those
+/// starting with ‘#’. Each glyph must be a single character one column wide,
+/// to fit the corner of a day cell.
+fn parse_glyph_map(text: &str) -> Result<GlyphMap> {
+    let mut glyphs = GlyphMap::default();
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = |msg: &str| anyhow!("line {}: {msg}", idx + 1);
+        let (date, glyph) = line
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| error("expected YYYY-MM-DD GLYPH"))?;
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| error(&format!(r#"invalid date "{date}""#)))?;
+        let glyph = match glyph.trim_start().chars().collect::<Vec<_>>()[..] {
+            [glyph] if glyph.width() == Some(1) => glyph,
+            _ => {
+                return Err(error(&format!(
+                    r#"glyph "{}" is not one column wide"#,
+                    glyph.trim_start()
+                )));
+            }
+        };
+        glyphs.0.insert(date, glyph);
+    }
+    Ok(glyphs)
+}
+
+const BLOCK_WIDTH: usize = 2 /* sun */ + 3 * 6 /* mon-sat */;
+const WEEK_NUMBER_WIDTH: usize = 3;
+const HORIZONTAL_SEPARATOR: &str = "  ";
+
+/// Width of a single month, without the separator: annotations take one more
+/// column, after Saturday.
+fn block_width(weeks: Option<WeekNumbering>, annotations: &[Box<dyn DayAnnotation>]) -> usize {
+    BLOCK_WIDTH + weeks.map_or(0, |_| WEEK_NUMBER_WIDTH) + usize::from(!annotations.is_empty())
+}
+
+fn format_month(
+    year: i32,
+    month: u32,
+    print_year: bool,
+    today: NaiveDate,
+    args: &Args,
+) -> Vec<String> {
+    let theme = &args.theme;
+    // Corner glyphs take the place of the space between days
+    let (day_width, day_separator) = match args.annotations.is_empty() {
+        true => (BLOCK_WIDTH, " "),
+        false => (BLOCK_WIDTH + 1, ""),
+    };
+    let mut label: String = MONTH_NAMES[month as usize - 1].to_string();
+    if print_year {
+        label += &format!(" {year}").to_string();
+    }
+    // Pad outside of the escape codes, so that they don't count as width
+    let padding = day_width.saturating_sub(label.chars().count());
+    let weekdays = "Su Mo Tu We Th Fr Sa";
+    let mut rows = vec![
+        format!(
+            "{}{}{}",
+            " ".repeat(padding / 2),
+            theme.header.paint(label),
+            " ".repeat(padding - padding / 2)
+        ),
+        theme.header.paint(weekdays).to_string() + &" ".repeat(day_width - weekdays.len()),
+    ];
+
+    let dt = chrono::NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+    let filler_num = dt.weekday().number_from_sunday() - 1;
+    let first_sunday = dt - Days::new(filler_num.into());
+
+    let days = (0..42).map(|idx| {
+        let date = first_sunday + Days::new(idx);
+        let cell = if date.month() != month {
+            DayCell::Adjacent(date)
+        } else {
+            DayCell::Day {
+                date,
+                today: date == today,
+            }
+        };
+        cell.render(theme, &args.annotations)
+    });
+
+    rows.extend(
+        days.chunks(7)
+            .into_iter()
+            .map(|ds| itertools::join(ds, day_separator)),
+    );
+
+    if let Some(numbering) = args.weeks {
+        let last_day = dt + Days::new((dt.num_days_in_month() - 1).into());
+        for (idx, row) in rows.iter_mut().enumerate() {
+            let prefix = match idx.checked_sub(2) {
+                Some(week_idx) => {
+                    let sunday = first_sunday + Days::new(7 * week_idx as u64);
+                    if sunday <= last_day {
+                        format!("{:>2} ", numbering.week_of_row(sunday))
+                    } else {
+                        " ".repeat(WEEK_NUMBER_WIDTH)
+                    }
+                }
+                None => " ".repeat(WEEK_NUMBER_WIDTH),
+            };
+            row.insert_str(0, &prefix);
+        }
+    }
+
+    rows.iter_mut()
+        .for_each(|r: &mut String| *r += HORIZONTAL_SEPARATOR);
+    rows
+}
+
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+fn month_arg_parser(arg: &str) -> Result<u32> {
+    if arg.chars().all(char::is_numeric) {
+        let month = arg.parse::<u32>().unwrap();
+        if (1..=12).contains(&month) {
+            return Ok(month);
+        }
+        return Err(anyhow!(r#"month "{arg}" not in the range 1 through 12"#));
+    }
+
+    let candidates: Vec<(String, u32)> = MONTH_NAMES
+        .into_iter()
+        .map(str::to_lowercase)
+        .zip(1..=12)
+        .filter(|(n, _)| n.starts_with(arg))
+        .collect();
+
+    match candidates.as_slice() {
+        [(_, idx)] => Ok(*idx),
+        [_, ..] => bail!(r#"Ambigous month name "{arg}""#),
+        [] => bail!(r#"Invalid month "{arg}""#),
+    }
+}
+
+fn year_month_arg_parser(arg: &str) -> Result<(i32, u32)> {
+    let invalid = || anyhow!(r#"Invalid month "{arg}", expected YYYY-MM"#);
+    let (year, month) = arg.split_once('-').ok_or_else(invalid)?;
+    let year: i32 = year.parse().map_err(|_| invalid())?;
+    let month: u32 = month.parse().map_err(|_| invalid())?;
+    if !(1..=9999).contains(&year) {
+        bail!(r#"year "{year}" not in the range 1 through 9999"#);
+    }
+    if !(1..=12).contains(&month) {
+        bail!(r#"month "{month}" not in the range 1 through 12"#);
+    }
+    Ok((year, month))
+}
+
+fn show_year_month((year, month): (i32, u32)) -> String {
+    format!("{year:04}-{month:02}")
+}
+
+/// Parses "PART=STYLE[+STYLE...],..." on top of the default theme, where
+/// PART is header, weekend, today or other, and "none" clears a style.
+fn parse_theme(spec: &str) -> Result<Theme> {
+    let mut theme = Theme::default();
+    for item in spec.split(',').filter(|item| !item.is_empty()) {
+        let (part, styles) = item
+            .split_once('=')
+            .ok_or_else(|| anyhow!("expected PART=STYLE, got \"{item}\""))?;
+        let target = match part {
+            "header" => &mut theme.header,
+            "weekend" => &mut theme.weekend,
+            "today" => &mut theme.today,
+            "other" => &mut theme.other,
+            _ => bail!("unknown theme part \"{part}\""),
+        };
+        *target = styles.split('+').try_fold(Style::new(), add_style)?;
+    }
+    Ok(theme)
+}
+
+fn add_style(style: Style, name: &str) -> Result<Style> {
+    Ok(match name {
+        "none" => style,
+        "bold" => style.bold(),
+        "dimmed" | "dim" => style.dimmed(),
+        "italic" => style.italic(),
+        "underline" => style.underline(),
+        "reverse" => style.reverse(),
+        "black" => style.fg(Colour::Black),
+        "red" => style.fg(Colour::Red),
+        "green" => style.fg(Colour::Green),
+        "yellow" => style.fg(Colour::Yellow),
+        "blue" => style.fg(Colour::Blue),
+        "purple" | "magenta" => style.fg(Colour::Purple),
+        "cyan" => style.fg(Colour::Cyan),
+        "white" => style.fg(Colour::White),
+        _ => bail!("unknown style \"{name}\""),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use assertables::*;
+    use learnr::assert_err_str_contains;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn args(weeks: Option<WeekNumbering>, theme: Theme) -> Args {
+        Args {
+            period: Period::Year(2020),
+            weeks,
+            theme,
+            annotations: vec![],
+        }
+    }
+
+    #[test]
+    fn test_month_arg_parser() {
+        let res = month_arg_parser("1");
+        assert_ok_eq_x!(res, 1);
+
+        let res = month_arg_parser("12");
+        assert_ok_eq_x!(res, 12);
+
+        let res = month_arg_parser("jan");
+        assert_ok_eq_x!(res, 1);
+
+        let res = month_arg_parser("0");
+        assert_err_str_contains!(res, r#"month "0" not in the range 1 through 12"#);
+
+        let res = month_arg_parser("13");
+        assert_err_str_contains!(res, r#"month "13" not in the range 1 through 12"#);
+
+        let res = month_arg_parser("foo");
+        assert_err_str_contains!(res, r#"Invalid month "foo""#);
+
+        assert_err_str_contains!(month_arg_parser("ju"), "Ambigous");
+    }
+
+    #[test]
+    fn test_year_month_arg_parser() {
+        assert_ok_eq_x!(year_month_arg_parser("2024-11"), (2024, 11));
+        assert_ok_eq_x!(year_month_arg_parser("1-1"), (1, 1));
+        assert_err_str_contains!(year_month_arg_parser("2024"), "expected YYYY-MM");
+        assert_err_str_contains!(year_month_arg_parser("2024-nov"), "expected YYYY-MM");
+        assert_err_str_contains!(year_month_arg_parser("2024-13"), r#"month "13" not"#);
+        assert_err_str_contains!(year_month_arg_parser("0-1"), r#"year "0" not"#);
+    }
+
+    #[test]
+    fn test_months_between() {
+        assert_eq!(
+            months_between((2024, 11), (2025, 2)),
+            vec![(2024, 11), (2024, 12), (2025, 1), (2025, 2)]
+        );
+        assert_eq!(months_between((2024, 5), (2024, 5)), vec![(2024, 5)]);
+        assert_eq!(months_between((2024, 5), (2024, 4)), vec![]);
+        assert_eq!(months_between((2020, 1), (2022, 12)).len(), 36);
+    }
+
+    #[test]
+    fn test_format_month() {
+        let today = NaiveDate::from_ymd_opt(0, 1, 1).unwrap();
+        let leap_february = vec![
+            "   February 2020      ",
+            "Su Mo Tu We Th Fr Sa  ",
+            "                   1  ",
+            " 2  3  4  5  6  7  8  ",
+            " 9 10 11 12 13 14 15  ",
+            "16 17 18 19 20 21 22  ",
+            "23 24 25 26 27 28 29  ",
+            "                      ",
+        ];
+        assert_eq!(
+            format_month(2020, 2, true, today, &args(None, Theme::default())),
+            leap_february
+        );
+
+        let may = vec![
+            "        May           ",
+            "Su Mo Tu We Th Fr Sa  ",
+            "                1  2  ",
+            " 3  4  5  6  7  8  9  ",
+            "10 11 12 13 14 15 16  ",
+            "17 18 19 20 21 22 23  ",
+            "24 25 26 27 28 29 30  ",
+            "31                    ",
+        ];
+        assert_eq!(
+            format_month(2020, 5, false, today, &args(None, Theme::default())),
+            may
+        );
+
+        let april_hl = vec![
+            "     April 2021       ",
+            "Su Mo Tu We Th Fr Sa  ",
+            "             1  2  3  ",
+            " 4  5  6 \u{1b}[7m 7\u{1b}[0m  8  9 10  ",
+            "11 12 13 14 15 16 17  ",
+            "18 19 20 21 22 23 24  ",
+            "25 26 27 28 29 30     ",
+            "                      ",
+        ];
+        let today = NaiveDate::from_ymd_opt(2021, 4, 7).unwrap();
+        assert_eq!(
+            format_month(2021, 4, true, today, &args(None, Theme::default())),
+            april_hl
+        );
+    }
+
+    #[test]
+    fn test_format_month_theme() {
+        let theme = Theme {
+            weekend: Style::new().bold(),
+            adjacent: true,
+            ..Theme::default()
+        };
+        let today = NaiveDate::from_ymd_opt(2021, 4, 7).unwrap();
+        let april = format_month(2021, 4, false, today, &args(None, theme));
+        assert_eq!(april[0], "       April          ");
+        assert_eq!(
+            april[2],
+            "\u{1b}[2m28\u{1b}[0m \u{1b}[2m29\u{1b}[0m \u{1b}[2m30\u{1b}[0m \u{1b}[2m31\u{1b}[0m  1  2 \u{1b}[1m 3\u{1b}[0m  "
+        );
+        assert_eq!(
+            april[3],
+            "\u{1b}[1m 4\u{1b}[0m  5  6 \u{1b}[7m 7\u{1b}[0m  8  9 \u{1b}[1m10\u{1b}[0m  "
+        );
+    }
+
+    #[test]
+    fn test_day_cell_render() {
+        let theme = Theme::default();
+        let saturday = NaiveDate::from_ymd_opt(2021, 4, 3).unwrap();
+        let today = DayCell::Day {
+            date: saturday,
+            today: true,
+        };
+        assert_eq!(today.render(&theme, &[]), "\u{1b}[7m 3\u{1b}[0m");
+        assert_eq!(DayCell::Adjacent(saturday).render(&theme, &[]), "  ");
+
+        let theme = Theme {
+            today: Style::new(),
+            other: Style::new().underline(),
+            adjacent: true,
+            ..theme
+        };
+        assert_eq!(today.render(&theme, &[]), " 3");
+        assert_eq!(
+            DayCell::Adjacent(saturday).render(&theme, &[]),
+            "\u{1b}[4m 3\u{1b}[0m"
+        );
+    }
+
+    #[test]
+    fn test_day_cell_render_annotations() {
+        let glyphs = GlyphMap(HashMap::from([(ymd(2024, 12, 25), '*')]));
+        let annotations: Vec<Box<dyn DayAnnotation>> = vec![Box::new(glyphs)];
+        let theme = Theme::default();
+        let christmas = DayCell::Day {
+            date: ymd(2024, 12, 25),
+            today: false,
+        };
+        assert_eq!(christmas.render(&theme, &annotations), "25*");
+        let day = DayCell::Day {
+            date: ymd(2024, 12, 24),
+            today: true,
+        };
+        assert_eq!(day.render(&theme, &annotations), "\u{1b}[7m24\u{1b}[0m ");
+        assert_eq!(
+            DayCell::Adjacent(ymd(2024, 12, 25)).render(&theme, &annotations),
+            "   "
+        );
+    }
+
+    #[test]
+    fn test_annotation_glyph_precedence() {
+        let first = GlyphMap(HashMap::from([(ymd(2024, 1, 1), 'a')]));
+        let second = GlyphMap(HashMap::from([
+            (ymd(2024, 1, 1), 'b'),
+            (ymd(2024, 1, 2), 'c'),
+        ]));
+        let annotations: Vec<Box<dyn DayAnnotation>> = vec![Box::new(first), Box::new(second)];
+        assert_eq!(annotation_glyph(&annotations, ymd(2024, 1, 1)), Some('a'));
+        assert_eq!(annotation_glyph(&annotations, ymd(2024, 1, 2)), Some('c'));
+        assert_eq!(annotation_glyph(&annotations, ymd(2024, 1, 3)), None);
+    }
+
+    #[test]
+    fn test_parse_glyph_map() {
+        assert_eq!(
+            parse_glyph_map("# nothing\n\n").unwrap(),
+            GlyphMap::default()
+        );
+        assert_eq!(
+            parse_glyph_map("2024-12-25 *\n  2024-12-31\t\t○\n").unwrap(),
+            GlyphMap(HashMap::from([
+                (ymd(2024, 12, 25), '*'),
+                (ymd(2024, 12, 31), '○')
+            ]))
+        );
+        assert_err_str_contains!(
+            parse_glyph_map("\n2024-12-25"),
+            "line 2: expected YYYY-MM-DD GLYPH"
+        );
+        assert_err_str_contains!(
+            parse_glyph_map("2024-13-01 *"),
+            r#"line 1: invalid date "2024-13-01""#
+        );
+        assert_err_str_contains!(
+            parse_glyph_map("2024-12-25 **"),
+            r#"glyph "**" is not one column wide"#
+        );
+        assert_err_str_contains!(parse_glyph_map("2024-12-25 🎄"), "not one column wide");
+    }
+
+    #[cfg(feature = "moon")]
+    #[test]
+    fn test_moon_phase() {
+        let phases = |year, month| {
+            let first = ymd(year, month, 1);
+            (0..first.num_days_in_month())
+                .map(|day| first + Days::new(day.into()))
+                .filter_map(|date| MoonPhase.glyph(date).map(|glyph| (date.day(), glyph)))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(phases(2024, 4), [(2, '◑'), (8, '●'), (15, '◐'), (23, '○')]);
+        assert_eq!(phases(2025, 1), [(6, '◐'), (13, '○'), (21, '◑'), (29, '●')]);
+        assert_eq!(phases(1969, 7), [(6, '◑'), (14, '●'), (22, '◐'), (29, '○')]);
+    }
+
+    #[test]
+    fn test_format_month_annotated() {
+        let glyphs = GlyphMap(HashMap::from([(ymd(2020, 2, 29), '!')]));
+        let args = Args {
+            annotations: vec![Box::new(glyphs)],
+            ..args(Some(WeekNumbering::Iso), Theme::default())
+        };
+        let today = NaiveDate::from_ymd_opt(0, 1, 1).unwrap();
+        let february = format_month(2020, 2, false, today, &args);
+        assert_eq!(
+            february[..3],
+            [
+                "         February         ",
+                "   Su Mo Tu We Th Fr Sa   ",
+                " 5                    1   ",
+            ]
+        );
+        assert_eq!(february[6], " 9 23 24 25 26 27 28 29!  ");
+        assert!(
+            february
+                .iter()
+                .all(|row| row.len() == args.block_width() + 2)
+        );
+    }
+
+    #[test]
+    fn test_parse_theme() {
+        assert_ok_eq_x!(parse_theme(""), Theme::default());
+        assert_ok_eq_x!(
+            parse_theme("weekend=bold+red,today=none"),
+            Theme {
+                weekend: Style::new().bold().fg(Colour::Red),
+                today: Style::new(),
+                ..Theme::default()
+            }
+        );
+        assert_err_str_contains!(
+            parse_theme("weekend"),
+            r#"expected PART=STYLE, got "weekend""#
+        );
+        assert_err_str_contains!(parse_theme("week=bold"), r#"unknown theme part "week""#);
+        assert_err_str_contains!(parse_theme("today=loud"), r#"unknown style "loud""#);
+    }
+
+    fn ymd(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_week_numbering_iso() {
+        // 2020-12-27 is a Sunday, its Monday starts ISO week 53 of 2020
+        assert_eq!(WeekNumbering::Iso.week_of_row(ymd(2020, 12, 27)), 53);
+        // 2021-01-03: Monday 2021-01-04 starts week 1
+        assert_eq!(WeekNumbering::Iso.week_of_row(ymd(2021, 1, 3)), 1);
+        // 2024-12-29: Monday 2024-12-30 is already in week 1 of 2025
+        assert_eq!(WeekNumbering::Iso.week_of_row(ymd(2024, 12, 29)), 1);
+        // 2023-01-01: Monday 2023-01-02 starts week 1
+        assert_eq!(WeekNumbering::Iso.week_of_row(ymd(2023, 1, 1)), 1);
+        // 2022-01-02: Monday 2022-01-03 starts week 1, 2022-01-01 was week 52 of 2021
+        assert_eq!(WeekNumbering::Iso.week_of_row(ymd(2021, 12, 26)), 52);
+        assert_eq!(WeekNumbering::Iso.week_of_row(ymd(2022, 1, 2)), 1);
+    }
+
+    #[test]
+    fn test_week_numbering_sunday() {
+        // January 1st is always in week 1
+        assert_eq!(WeekNumbering::Sunday.week_of_row(ymd(2020, 12, 27)), 1);
+        assert_eq!(WeekNumbering::Sunday.week_of_row(ymd(2023, 1, 1)), 1);
+        assert_eq!(WeekNumbering::Sunday.week_of_row(ymd(2023, 1, 8)), 2);
+        assert_eq!(WeekNumbering::Sunday.week_of_row(ymd(2022, 12, 25)), 53);
+        assert_eq!(WeekNumbering::Sunday.week_of_row(ymd(2025, 12, 21)), 52);
+        assert_eq!(WeekNumbering::Sunday.week_of_row(ymd(2025, 12, 28)), 1);
+    }
+
+    #[test]
+    fn test_format_month_week_numbers() {
+        let today = NaiveDate::from_ymd_opt(0, 1, 1).unwrap();
+        let january_iso = vec![
+            "       January 2021      ",
+            "   Su Mo Tu We Th Fr Sa  ",
+            "53                 1  2  ",
+            " 1  3  4  5  6  7  8  9  ",
+            " 2 10 11 12 13 14 15 16  ",
+            " 3 17 18 19 20 21 22 23  ",
+            " 4 24 25 26 27 28 29 30  ",
+            " 5 31                    ",
+        ];
+        assert_eq!(
+            format_month(
+                2021,
+                1,
+                true,
+                today,
+                &args(Some(WeekNumbering::Iso), Theme::default())
+            ),
+            january_iso
+        );
+
+        let december_sunday = vec![
+            "         December        ",
+            "   Su Mo Tu We Th Fr Sa  ",
+            "49     1  2  3  4  5  6  ",
+            "50  7  8  9 10 11 12 13  ",
+            "51 14 15 16 17 18 19 20  ",
+            "52 21 22 23 24 25 26 27  ",
+            " 1 28 29 30 31           ",
+            "                         ",
+        ];
+        assert_eq!(
+            format_month(
+                2025,
+                12,
+                false,
+                today,
+                &args(Some(WeekNumbering::Sunday), Theme::default())
+            ),
+            december_sunday
+        );
+    }
+}
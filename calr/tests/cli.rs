@@ -222,3 +222,217 @@ fn year() -> Result<()> {
     assert_eq!(lines.len(), 37);
     Ok(())
 }
+
+// --------------------------------------------------
+#[test]
+fn test_1_2021_week_numbers() -> Result<()> {
+    run!("tests/expected/1-2021.w.txt", "-w", "-m", "1", "2021")
+}
+
+// --------------------------------------------------
+#[test]
+fn test_12_2025_week_rule_sunday() -> Result<()> {
+    run!(
+        "tests/expected/12-2025.w-sunday.txt",
+        "--week-rule",
+        "sunday",
+        "-m",
+        "12",
+        "2025"
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn test_span_across_years() -> Result<()> {
+    run!(
+        "tests/expected/2024-11_2025-02.txt",
+        "--from",
+        "2024-11",
+        "--to",
+        "2025-02"
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn test_span_within_year() -> Result<()> {
+    run!(
+        "tests/expected/2024-03_2024-04.txt",
+        "--from",
+        "2024-03",
+        "--to",
+        "2024-04"
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_span_reversed() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--from", "2025-02", "--to", "2024-11"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--from 2025-02 is after --to 2024-11",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_from_without_to() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--from", "2025-02"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--to <YYYY-MM>"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_span_and_year() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--from", "2024-11", "--to", "2025-02", "2024"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn test_2_2020_adjacent() -> Result<()> {
+    run!(
+        "tests/expected/2-2020.adjacent.txt",
+        "--color",
+        "--adjacent",
+        "-m",
+        "2",
+        "2020"
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn theme_from_env() -> Result<()> {
+    let output = cargo_bin_cmd!()
+        .env("CALR_THEME", "header=bold")
+        .args(["--color", "-m", "2", "2020"])
+        .output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.starts_with("   \u{1b}[1mFebruary 2020\u{1b}[0m      \n"));
+
+    // An explicit --theme wins over the environment
+    let output = cargo_bin_cmd!()
+        .env("CALR_THEME", "header=bold")
+        .args(["--color", "--theme", "header=none", "-m", "2", "2020"])
+        .output()?;
+    let stdout = String::from_utf8(output.stdout)?;
+    assert_eq!(stdout, fs::read_to_string("tests/expected/2-2020.txt")?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn unstyled_on_pipe() -> Result<()> {
+    for flags in [&[][..], &["--color=never"]] {
+        let output = cargo_bin_cmd!()
+            .env("CALR_THEME", "header=bold")
+            .args(flags)
+            .args(["--theme", "weekend=bold", "--adjacent", "-m", "1", "2025"])
+            .output()?;
+        assert!(output.status.success());
+        assert_not_contains!(String::from_utf8(output.stdout)?, "\u{1b}");
+    }
+    // The current month has today in it
+    let output = cargo_bin_cmd!().output()?;
+    assert_not_contains!(String::from_utf8(output.stdout)?, "\u{1b}");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_theme() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--theme", "weekend=sparkly"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(r#"unknown style "sparkly""#));
+    cargo_bin_cmd!()
+        .env("CALR_THEME", "holiday=bold")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            r#"CALR_THEME: unknown theme part "holiday""#,
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn test_12_2024_annotate() -> Result<()> {
+    run!(
+        "tests/expected/12-2024.annotate.txt",
+        "--annotate",
+        "tests/inputs/holidays.txt",
+        "-m",
+        "12",
+        "2024"
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn annotate_adjacent_days() -> Result<()> {
+    let output = cargo_bin_cmd!()
+        .args([
+            "--color",
+            "--adjacent",
+            "--annotate",
+            "tests/inputs/holidays.txt",
+        ])
+        .args(["-m", "12", "2024"])
+        .output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert_contains!(stdout, "31!\u{1b}[2m 1\u{1b}[0m*\u{1b}[2m 2\u{1b}[0m ");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_annotations() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--annotate", "tests/inputs/missing.txt"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("tests/inputs/missing.txt: "));
+    cargo_bin_cmd!()
+        .args(["--annotate", "tests/expected/2020.txt"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "tests/expected/2020.txt: line 1: expected YYYY-MM-DD GLYPH",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn not_paged_on_pipe() -> Result<()> {
+    let expected = fs::read_to_string("tests/expected/2020.txt")?;
+    for flags in [&[][..], &["--no-pager"]] {
+        cargo_bin_cmd!()
+            .env("PAGER", "sed s/^/paged:/")
+            .env("LINES", "5")
+            .args(flags)
+            .arg("2020")
+            .assert()
+            .success()
+            .stdout(expected.clone());
+    }
+    Ok(())
+}
@@ -0,0 +1,112 @@
+//! The --hex dump of whatever catr would print otherwise.
+
+use std::io::{self, Write};
+
+/// Bytes on each row of the dump, unless --width says otherwise
+pub const DEFAULT_WIDTH: usize = 16;
+
+/// Turns the bytes written to it into rows of a canonical hex dump, like
+/// ‘hexdump -C’: the offset of the row, its bytes in hex in groups of
+/// eight, and the printable ones between bars. Rows are written as soon as
+/// they are full; [`HexDump::finish`] writes the last one.
+pub struct HexDump<W: Write> {
+    out: W,
+    width: usize,
+    /// Offset of the row being filled
+    offset: u64,
+    row: Vec<u8>,
+}
+
+impl<W: Write> HexDump<W> {
+    pub fn new(out: W, width: usize) -> Self {
+        HexDump {
+            out,
+            width,
+            offset: 0,
+            row: Vec::with_capacity(width),
+        }
+    }
+
+    /// Writes what is left of the last row, then the offset where the input
+    /// ended, unless there was none.
+    pub fn finish(mut self) -> io::Result<W> {
+        if !self.row.is_empty() {
+            self.write_row()?;
+        }
+        if self.offset > 0 {
+            writeln!(self.out, "{:08x}", self.offset)?;
+        }
+        Ok(self.out)
+    }
+
+    fn write_row(&mut self) -> io::Result<()> {
+        let mut line = format!("{:08x} ", self.offset);
+        for idx in 0..self.width {
+            if idx.is_multiple_of(8) {
+                line.push(' ');
+            }
+            match self.row.get(idx) {
+                Some(byte) => line.push_str(&format!("{byte:02x} ")),
+                None => line.push_str("   "),
+            }
+        }
+        line.push_str(" |");
+        line.extend(self.row.iter().map(|&byte| match byte {
+            0x20..=0x7e => byte as char,
+            _ => '.',
+        }));
+        writeln!(self.out, "{line}|")?;
+        self.offset += self.row.len() as u64;
+        self.row.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for HexDump<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rest = buf;
+        while !rest.is_empty() {
+            let taken = rest.len().min(self.width - self.row.len());
+            self.row.extend_from_slice(&rest[..taken]);
+            rest = &rest[taken..];
+            if self.row.len() == self.width {
+                self.write_row()?;
+            }
+        }
+        Ok(buf.len())
+    }
+
+    /// Flushes the rows written so far; a partial row waits for more bytes.
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dump(input: &[u8], width: usize) -> String {
+        let mut dump = HexDump::new(vec![], width);
+        // Rows don't depend on how the bytes arrive
+        for chunk in input.chunks(5) {
+            dump.write_all(chunk).unwrap();
+        }
+        String::from_utf8(dump.finish().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_hex_dump() {
+        assert_eq!(
+            dump(b"hello, world\n\x00\xff\x7fsixteen more", 16),
+            "00000000  68 65 6c 6c 6f 2c 20 77  6f 72 6c 64 0a 00 ff 7f  |hello, world....|\n\
+             00000010  73 69 78 74 65 65 6e 20  6d 6f 72 65              |sixteen more|\n\
+             0000001c\n"
+        );
+        assert_eq!(
+            dump(b"abcde", 4),
+            "00000000  61 62 63 64  |abcd|\n00000004  65           |e|\n00000005\n"
+        );
+        assert_eq!(dump(b"", 16), "");
+    }
+}
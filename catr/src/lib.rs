@@ -0,0 +1,528 @@
+use anyhow::{Context, Result, anyhow};
+use clap::{ArgGroup, CommandFactory, Parser, ValueEnum, error::ErrorKind};
+use learnr::Compression;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, IsTerminal, Read, StdoutLock, Write};
+use unicode_width::UnicodeWidthChar;
+use walkdir::WalkDir;
+
+mod hex;
+
+use hex::HexDump;
+
+#[derive(Debug, Parser)]
+#[command(author, version, about)]
+#[command(group(ArgGroup::new("numbering").args(["number_lines", "number_nonblank_lines"])))]
+#[command(group(ArgGroup::new("layout").args(["side_by_side", "hex"])))]
+/// Rust version of cat ‘cat’
+struct Args {
+    /// Input file(s)
+    #[arg(value_name = "FILE", default_value = "-")]
+    files: Vec<String>,
+
+    /// Number lines
+    #[arg(short('n'), long("number"), conflicts_with("number_nonblank_lines"))]
+    number_lines: bool,
+
+    /// Number non-blank lines
+    #[arg(short('b'), long("number-nonblank"))]
+    number_nonblank_lines: bool,
+
+    /// Number the first line N
+    #[arg(long, value_name = "N", default_value_t = 1, requires = "numbering")]
+    number_start: u64,
+
+    /// Add K to the line number for each numbered line
+    #[arg(long, value_name = "K", default_value_t = 1, requires = "numbering")]
+    number_step: u64,
+
+    /// Write line numbers in RADIX
+    #[arg(
+        long,
+        value_name = "RADIX",
+        value_enum,
+        default_value_t = Radix::Dec,
+        requires = "numbering"
+    )]
+    number_radix: Radix,
+
+    /// Concatenate all regular files below directories, sorted by name
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Equivalent to -vET
+    #[arg(short('A'), long)]
+    show_all: bool,
+
+    /// Equivalent to -vE
+    #[arg(short('e'))]
+    show_ends_nonprinting: bool,
+
+    /// Display $ at end of each line
+    #[arg(short('E'), long)]
+    show_ends: bool,
+
+    /// Equivalent to -vT
+    #[arg(short('t'))]
+    show_tabs_nonprinting: bool,
+
+    /// Display TAB characters as ^I
+    #[arg(short('T'), long)]
+    show_tabs: bool,
+
+    /// Use ^ and M- notation, except for LFD and TAB
+    #[arg(short('v'), long)]
+    show_nonprinting: bool,
+
+    /// Write output as soon as it is read instead of buffering it
+    #[arg(short('u'), long)]
+    unbuffered: bool,
+
+    /// Decompress gzip and zstd input whatever its name (.gz and .zst files
+    /// are always decompressed)
+    #[arg(short('z'), long)]
+    decompress: bool,
+
+    /// Print exactly two files next to each other, line by line, cutting
+    /// lines that don't fit in their column
+    #[arg(
+        long,
+        conflicts_with_all = ["numbering", "recursive", "show_all", "show_ends_nonprinting",
+            "show_ends", "show_tabs_nonprinting", "show_tabs", "show_nonprinting"]
+    )]
+    side_by_side: bool,
+
+    /// Print a hex dump of the output instead, with offsets and the
+    /// printable bytes next to their hex values
+    #[arg(
+        long,
+        conflicts_with_all = ["numbering", "show_all", "show_ends_nonprinting", "show_ends",
+            "show_tabs_nonprinting", "show_tabs", "show_nonprinting"]
+    )]
+    hex: bool,
+
+    /// Total width of the --side-by-side output [default: the terminal
+    /// width], or bytes on each row of the --hex dump [default: 16]
+    #[arg(
+        short('W'),
+        long,
+        value_name = "COLUMNS",
+        requires = "layout",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    width: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Radix {
+    Dec,
+    Hex,
+    Oct,
+}
+
+impl Radix {
+    /// Right-aligns `num` in the same six columns GNU cat uses.
+    fn format(self, num: u64) -> String {
+        match self {
+            Radix::Dec => format!("{num:6}"),
+            Radix::Hex => format!("{num:6x}"),
+            Radix::Oct => format!("{num:6o}"),
+        }
+    }
+}
+
+/// How bytes are made visible on output, as selected by -A/-E/-T/-v.
+#[derive(Debug, Default, Clone, Copy)]
+struct Show {
+    ends: bool,
+    tabs: bool,
+    nonprinting: bool,
+}
+
+impl Show {
+    fn from_args(args: &Args) -> Self {
+        Show {
+            ends: args.show_all || args.show_ends_nonprinting || args.show_ends,
+            tabs: args.show_all || args.show_tabs_nonprinting || args.show_tabs,
+            nonprinting: args.show_all
+                || args.show_ends_nonprinting
+                || args.show_tabs_nonprinting
+                || args.show_nonprinting,
+        }
+    }
+
+    fn is_plain(self) -> bool {
+        !(self.ends || self.tabs || self.nonprinting)
+    }
+
+    /// Writes `line` (including its terminator, if any) transformed byte by byte.
+    fn write_line(self, out: &mut impl Write, line: &[u8]) -> io::Result<()> {
+        if self.is_plain() {
+            return out.write_all(line);
+        }
+        let (mut body, newline) = match line.strip_suffix(b"\n") {
+            Some(body) => (body, true),
+            None => (line, false),
+        };
+        // Like GNU cat, -E marks a CRLF ending as ^M$ even without -v
+        let mut crlf = false;
+        if self.ends
+            && newline
+            && !self.nonprinting
+            && let Some(stripped) = body.strip_suffix(b"\r")
+        {
+            body = stripped;
+            crlf = true;
+        }
+        for &byte in body {
+            match byte {
+                b'\t' if self.tabs => out.write_all(b"^I")?,
+                b'\t' => out.write_all(b"\t")?,
+                _ if self.nonprinting => write_nonprinting(out, byte)?,
+                _ => out.write_all(&[byte])?,
+            }
+        }
+        if crlf {
+            out.write_all(b"^M")?;
+        }
+        if self.ends && newline {
+            out.write_all(b"$")?;
+        }
+        if newline {
+            out.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes `byte` in GNU cat’s caret and M- notation.
+fn write_nonprinting(out: &mut impl Write, byte: u8) -> io::Result<()> {
+    let low = if byte >= 0x80 {
+        out.write_all(b"M-")?;
+        byte - 0x80
+    } else {
+        byte
+    };
+    match low {
+        0..0x20 => out.write_all(&[b'^', low + 0x40]),
+        0x7f => out.write_all(b"^?"),
+        _ => out.write_all(&[low]),
+    }
+}
+
+/// Buffer size for reading and writing, the same as GNU cat’s
+const BUF_SIZE: usize = 128 * 1024;
+
+/// How output reaches stdout: in large blocks when writing to a file or
+/// pipe, per line on a terminal, and immediately with -u.
+enum Output<'a> {
+    Buffered(BufWriter<StdoutLock<'a>>),
+    // StdoutLock itself flushes on every newline
+    LineBuffered(StdoutLock<'a>),
+    Unbuffered(StdoutLock<'a>),
+}
+
+impl Output<'_> {
+    fn new(unbuffered: bool) -> Self {
+        let stdout = io::stdout();
+        if unbuffered {
+            Output::Unbuffered(stdout.lock())
+        } else if stdout.is_terminal() {
+            Output::LineBuffered(stdout.lock())
+        } else {
+            Output::Buffered(BufWriter::with_capacity(BUF_SIZE, stdout.lock()))
+        }
+    }
+}
+
+impl Write for Output<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Buffered(out) => out.write(buf),
+            Output::LineBuffered(out) => out.write(buf),
+            Output::Unbuffered(out) => {
+                let written = out.write(buf)?;
+                out.flush()?;
+                Ok(written)
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Buffered(out) => out.flush(),
+            Output::LineBuffered(out) | Output::Unbuffered(out) => out.flush(),
+        }
+    }
+}
+
+fn run(args: Args) -> Result<()> {
+    let mut out = Output::new(args.unbuffered);
+    if args.side_by_side {
+        let width = args
+            .width
+            .map_or_else(learnr::terminal_width, |width| width as usize);
+        let [left, right] = [&args.files[0], &args.files[1]].map(|filename| {
+            open(filename)
+                .and_then(|file| decompressed(file, filename, args.decompress))
+                .map_err(|err| anyhow!("Failed to open {filename}: {err}"))
+        });
+        print_side_by_side(left?, right?, width, &mut out)?;
+    } else if args.hex {
+        let width = args
+            .width
+            .map_or(hex::DEFAULT_WIDTH, |width| width as usize);
+        let mut dump = HexDump::new(&mut out, width);
+        print_files(&mut dump, &args)?;
+        dump.finish().context("write error")?;
+    } else {
+        print_files(&mut out, &args)?;
+    }
+    out.flush().context("write error")?;
+    Ok(())
+}
+
+/// An input that remembers whether reading it failed, so that such errors
+/// can be told apart from failed writes: a bad input is only reported, while
+/// a failed write ends the whole run.
+struct Input<R> {
+    inner: R,
+    failed: bool,
+}
+
+impl<R: BufRead> Read for Input<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf);
+        self.failed |= read.is_err();
+        read
+    }
+}
+
+impl<R: BufRead> BufRead for Input<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        let filled = self.inner.fill_buf();
+        self.failed |= filled.is_err();
+        filled
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.inner.consume(amount)
+    }
+}
+
+/// Prints every file of `args` in turn, reporting those that can't be read.
+/// A failed write is the only error, as it ends the run.
+fn print_files(out: &mut impl Write, args: &Args) -> Result<()> {
+    for entry in find_files(&args.files, args.recursive) {
+        let filename = match entry {
+            Err(err) => {
+                eprintln!("catr: {err}");
+                continue;
+            }
+            Ok(filename) => filename,
+        };
+        match open(&filename).and_then(|file| decompressed(file, &filename, args.decompress)) {
+            Err(err) => {
+                eprintln!("Failed to open {filename}: {err}");
+            }
+            Ok(file) => {
+                let mut input = Input {
+                    inner: file,
+                    failed: false,
+                };
+                match print_file(&mut input, out, args) {
+                    Err(err) if input.failed => eprintln!("{filename}: {err}"),
+                    Err(err) => return Err(err.context("write error")),
+                    Ok(()) => (),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Expands directories into the regular files below them when `recursive`
+/// is set, and reports them as errors otherwise.
+fn find_files(files: &[String], recursive: bool) -> Vec<Result<String>> {
+    let mut result = vec![];
+    for filename in files {
+        let is_dir = filename != "-" && fs::metadata(filename).is_ok_and(|m| m.is_dir());
+        if !is_dir {
+            result.push(Ok(filename.clone()));
+        } else if !recursive {
+            result.push(Err(anyhow!("{filename}: Is a directory")));
+        } else {
+            for dent in WalkDir::new(filename).sort_by_file_name() {
+                match dent {
+                    Err(err) => result.push(Err(anyhow!(err))),
+                    Ok(dent) if dent.file_type().is_file() => {
+                        result.push(Ok(dent.path().to_string_lossy().into_owned()))
+                    }
+                    Ok(_) => (),
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Copies `file` to `out` line by line, keeping the original bytes and line
+/// terminators so that non-UTF-8 input and a missing final newline survive.
+/// Without numbering or display options the bytes are copied in bulk.
+fn print_file(file: &mut impl BufRead, out: &mut impl Write, args: &Args) -> Result<()> {
+    let show = Show::from_args(args);
+    if show.is_plain() && !args.number_lines && !args.number_nonblank_lines {
+        io::copy(file, out)?;
+        return Ok(());
+    }
+    let mut ctr = args.number_start;
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if file.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        let blank = line == b"\n";
+        if args.number_lines || (args.number_nonblank_lines && !blank) {
+            write!(out, "{}\t", args.number_radix.format(ctr))?;
+            ctr = ctr.saturating_add(args.number_step);
+        }
+        show.write_line(out, &line)?;
+    }
+    Ok(())
+}
+
+/// Separates the two columns of --side-by-side
+const GUTTER: &str = " | ";
+
+/// Prints the lines of `left` and `right` in two columns that share `width`
+/// with the gutter, until both files end. Tabs are expanded so that the
+/// gutter stays straight, and a file that ends first leaves its column blank.
+fn print_side_by_side(
+    mut left: Box<dyn BufRead>,
+    mut right: Box<dyn BufRead>,
+    width: usize,
+    out: &mut impl Write,
+) -> Result<()> {
+    let column = width.saturating_sub(GUTTER.len()) / 2;
+    let mut left_line = Vec::new();
+    let mut right_line = Vec::new();
+    loop {
+        left_line.clear();
+        right_line.clear();
+        let left_read = left.read_until(b'\n', &mut left_line)?;
+        let right_read = right.read_until(b'\n', &mut right_line)?;
+        if left_read == 0 && right_read == 0 {
+            break;
+        }
+        let row = format!(
+            "{}{GUTTER}{}",
+            fit_column(&left_line, column),
+            fit_column(&right_line, column)
+        );
+        writeln!(out, "{}", row.trim_end())?;
+    }
+    Ok(())
+}
+
+/// Lays out `line`, without its terminator, in exactly `column` display
+/// columns: cut if it is wider, padded with spaces if it is narrower.
+fn fit_column(line: &[u8], column: usize) -> String {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    let mut cell = String::new();
+    let mut width = 0;
+    for ch in String::from_utf8_lossy(line).chars() {
+        if ch == '\t' {
+            let stop = (width / 8 + 1) * 8;
+            if stop > column {
+                break;
+            }
+            cell.extend(std::iter::repeat_n(' ', stop - width));
+            width = stop;
+            continue;
+        }
+        let ch_width = ch.width().unwrap_or(0);
+        if width + ch_width > column {
+            break;
+        }
+        cell.push(ch);
+        width += ch_width;
+    }
+    cell.extend(std::iter::repeat_n(' ', column - width));
+    cell
+}
+
+/// The command line that [`main`] parses, for generating the man page.
+pub fn command() -> clap::Command {
+    Args::command()
+}
+
+/// What the man page tells beyond --help.
+pub const MAN_DESCRIPTION: &str = "\
+Each FILE is copied to standard output in turn, or standard input when FILE is \
+‘-’ or there are none. Bytes are copied as they are, so input that isn't UTF-8 \
+and a missing final newline come out unchanged unless a display option such as \
+-v asks otherwise.\n\n\
+Files ending in ‘.gz’ or ‘.zst’ are decompressed, and so is any input that \
+starts like a gzip or zstd stream with -z. A file that can't be read is \
+reported and skipped, and the others are still printed. A failed write to \
+standard output stops everything at once, with an exit status of 1.\n\n\
+With --hex, everything that would be printed is shown as a hex dump instead, \
+the way ‘hexdump -C’ shows it: the offset of each row, its bytes in hex, and \
+the printable ones as text with a dot for the others. Offsets run on from one \
+FILE to the next, as the files are concatenated first.";
+
+pub fn main(args: impl IntoIterator<Item = OsString>) {
+    let args = Args::parse_from(args);
+    if args.side_by_side && args.files.len() != 2 {
+        Args::command()
+            .error(
+                ErrorKind::WrongNumberOfValues,
+                "--side-by-side needs exactly two files",
+            )
+            .exit();
+    }
+    if args.side_by_side
+        && let Some(width) = args.width
+        && width < GUTTER.len() as u64 + 2
+    {
+        Args::command()
+            .error(
+                ErrorKind::ValueValidation,
+                format!(
+                    "--side-by-side needs a width of at least {}",
+                    GUTTER.len() + 2
+                ),
+            )
+            .exit();
+    }
+    if let Err(e) = run(args) {
+        learnr::exit_on_broken_pipe(&e);
+        eprintln!("catr: {e:#}");
+        std::process::exit(1);
+    }
+}
+
+/// Decompresses files named ‘*.gz’ or ‘*.zst’, and with `force` any input
+/// that starts with a gzip or zstd magic number.
+fn decompressed(file: Box<dyn BufRead>, filename: &str, force: bool) -> Result<Box<dyn BufRead>> {
+    if force {
+        learnr::auto_decompress(file)
+    } else if let Some(compression) = Compression::from_path(filename) {
+        learnr::decompress(file, compression)
+    } else {
+        Ok(file)
+    }
+}
+
+fn open(filename: &str) -> Result<Box<dyn BufRead>> {
+    match filename {
+        "-" => Ok(Box::new(BufReader::with_capacity(BUF_SIZE, io::stdin()))),
+        _ => Ok(Box::new(BufReader::with_capacity(
+            BUF_SIZE,
+            File::open(filename)?,
+        ))),
+    }
+}
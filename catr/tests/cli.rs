@@ -9,6 +9,11 @@ const EMPTY: &str = "tests/inputs/empty.txt";
 const FOX: &str = "tests/inputs/fox.txt";
 const SPIDERS: &str = "tests/inputs/spiders.txt";
 const BUSTLE: &str = "tests/inputs/the-bustle.txt";
+const NO_NEWLINE: &str = "tests/inputs/no-newline.txt";
+const CONFD: &str = "tests/confd";
+const NONPRINTING: &str = "tests/inputs/nonprinting.txt";
+const FOX_GZ: &str = "tests/inputs/fox.txt.gz";
+const BUSTLE_ZST: &str = "tests/inputs/the-bustle.txt.zst";
 
 // --------------------------------------------------
 #[test]
@@ -62,6 +67,17 @@ fn run(args: &[&str], expected_file: &str) -> Result<()> {
     Ok(())
 }
 
+// --------------------------------------------------
+fn run_bytes(args: &[&str], expected_file: &str) -> Result<()> {
+    let expected = fs::read(expected_file)?;
+    cargo_bin_cmd!()
+        .args(args)
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
 // --------------------------------------------------
 fn run_stdin(input_file: &str, args: &[&str], expected_file: &str) -> Result<()> {
     let input = fs::read_to_string(input_file)?;
@@ -196,3 +212,405 @@ fn all_n() -> Result<()> {
 fn all_b() -> Result<()> {
     run(&[FOX, SPIDERS, BUSTLE, "-b"], "tests/expected/all.b.out")
 }
+
+// --------------------------------------------------
+#[test]
+fn no_final_newline_b() -> Result<()> {
+    run(&["-b", NO_NEWLINE], "tests/expected/no-newline.txt.b.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn non_utf8_stdin() -> Result<()> {
+    let input: &[u8] = b"caf\xe9\n\xff\xfe\n";
+    cargo_bin_cmd!()
+        .arg("-n")
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(&b"     1\tcaf\xe9\n     2\t\xff\xfe\n"[..]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn continues_after_bad_file() -> Result<()> {
+    let bad = gen_bad_file();
+    let expected = fs::read_to_string("tests/expected/fox.txt.out")?;
+    cargo_bin_cmd!()
+        .args([&bad, FOX])
+        .assert()
+        .success()
+        .stdout(expected)
+        .stderr(predicate::str::contains(bad));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_on_write_error() -> Result<()> {
+    let full = fs::File::create("/dev/full")?;
+    for args in [&[FOX][..], &["-u", FOX], &["-n", FOX], &["--hex", FOX]] {
+        let output = std::process::Command::new(assert_cmd::cargo::cargo_bin!())
+            .args(args)
+            .stdout(full.try_clone()?)
+            .output()?;
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(
+            String::from_utf8(output.stderr)?,
+            "catr: write error: No space left on device (os error 28)\n"
+        );
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn skips_dir() -> Result<()> {
+    let expected = fs::read_to_string("tests/expected/fox.txt.out")?;
+    cargo_bin_cmd!()
+        .args([CONFD, FOX])
+        .assert()
+        .success()
+        .stdout(expected)
+        .stderr("catr: tests/confd: Is a directory\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn recursive() -> Result<()> {
+    run(&["-r", CONFD], "tests/expected/confd.r.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn recursive_n() -> Result<()> {
+    run(
+        &["--recursive", "-n", CONFD],
+        "tests/expected/confd.r.n.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_show_all() -> Result<()> {
+    run_bytes(&["-A", NONPRINTING], "tests/expected/nonprinting.txt.A.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_show_all_combined() -> Result<()> {
+    run_bytes(
+        &["-vET", NONPRINTING],
+        "tests/expected/nonprinting.txt.A.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_show_ends() -> Result<()> {
+    run_bytes(
+        &["--show-ends", NONPRINTING],
+        "tests/expected/nonprinting.txt.E.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_show_tabs() -> Result<()> {
+    run_bytes(&["-T", NONPRINTING], "tests/expected/nonprinting.txt.T.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_show_nonprinting() -> Result<()> {
+    run_bytes(&["-v", NONPRINTING], "tests/expected/nonprinting.txt.v.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_n() -> Result<()> {
+    run_bytes(&["-n", NONPRINTING], "tests/expected/nonprinting.txt.n.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn gzip() -> Result<()> {
+    run(&[FOX_GZ], "tests/expected/fox.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn gzip_multi_member_n() -> Result<()> {
+    run(
+        &["-n", "tests/inputs/fox-spiders.gz"],
+        "tests/expected/fox-spiders.gz.n.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn zstd() -> Result<()> {
+    run(&[BUSTLE_ZST], "tests/expected/the-bustle.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn decompress_stdin() -> Result<()> {
+    let input = fs::read(BUSTLE_ZST)?;
+    let expected = fs::read_to_string("tests/expected/the-bustle.txt.stdin.out")?;
+    cargo_bin_cmd!()
+        .args(["--decompress", "-"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn decompress_plain_passthrough() -> Result<()> {
+    run(&["-z", FOX], "tests/expected/fox.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn compressed_stdin_untouched() -> Result<()> {
+    let input = fs::read(FOX_GZ)?;
+    cargo_bin_cmd!()
+        .write_stdin(input.clone())
+        .assert()
+        .success()
+        .stdout(input);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn binary_stdin_identical() -> Result<()> {
+    // Several buffers worth of every byte value, without a final newline
+    let input: Vec<u8> = (0..=255u8).cycle().take(3 * 128 * 1024 + 17).collect();
+    cargo_bin_cmd!()
+        .write_stdin(input.clone())
+        .assert()
+        .success()
+        .stdout(input);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn binary_files_identical() -> Result<()> {
+    let mut expected = fs::read(FOX_GZ)?;
+    expected.extend(fs::read(NONPRINTING)?);
+    cargo_bin_cmd!()
+        .args(["-", NONPRINTING])
+        .write_stdin(fs::read(FOX_GZ)?)
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn all_unbuffered() -> Result<()> {
+    run(&["-u", FOX, SPIDERS, BUSTLE], "tests/expected/all.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn all_unbuffered_n() -> Result<()> {
+    run(
+        &["--unbuffered", "-n", FOX, SPIDERS, BUSTLE],
+        "tests/expected/all.n.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_stdin_unbuffered_b() -> Result<()> {
+    run_stdin(
+        BUSTLE,
+        &["-u", "-b"],
+        "tests/expected/the-bustle.txt.b.stdin.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_n_start_step_oct() -> Result<()> {
+    run(
+        &[
+            "-n",
+            "--number-start",
+            "8",
+            "--number-step",
+            "8",
+            "--number-radix",
+            "oct",
+            BUSTLE,
+        ],
+        "tests/expected/the-bustle.txt.n.oct.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bustle_b_start_step_hex() -> Result<()> {
+    run(
+        &[
+            "-b",
+            "--number-start=240",
+            "--number-step=16",
+            "--number-radix=hex",
+            BUSTLE,
+        ],
+        "tests/expected/the-bustle.txt.b.hex.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_number_start_without_numbering() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--number-start", "5", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "the following required arguments were not provided",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn side_by_side() -> Result<()> {
+    run(
+        &["--side-by-side", "--width", "30", BUSTLE, SPIDERS],
+        "tests/expected/the-bustle.txt.spiders.txt.side-by-side.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn side_by_side_terminal_width() -> Result<()> {
+    cargo_bin_cmd!()
+        .env("COLUMNS", "21")
+        .args(["--side-by-side", FOX, SPIDERS])
+        .assert()
+        .success()
+        .stdout("The quick | Don't wor\n          | I keep ho\n          | casually.\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn side_by_side_expands_tabs() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--side-by-side", "-W", "23", "-", FOX])
+        .write_stdin("a\tb\n\t\tcut\n")
+        .assert()
+        .success()
+        .stdout("a       b  | The quick\n           |\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_side_by_side_one_file() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--side-by-side", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "--side-by-side needs exactly two files",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_side_by_side_numbered() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--side-by-side", "-n", FOX, SPIDERS])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "the argument '--side-by-side' cannot be used with",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn hex() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--hex", FOX])
+        .assert()
+        .success()
+        .stdout(
+            "00000000  54 68 65 20 71 75 69 63  6b 20 62 72 6f 77 6e 20  |The quick brown |\n\
+             00000010  66 6f 78 20 6a 75 6d 70  73 20 6f 76 65 72 20 74  |fox jumps over t|\n\
+             00000020  68 65 20 6c 61 7a 79 20  64 6f 67 2e 0a           |he lazy dog..|\n\
+             0000002d\n",
+        );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn hex_width_across_files() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--hex", "-W", "8", "-", FOX])
+        .write_stdin("ab\x01")
+        .assert()
+        .success()
+        .stdout(
+            "00000000  61 62 01 54 68 65 20 71  |ab.The q|\n\
+             00000008  75 69 63 6b 20 62 72 6f  |uick bro|\n\
+             00000010  77 6e 20 66 6f 78 20 6a  |wn fox j|\n\
+             00000018  75 6d 70 73 20 6f 76 65  |umps ove|\n\
+             00000020  72 20 74 68 65 20 6c 61  |r the la|\n\
+             00000028  7a 79 20 64 6f 67 2e 0a  |zy dog..|\n\
+             00000030\n",
+        );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn hex_decompressed() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--hex", FOX_GZ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("|The quick brown |"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_hex_numbered() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--hex", "-n", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "the argument '--hex' cannot be used with",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_side_by_side_narrow() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--side-by-side", "-W", "4", FOX, SPIDERS])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("at least 5"));
+    Ok(())
+}
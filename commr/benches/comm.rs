@@ -0,0 +1,74 @@
+//! Compares commr with GNU comm on generated sorted inputs.
+//!
+//! Inputs are 16M per file by default, set e.g. `COMMR_BENCH_SIZE=1G` for
+//! the full-size run: `COMMR_BENCH_SIZE=1G cargo bench -p commr`.
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+
+const LINE_LEN: u64 = 17;
+
+fn generate(path: &Path, size: u64, keep: impl Fn(u64) -> bool) {
+    let mut out = BufWriter::new(File::create(path).expect("create bench input"));
+    let mut written = 0;
+    let mut i = 0;
+    while written < size {
+        if keep(i) {
+            writeln!(out, "{i:016}").expect("write bench input");
+            written += LINE_LEN;
+        }
+        i += 1;
+    }
+    out.flush().expect("flush bench input");
+}
+
+fn run_comm(cmd: &mut Command) {
+    let status = cmd
+        .stdout(Stdio::null())
+        .env("LC_ALL", "C")
+        .status()
+        .expect("run comm");
+    assert!(status.success());
+}
+
+fn bench_comm(c: &mut Criterion) {
+    let size = std::env::var("COMMR_BENCH_SIZE")
+        .map(|s| learnr::parse_size(&s).expect("COMMR_BENCH_SIZE"))
+        .unwrap_or(16 << 20);
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    let file1 = dir.path().join("file1.txt");
+    let file2 = dir.path().join("file2.txt");
+    generate(&file1, size, |i| i % 3 != 0);
+    generate(&file2, size, |i| i % 2 == 0);
+
+    let mut group = c.benchmark_group("comm");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(2 * size));
+
+    group.bench_function("commr", |b| {
+        b.iter(|| run_comm(Command::new(env!("CARGO_BIN_EXE_commr")).args([&file1, &file2])))
+    });
+
+    let have_gnu_comm = Command::new("comm")
+        .arg("--version")
+        .stdout(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success());
+    if have_gnu_comm {
+        group.bench_function("GNU comm", |b| {
+            b.iter(|| run_comm(Command::new("comm").args([&file1, &file2])))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_comm);
+criterion_main!(benches);
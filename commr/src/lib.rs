@@ -0,0 +1,200 @@
+use anyhow::{Result, bail};
+use clap::{CommandFactory, Parser};
+use learnr::{CLIInput, open};
+use regex::bytes::Regex;
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    ffi::OsString,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+};
+
+/// ’comm’ in Rust
+#[derive(Debug, Parser)]
+#[command(about, version, author)]
+pub struct Args {
+    #[arg(value_name = "FILE1")]
+    file1: CLIInput,
+
+    #[arg(value_name = "FILE2")]
+    file2: CLIInput,
+
+    /// suppress column 1 (lines unique to FILE1)
+    #[arg(short('1'), action=clap::ArgAction::SetFalse)]
+    show_col1: bool,
+
+    /// suppress column 2 (lines unique to FILE2)
+    #[arg(short('2'), action=clap::ArgAction::SetFalse)]
+    show_col2: bool,
+
+    /// suppress column 3 (lines that appear in both files)
+    #[arg(short('3'), action=clap::ArgAction::SetFalse)]
+    show_col3: bool,
+
+    /// compare ignoring case
+    #[arg(short('i'))]
+    insensitive: bool,
+
+    /// avoid comparing the first N fields
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    skip_fields: usize,
+
+    /// avoid comparing the first N characters, after any skipped fields
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    skip_chars: usize,
+
+    /// remove everything matching PATTERN from both lines before comparing,
+    /// e.g. timestamps or ids
+    #[arg(long, value_name = "PATTERN")]
+    ignore_regex: Option<Regex>,
+
+    /// separate columns with STR
+    #[arg(
+        short('d'),
+        long("output-delimiter"),
+        default_value = "\t",
+        value_name = "STR"
+    )]
+    delimiter: String,
+
+    /// size of the read buffer for each input and of the output buffer
+    #[arg(long, value_name = "SIZE", default_value = "64K", value_parser = parse_buffer_size)]
+    buffer_size: usize,
+}
+
+/// The command line that [`main`] parses, for generating the man page.
+pub fn command() -> clap::Command {
+    Args::command()
+}
+
+/// What the man page tells beyond --help.
+pub const MAN_DESCRIPTION: &str = "\
+Both inputs must be sorted, and ‘-’ stands for standard input. The output has \
+three columns, of the lines only in FILE1, those only in FILE2 and those in \
+both, each indented by one more delimiter than the previous; -1, -2 and -3 \
+suppress them.\n\n\
+Only the part of each line left by --skip-fields, --skip-chars and \
+--ignore-regex is compared, but lines are always printed whole.\n\n\
+Memory use doesn't grow with the length of the inputs, but it isn't constant \
+either: it is bounded by the buffers of --buffer-size plus the longest line \
+of each input. A line is held whole while it is compared, since the lesser \
+of two lines is printed only once their difference is found.";
+
+pub fn main(args: impl IntoIterator<Item = OsString>) -> Result<()> {
+    let args = Args::parse_from(args);
+    if args.file1 == CLIInput::StdIn && args.file2 == CLIInput::StdIn {
+        bail!(r#"Both input files cannot be STDIN ("-")"#);
+    }
+    // Reads this large go past the smaller buffer of `open`, straight to
+    // the file
+    let mut fh1 = BufReader::with_capacity(args.buffer_size, open(&args.file1)?);
+    let mut fh2 = BufReader::with_capacity(args.buffer_size, open(&args.file2)?);
+    let mut out = BufWriter::with_capacity(args.buffer_size, io::stdout().lock());
+
+    // Memory use is bounded by the buffers plus the longest line of each
+    // input, not constant: both lines are read into the same two allocations
+    // over and over, and each must be whole to be printed after the compare.
+    let mut line1 = Vec::new();
+    let mut line2 = Vec::new();
+    let mut has_line1 = read_line(&mut fh1, &mut line1)?;
+    let mut has_line2 = read_line(&mut fh2, &mut line2)?;
+
+    let c2_prefix = if args.show_col1 {
+        args.delimiter.clone()
+    } else {
+        String::new()
+    };
+    let c3_prefix = if args.show_col2 {
+        c2_prefix.clone() + &args.delimiter
+    } else {
+        c2_prefix.clone()
+    };
+
+    loop {
+        let ord = match (has_line1, has_line2) {
+            (false, false) => break,
+            (true, true) => compare(&key(&line1, &args), &key(&line2, &args), args.insensitive),
+
+            // EOF is always the biggest
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+        };
+
+        // l1 ? l2
+        match ord {
+            Ordering::Less => {
+                if args.show_col1 {
+                    write_line(&mut out, "", &line1)?;
+                }
+                has_line1 = read_line(&mut fh1, &mut line1)?;
+            }
+            Ordering::Greater => {
+                if args.show_col2 {
+                    write_line(&mut out, &c2_prefix, &line2)?;
+                }
+                has_line2 = read_line(&mut fh2, &mut line2)?;
+            }
+            Ordering::Equal => {
+                if args.show_col3 {
+                    write_line(&mut out, &c3_prefix, &line1)?;
+                }
+                has_line1 = read_line(&mut fh1, &mut line1)?;
+                has_line2 = read_line(&mut fh2, &mut line2)?;
+            }
+        }
+    }
+
+    out.flush()?;
+    Ok(())
+}
+
+/// Reads the next line without its terminator into `buf`, reusing its allocation.
+/// Returns `false` at EOF.
+fn read_line(fh: &mut impl BufRead, buf: &mut Vec<u8>) -> Result<bool> {
+    buf.clear();
+    if fh.read_until(b'\n', buf)? == 0 {
+        return Ok(false);
+    }
+    if buf.last() == Some(&b'\n') {
+        buf.pop();
+        if buf.last() == Some(&b'\r') {
+            buf.pop();
+        }
+    }
+    Ok(true)
+}
+
+/// The part of a line that is compared; the whole line is still printed.
+/// Both inputs go through this same function, so they are always compared
+/// on equal terms.
+fn key<'a>(line: &'a [u8], args: &Args) -> Cow<'a, [u8]> {
+    let key = &line[learnr::key_start(line, args.skip_fields, args.skip_chars)..];
+    match &args.ignore_regex {
+        Some(regex) => regex.replace_all(key, b""),
+        None => Cow::Borrowed(key),
+    }
+}
+
+fn compare(line1: &[u8], line2: &[u8], insensitive: bool) -> Ordering {
+    if insensitive {
+        String::from_utf8_lossy(line1)
+            .to_lowercase()
+            .cmp(&String::from_utf8_lossy(line2).to_lowercase())
+    } else {
+        line1.cmp(line2)
+    }
+}
+
+fn write_line(out: &mut impl Write, prefix: &str, line: &[u8]) -> Result<()> {
+    out.write_all(prefix.as_bytes())?;
+    out.write_all(line)?;
+    out.write_all(b"\n")?;
+    Ok(())
+}
+
+fn parse_buffer_size(arg: &str) -> Result<usize> {
+    match learnr::parse_size(arg)? {
+        0 => bail!("buffer size must be positive"),
+        size => Ok(usize::try_from(size)?),
+    }
+}
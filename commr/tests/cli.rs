@@ -9,6 +9,10 @@ const EMPTY: &str = "tests/inputs/empty.txt";
 const FILE1: &str = "tests/inputs/file1.txt";
 const FILE2: &str = "tests/inputs/file2.txt";
 const BLANK: &str = "tests/inputs/blank.txt";
+const LOG1: &str = "tests/inputs/log1.txt";
+const LOG2: &str = "tests/inputs/log2.txt";
+const IDS1: &str = "tests/inputs/ids1.txt";
+const IDS2: &str = "tests/inputs/ids2.txt";
 
 // --------------------------------------------------
 #[test]
@@ -402,3 +406,145 @@ fn file1_file2_123_delim() -> Result<()> {
 fn blank_file1() -> Result<()> {
     run!("tests/expected/blank_file1.out", BLANK, FILE1)
 }
+
+// --------------------------------------------------
+#[test]
+fn tiny_buffer_size() -> Result<()> {
+    run!(
+        "tests/expected/file1_file2.out",
+        FILE1,
+        FILE2,
+        "--buffer-size",
+        "1"
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn long_lines() -> Result<()> {
+    // Lines much longer than the buffers must still compare as a whole
+    let long_a = "a".repeat(100_000);
+    let long_b = format!("{long_a}b");
+    let input = format!("{long_a}\n{long_b}\n");
+
+    let output = cargo_bin_cmd!()
+        .args(["--buffer-size", "1K", "-", "tests/inputs/empty.txt"])
+        .write_stdin(input.clone())
+        .output()
+        .expect("fail");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout)?, input);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn non_utf8_lines() -> Result<()> {
+    let output = cargo_bin_cmd!()
+        .args(["-12", "-", "tests/inputs/empty.txt"])
+        .write_stdin(&b"caf\xe9\n"[..])
+        .output()
+        .expect("fail");
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"");
+
+    let output = cargo_bin_cmd!()
+        .args(["-", "tests/inputs/empty.txt"])
+        .write_stdin(&b"caf\xe9\n"[..])
+        .output()
+        .expect("fail");
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"caf\xe9\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_zero_buffer_size() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--buffer-size", "0", FILE1, FILE2])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("buffer size must be positive"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn log1_log2_skip_fields() -> Result<()> {
+    run!(
+        "tests/expected/log1_log2.skip.out",
+        LOG1,
+        LOG2,
+        "--skip-fields",
+        "1"
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn log1_log2_skip_chars() -> Result<()> {
+    run!(
+        "tests/expected/log1_log2.skip.out",
+        LOG1,
+        LOG2,
+        "--skip-chars",
+        "9"
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn log1_log2_no_skip() -> Result<()> {
+    // Without skipping, the timestamps make every line unique to its file
+    let expected = format!(
+        "{}{}",
+        fs::read_to_string(LOG1)?,
+        fs::read_to_string(LOG2)?
+            .lines()
+            .map(|line| format!("\t{line}\n"))
+            .collect::<String>()
+    );
+    let output = cargo_bin_cmd!().args([LOG1, LOG2]).output()?;
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout)?, expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn ids1_ids2_ignore_regex() -> Result<()> {
+    run!(
+        "tests/expected/ids1_ids2.ignore.out",
+        IDS1,
+        IDS2,
+        "--ignore-regex",
+        " id=[0-9a-f-]+"
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn log1_log2_ignore_regex() -> Result<()> {
+    // Removing the timestamps works just like skipping them
+    run!(
+        "tests/expected/log1_log2.skip.out",
+        LOG1,
+        LOG2,
+        "--ignore-regex",
+        "^[0-9:]+"
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_ignore_regex() -> Result<()> {
+    cargo_bin_cmd!()
+        .args([LOG1, LOG2, "--ignore-regex", "("])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "invalid value '(' for '--ignore-regex <PATTERN>'",
+        ));
+    Ok(())
+}
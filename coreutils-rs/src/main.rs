@@ -0,0 +1,131 @@
+//! Every tool of the workspace in one binary, BusyBox style: it runs the
+//! tool it is named after, so a link called ‘grep’ or ‘grepr’ is grepr, or
+//! else the tool named by its first argument, as in ‘coreutils-rs grep …’.
+
+mod man;
+
+use std::{
+    ffi::{OsStr, OsString},
+    path::Path,
+    process::exit,
+};
+
+use anyhow::Result;
+
+type Main = fn(Vec<OsString>) -> Result<()>;
+
+/// Each tool is known by its own name and by the name of what it imitates.
+const TOOLS: [(&str, &str, Main); 16] = [
+    ("cal", "calr", calr::main),
+    ("cat", "catr", |args| {
+        catr::main(args);
+        Ok(())
+    }),
+    ("comm", "commr", commr::main),
+    ("cut", "cutr", |args| {
+        cutr::main(args);
+        Ok(())
+    }),
+    ("echo", "echor", |args| {
+        echor::main(args);
+        Ok(())
+    }),
+    ("false", "false", |_| hello::r#false()),
+    ("find", "findr", findr::main),
+    ("fortune", "fortuner", fortuner::main),
+    ("grep", "grepr", grepr::main),
+    ("head", "headr", |args| {
+        headr::main(args);
+        Ok(())
+    }),
+    ("hello", "hello", |_| {
+        hello::main();
+        Ok(())
+    }),
+    ("ls", "lsr", lsr::main),
+    ("tail", "tailr", tailr::main),
+    ("true", "true", |_| {
+        hello::r#true();
+        Ok(())
+    }),
+    ("uniq", "uniqr", uniqr::main),
+    ("wc", "wcr", |args| {
+        wcr::main(args);
+        Ok(())
+    }),
+];
+
+fn find_tool(name: &OsStr) -> Option<Main> {
+    TOOLS
+        .iter()
+        .find(|(original, ours, _)| name == *original || name == *ours)
+        .map(|(_, _, main)| *main)
+}
+
+/// Runs a tool the way its own binary does, where a reader that went away
+/// is no error.
+fn run(main: Main, args: Vec<OsString>) -> Result<()> {
+    main(args).inspect_err(learnr::exit_on_broken_pipe)
+}
+
+fn usage() -> String {
+    let names: Vec<_> = TOOLS.iter().map(|(_, ours, _)| *ours).collect();
+    format!(
+        "Usage: coreutils-rs TOOL [ARGS]...\n\
+         \x20      coreutils-rs --generate-man DIR\n\n\
+         Runs TOOL, which is one of:\n  {}\n\n\
+         Each can also be run under the name of what it imitates, e.g. ‘grep’,\n\
+         or through a link to this binary with one of these names.\n\n\
+         With --generate-man, writes the man page of every tool into DIR.\n",
+        names.join(" ")
+    )
+}
+
+fn main() -> Result<()> {
+    let mut args: Vec<OsString> = std::env::args_os().collect();
+    let invoked_as = args
+        .first()
+        .and_then(|arg0| Path::new(arg0).file_name())
+        .and_then(find_tool);
+    if let Some(main) = invoked_as {
+        return run(main, args);
+    }
+
+    // The tool is the first argument, and sees itself run under that name
+    args.remove(0);
+    let Some(name) = args.first() else {
+        eprint!("{}", usage());
+        exit(1);
+    };
+    match name.to_str() {
+        Some("-h" | "--help") => {
+            print!("{}", usage());
+            return Ok(());
+        }
+        Some("--list") => {
+            for (_, ours, _) in TOOLS {
+                println!("{ours}");
+            }
+            return Ok(());
+        }
+        Some("--generate-man") => {
+            let Some(dir) = args.get(1) else {
+                eprint!(
+                    "coreutils-rs: --generate-man needs a directory\n\n{}",
+                    usage()
+                );
+                exit(1);
+            };
+            return man::generate(Path::new(dir));
+        }
+        _ => (),
+    }
+    match find_tool(name) {
+        Some(main) => run(main, args),
+        None => {
+            eprintln!("coreutils-rs: unknown tool '{}'", name.to_string_lossy());
+            eprint!("\n{}", usage());
+            exit(1);
+        }
+    }
+}
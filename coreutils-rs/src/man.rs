@@ -0,0 +1,63 @@
+//! Man pages for every tool, generated from the command line each one
+//! parses, with what its --help doesn't tell added to the description.
+
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use clap::Command;
+use clap_mangen::Man;
+
+/// Each tool's command line and the extended description of its man page.
+fn pages() -> [(Command, &'static str); 16] {
+    [
+        (calr::command(), calr::MAN_DESCRIPTION),
+        (catr::command(), catr::MAN_DESCRIPTION),
+        (commr::command(), commr::MAN_DESCRIPTION),
+        (cutr::command(), cutr::MAN_DESCRIPTION),
+        (echor::command(), echor::MAN_DESCRIPTION),
+        (
+            Command::new("false").about("Do nothing, unsuccessfully"),
+            "Exits with status 1.",
+        ),
+        (findr::command(), findr::MAN_DESCRIPTION),
+        (fortuner::command(), fortuner::MAN_DESCRIPTION),
+        (grepr::command(), grepr::MAN_DESCRIPTION),
+        (headr::command(), headr::MAN_DESCRIPTION),
+        (
+            Command::new("hello").about("Greet the world"),
+            "Prints ‘Hello, world!’.",
+        ),
+        (lsr::command(), lsr::MAN_DESCRIPTION),
+        (tailr::command(), tailr::MAN_DESCRIPTION),
+        (
+            Command::new("true").about("Do nothing, successfully"),
+            "Exits with a status indicating success.",
+        ),
+        (uniqr::command(), uniqr::MAN_DESCRIPTION),
+        (wcr::command(), wcr::MAN_DESCRIPTION),
+    ]
+}
+
+/// Writes ‘NAME.1’ into `dir` for every tool, creating `dir` if needed.
+pub fn generate(dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("{}", dir.display()))?;
+    for (cmd, description) in pages() {
+        let man = Man::new(with_description(cmd, description));
+        let path = dir.join(man.get_filename());
+        let mut page = Vec::new();
+        man.render(&mut page)?;
+        fs::write(&path, page).with_context(|| format!("{}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// The DESCRIPTION section shows the long help, or else the summary, so the
+/// extended text goes after whichever the tool has.
+fn with_description(cmd: Command, description: &str) -> Command {
+    let help = cmd
+        .get_long_about()
+        .or(cmd.get_about())
+        .map(ToString::to_string)
+        .unwrap_or_default();
+    cmd.long_about(format!("{help}\n\n{description}"))
+}
@@ -0,0 +1,180 @@
+use anyhow::Result;
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use pretty_assertions::assert_eq;
+use std::process::{Command, Stdio};
+
+// --------------------------------------------------
+#[test]
+fn runs_first_argument() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["echor", "hello", "there"])
+        .assert()
+        .success()
+        .stdout("hello there\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn runs_original_name() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["wc", "-l"])
+        .write_stdin("one\ntwo\n")
+        .assert()
+        .success()
+        .stdout("       2\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn tool_sees_its_name() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["tail", "--bogus"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Usage: tail [OPTIONS]"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn runs_link_name() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let link = dir.path().join("head");
+    std::os::unix::fs::symlink(assert_cmd::cargo::cargo_bin!(), &link)?;
+    let output = Command::new(&link)
+        .args(["-n", "1", "../headr/tests/inputs/two.txt"])
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout)?, "Two lines.\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn exit_status_is_kept() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["cutr", "-f", "1", "does-not-exist"])
+        .assert()
+        .code(1);
+    cargo_bin_cmd!().arg("true").assert().success();
+    cargo_bin_cmd!().arg("false").assert().code(1);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn broken_pipe_is_quiet() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let input = dir.path().join("input");
+    let lines: String = (0..100_000).map(|num| format!("x{num}\n")).collect();
+    std::fs::write(&input, lines)?;
+    for args in [
+        &["cat"][..],
+        &["cut", "-f", "1"],
+        &["grep", "x"],
+        &["tail", "-n", "100000"],
+        &["uniq"],
+        &["wc"],
+    ] {
+        let mut child = Command::new(assert_cmd::cargo::cargo_bin!())
+            .args(args)
+            .stdin(std::fs::File::open(&input)?)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        // Nobody reads the output, so the first write hits a closed pipe
+        drop(child.stdout.take());
+        let output = child.wait_with_output()?;
+        assert_eq!(
+            output.status.code(),
+            Some(learnr::BROKEN_PIPE_EXIT_CODE),
+            "{args:?}"
+        );
+        assert_eq!(String::from_utf8(output.stderr)?, "", "{args:?}");
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn list() -> Result<()> {
+    let output = cargo_bin_cmd!().arg("--list").output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    let tools: Vec<_> = stdout.lines().collect();
+    assert_eq!(tools.len(), 16);
+    assert!(tools.contains(&"grepr"));
+    assert!(tools.contains(&"true"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_no_tool() -> Result<()> {
+    cargo_bin_cmd!()
+        .assert()
+        .failure()
+        .stderr(predicate::str::starts_with("Usage: coreutils-rs TOOL"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_unknown_tool() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["frobr", "x"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::starts_with(
+            "coreutils-rs: unknown tool 'frobr'\n",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn generate_man() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let man_dir = dir.path().join("man1");
+    cargo_bin_cmd!()
+        .arg("--generate-man")
+        .arg(&man_dir)
+        .assert()
+        .success()
+        .stdout("");
+
+    let list = cargo_bin_cmd!().arg("--list").output()?;
+    for tool in String::from_utf8(list.stdout)?.lines() {
+        let page = std::fs::read_to_string(man_dir.join(format!("{tool}.1")))?;
+        assert!(page.contains(&format!(".TH {tool} 1")), "{tool}");
+        assert!(page.contains(".SH DESCRIPTION"), "{tool}");
+        // A long option never gets split across lines, as in ‘--skip- chars’
+        for word in page.split_whitespace() {
+            assert!(
+                !(word.starts_with(r"\-\-") && word.ends_with(r"\-")),
+                "{tool}: {word}"
+            );
+        }
+    }
+
+    let page = std::fs::read_to_string(man_dir.join("tailr.1"))?;
+    assert!(page.contains(r"\fB\-\-sleep\-interval\fR"));
+    assert!(page.contains("With \\-F a file that is rotated"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_generate_man_no_dir() -> Result<()> {
+    cargo_bin_cmd!()
+        .arg("--generate-man")
+        .assert()
+        .failure()
+        .stderr(predicate::str::starts_with(
+            "coreutils-rs: --generate-man needs a directory\n",
+        ));
+    Ok(())
+}
@@ -0,0 +1,91 @@
+//! Compares cutr with GNU cut on a generated tab separated log.
+//!
+//! The input is 16M by default, set e.g. `CUTR_BENCH_SIZE=1G` for the
+//! full-size run: `CUTR_BENCH_SIZE=1G cargo bench -p cutr`.
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+
+/// Each selection is run by both programs, so that they can be compared
+const SELECTIONS: [[&str; 2]; 4] = [
+    ["-b", "1-8,20-30,60-"],
+    ["-c", "1-8,20-30,60-"],
+    ["-f", "2,5-7"],
+    ["--complement", "-f2"],
+];
+
+fn generate(path: &Path, size: u64) -> u64 {
+    let mut out = BufWriter::new(File::create(path).expect("create bench input"));
+    let mut written = 0;
+    let mut i: u64 = 0;
+    while written < size {
+        let line = format!(
+            "{i:012}\t{}\tINFO\tworker-{}\trequest served in {}ms\tok\n",
+            i * 7919 % 100_000,
+            i % 16,
+            i % 997
+        );
+        out.write_all(line.as_bytes()).expect("write bench input");
+        written += line.len() as u64;
+        i += 1;
+    }
+    out.flush().expect("flush bench input");
+    written
+}
+
+fn run_cut(cmd: &mut Command) {
+    let status = cmd
+        .stdout(Stdio::null())
+        .env("LC_ALL", "C")
+        .status()
+        .expect("run cut");
+    assert!(status.success());
+}
+
+fn bench_cut(c: &mut Criterion) {
+    let size = std::env::var("CUTR_BENCH_SIZE")
+        .map(|s| learnr::parse_size(&s).expect("CUTR_BENCH_SIZE"))
+        .unwrap_or(16 << 20);
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    let input = dir.path().join("log.tsv");
+    let size = generate(&input, size);
+
+    let have_gnu_cut = Command::new("cut")
+        .arg("--version")
+        .stdout(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success());
+
+    for selection in SELECTIONS {
+        let mut group = c.benchmark_group(format!("cut {}", selection.join(" ")));
+        group.sample_size(10);
+        group.throughput(Throughput::Bytes(size));
+
+        group.bench_function("cutr", |b| {
+            b.iter(|| {
+                run_cut(
+                    Command::new(env!("CARGO_BIN_EXE_cutr"))
+                        .args(selection)
+                        .arg(&input),
+                )
+            })
+        });
+        if have_gnu_cut {
+            group.bench_function("GNU cut", |b| {
+                b.iter(|| run_cut(Command::new("cut").args(selection).arg(&input)))
+            });
+        }
+
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_cut);
+criterion_main!(benches);
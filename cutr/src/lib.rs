@@ -0,0 +1,1513 @@
+use std::collections::{BTreeMap, HashSet};
+use std::ffi::OsString;
+use std::fmt::Display;
+use std::fs::File;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::ops::Range;
+
+use anyhow::bail;
+use anyhow::{Context, Result};
+use clap::{Args as ClapArgs, CommandFactory, Parser, ValueEnum};
+
+/// Rust version of ‘cut’
+#[derive(Debug, Parser)]
+#[command(about, author, version)]
+struct Args {
+    /// Inputs files(s)
+    #[arg(default_value = "-")]
+    files: Vec<String>,
+
+    /// Field delimiter, one or more bytes; ‘\t’, ‘\r’, ‘\0’, ‘\\’ and ‘\xHH’
+    /// stand for the bytes they usually do, and a final ‘\’ for itself
+    #[arg(short, long, default_value = "\\t", value_parser = parse_delimiter)]
+    delimiter: Delimiter,
+
+    /// Split fields on runs of whitespace, ignoring leading and trailing
+    /// whitespace (like awk), instead of on a delimiter
+    #[arg(short, long, conflicts_with_all = ["delimiter", "bytes", "chars"])]
+    whitespace: bool,
+
+    /// Separate the selected fields with STR, with the same escapes as
+    /// --delimiter and a single byte with --csv [default: the input
+    /// delimiter, or " " with --whitespace]
+    #[arg(
+        long,
+        value_name = "STR",
+        value_parser = parse_output_delimiter,
+        conflicts_with_all = ["bytes", "chars"]
+    )]
+    output_delimiter: Option<Box<[u8]>>,
+
+    /// Don't print lines without any delimiter, which are otherwise printed
+    /// whole
+    #[arg(short('s'), long, conflicts_with_all = ["bytes", "chars"])]
+    only_delimited: bool,
+
+    #[command(flatten)]
+    extract: ArgsExtract,
+
+    /// Parse fields as CSV, where quoted fields may hold the delimiter,
+    /// instead of splitting lines on every delimiter byte
+    #[arg(long, conflicts_with = "whitespace")]
+    csv: bool,
+
+    /// Quoting style for output fields
+    #[arg(long, value_enum, default_value_t = QuoteStyle::Necessary, requires = "csv")]
+    quote_style: QuoteStyle,
+
+    /// Quote character
+    #[arg(long, default_value = "\"", value_parser = parse_byte, requires = "csv")]
+    quote: u8,
+
+    /// Escape character for quotes inside quoted fields (instead of doubling them)
+    #[arg(long, value_parser = parse_byte, requires = "csv")]
+    escape: Option<u8>,
+
+    /// Select all the fields, bytes or characters except the given ones
+    #[arg(long)]
+    complement: bool,
+
+    /// Instead of the selected fields, print statistics about each of them
+    /// over all inputs
+    #[arg(long, conflicts_with_all = ["bytes", "chars"])]
+    stats: bool,
+
+    /// Line delimiter is NUL, not newline
+    #[arg(short, long)]
+    zero_terminated: bool,
+
+    /// Leave out the first N lines of each file, such as a preamble before
+    /// the columns start
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    skip: usize,
+
+    /// Print the lines skipped with --skip unchanged instead of leaving them out
+    #[arg(long, requires = "skip", conflicts_with = "stats")]
+    passthrough_header: bool,
+}
+
+impl Args {
+    /// The byte that ends each record, not part of it
+    fn eol(&self) -> u8 {
+        if self.zero_terminated { b'\0' } else { b'\n' }
+    }
+}
+
+/// Where a line is split into fields.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Delimiter {
+    /// At every occurrence of the byte, as with ‘cut -d’
+    Byte(u8),
+    /// At every occurrence of a string of several bytes
+    Str(Vec<u8>),
+    /// At runs of blanks, ignoring leading and trailing ones, like awk
+    Whitespace,
+}
+
+impl Delimiter {
+    /// The only kind of delimiter the CSV parser understands
+    fn byte(&self) -> Option<u8> {
+        match self {
+            Delimiter::Byte(byte) => Some(*byte),
+            _ => None,
+        }
+    }
+
+    /// What joins the output fields unless --output-delimiter says otherwise
+    fn output(&self) -> &[u8] {
+        match self {
+            Delimiter::Byte(byte) => std::slice::from_ref(byte),
+            Delimiter::Str(delimiter) => delimiter,
+            Delimiter::Whitespace => b" ",
+        }
+    }
+
+    /// Splits `line`, without its terminator, into fields. Quotes are
+    /// ordinary bytes here, as they are for ‘cut’.
+    fn split<'a>(&'a self, line: &'a [u8]) -> Box<dyn Iterator<Item = &'a [u8]> + 'a> {
+        match self {
+            Delimiter::Byte(byte) => Box::new(line.split(move |b| b == byte)),
+            Delimiter::Str(delimiter) => {
+                let mut rest = Some(line);
+                Box::new(std::iter::from_fn(move || {
+                    let field = rest?;
+                    match field
+                        .windows(delimiter.len())
+                        .position(|window| window == delimiter.as_slice())
+                    {
+                        Some(pos) => {
+                            rest = Some(&field[pos + delimiter.len()..]);
+                            Some(&field[..pos])
+                        }
+                        None => rest.take(),
+                    }
+                }))
+            }
+            Delimiter::Whitespace => {
+                let mut rest = line;
+                Box::new(std::iter::from_fn(move || {
+                    while let Some(len) = leading_whitespace(rest) {
+                        rest = &rest[len..];
+                    }
+                    if rest.is_empty() {
+                        return None;
+                    }
+                    let end = (1..rest.len())
+                        .find(|&end| leading_whitespace(&rest[end..]).is_some())
+                        .unwrap_or(rest.len());
+                    let (field, after) = rest.split_at(end);
+                    rest = after;
+                    Some(field)
+                }))
+            }
+        }
+    }
+}
+
+/// The length of the whitespace character that `bytes` start with, if they
+/// do, as `char::is_whitespace` tells for Unicode. Bytes that aren't UTF-8
+/// are never whitespace.
+fn leading_whitespace(bytes: &[u8]) -> Option<usize> {
+    match bytes.first()? {
+        byte if byte.is_ascii() => (*byte as char).is_whitespace().then_some(1),
+        // No character is longer than four bytes
+        _ => bytes[..bytes.len().min(4)]
+            .utf8_chunks()
+            .next()?
+            .valid()
+            .chars()
+            .next()
+            .filter(|ch| ch.is_whitespace())
+            .map(char::len_utf8),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum QuoteStyle {
+    /// Quote every field
+    Always,
+    /// Quote only fields containing the delimiter, quote or record terminator
+    Necessary,
+    /// Never quote fields
+    Never,
+}
+
+impl From<QuoteStyle> for csv::QuoteStyle {
+    fn from(style: QuoteStyle) -> Self {
+        match style {
+            QuoteStyle::Always => csv::QuoteStyle::Always,
+            QuoteStyle::Necessary => csv::QuoteStyle::Necessary,
+            QuoteStyle::Never => csv::QuoteStyle::Never,
+        }
+    }
+}
+
+#[derive(Debug, Clone, ClapArgs)]
+#[group(required = true, multiple = false)]
+struct ArgsExtract {
+    /// Selected fields, e.g. ‘1,3-5’, ‘-2’ (1 to 2), ‘4-’ (4 to the end),
+    /// ‘1-9:2’ (every other one) or ‘!3’ (all but 3)
+    #[arg(short, long, allow_hyphen_values = true, value_parser = parse_fields)]
+    fields: Option<PositionList>,
+
+    /// Selected bytes
+    #[arg(short, long, allow_hyphen_values = true, value_parser = parse_positions)]
+    bytes: Option<PositionList>,
+
+    /// Selected chars
+    #[arg(short, long, allow_hyphen_values = true, value_parser = parse_positions)]
+    chars: Option<PositionList>,
+}
+
+/// Every `step`th position of `range`, starting with its first.
+#[derive(Debug, Clone, PartialEq)]
+struct Stepped {
+    range: Range<usize>,
+    step: usize,
+}
+
+impl Stepped {
+    fn contains(&self, idx: usize) -> bool {
+        self.range.contains(&idx) && (idx - self.range.start).is_multiple_of(self.step)
+    }
+
+    /// The positions among the `len` of a record
+    fn indices(&self, len: usize) -> impl Iterator<Item = usize> {
+        (self.range.start..self.range.end.min(len)).step_by(self.step)
+    }
+}
+
+/// An item of a list with steps or negations
+#[derive(Debug, Clone, PartialEq)]
+enum PositionItem {
+    Include(Stepped),
+    /// ‘!’ in front: left out from what the rest of the list selects
+    Exclude(Stepped),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum PositionList {
+    /// The plain ranges ‘cut’ takes, which can be copied a run at a time
+    Ranges(Vec<Range<usize>>),
+    /// A list with a step or a ‘!’ somewhere, worked out for each record once
+    /// its length is known
+    Items(Vec<PositionItem>),
+}
+
+impl PositionList {
+    fn contains(&self, idx: usize) -> bool {
+        match self {
+            PositionList::Ranges(ranges) => ranges.iter().any(|range| range.contains(&idx)),
+            PositionList::Items(items) => {
+                let mut includes = includes(items).peekable();
+                // Only negations select everything else
+                let included = includes.peek().is_none() || includes.any(|s| s.contains(idx));
+                included && !excluded(items, idx)
+            }
+        }
+    }
+
+    /// The positions selected among the `len` of a record, in the order
+    /// given. With steps or negations, none comes twice.
+    fn resolve(&self, len: usize) -> Vec<usize> {
+        let items = match self {
+            PositionList::Ranges(ranges) => {
+                return ranges
+                    .iter()
+                    .flat_map(|range| range.start.min(len)..range.end.min(len))
+                    .collect();
+            }
+            PositionList::Items(items) => items,
+        };
+        let all = Stepped {
+            range: 0..usize::MAX,
+            step: 1,
+        };
+        let mut includes: Vec<&Stepped> = includes(items).collect();
+        if includes.is_empty() {
+            includes.push(&all);
+        }
+        let mut seen = vec![false; len];
+        includes
+            .into_iter()
+            .flat_map(|stepped| stepped.indices(len))
+            .filter(|&idx| !excluded(items, idx) && !std::mem::replace(&mut seen[idx], true))
+            .collect()
+    }
+}
+
+fn includes(items: &[PositionItem]) -> impl Iterator<Item = &Stepped> {
+    items.iter().filter_map(|item| match item {
+        PositionItem::Include(stepped) => Some(stepped),
+        PositionItem::Exclude(_) => None,
+    })
+}
+
+/// Whether a negated item of `items` leaves out `idx`.
+fn excluded(items: &[PositionItem], idx: usize) -> bool {
+    items
+        .iter()
+        .any(|item| matches!(item, PositionItem::Exclude(stepped) if stepped.contains(idx)))
+}
+
+/// The positions to print: those of `ranges` in the order they were given
+/// or, with `complement`, all the others in the order of the line. The
+/// complement of an open range is unbounded, so it can only be enumerated
+/// for a line of known length.
+#[derive(Debug, Clone)]
+pub struct Selection {
+    ranges: PositionList,
+    complement: bool,
+}
+
+impl Selection {
+    fn contains(&self, idx: usize) -> bool {
+        self.ranges.contains(idx) != self.complement
+    }
+
+    /// Indices of the selected positions among the `len` of a line
+    fn indices(&self, len: usize) -> impl Iterator<Item = usize> + '_ {
+        self.spans(len).flatten()
+    }
+
+    /// The selected positions among the `len` of a line, as runs of adjacent
+    /// ones that can be copied in one go.
+    fn spans(&self, len: usize) -> Box<dyn Iterator<Item = Range<usize>> + '_> {
+        let ranges = match &self.ranges {
+            PositionList::Ranges(ranges) => ranges,
+            PositionList::Items(_) => return Box::new(self.resolved_spans(len)),
+        };
+        if !self.complement {
+            return Box::new(
+                ranges
+                    .iter()
+                    .map(move |range| range.start.min(len)..range.end.min(len))
+                    .filter(|span| !span.is_empty()),
+            );
+        }
+        let mut pos = 0;
+        Box::new(std::iter::from_fn(move || {
+            // Step over the ranges left out, which may overlap each other
+            while let Some(end) = ranges
+                .iter()
+                .filter(|range| range.contains(&pos))
+                .map(|range| range.end)
+                .max()
+            {
+                pos = end;
+            }
+            if pos >= len {
+                return None;
+            }
+            let end = ranges
+                .iter()
+                .map(|range| range.start)
+                .filter(|&start| start > pos)
+                .min()
+                .map_or(len, |start| start.min(len));
+            let span = pos..end;
+            pos = end;
+            Some(span)
+        }))
+    }
+
+    /// [`Selection::spans`] for a list that has to be resolved position by
+    /// position.
+    fn resolved_spans(&self, len: usize) -> impl Iterator<Item = Range<usize>> + use<> {
+        let mut selected = self.ranges.resolve(len);
+        if self.complement {
+            let mut left_out = vec![false; len];
+            for &idx in &selected {
+                left_out[idx] = true;
+            }
+            selected = (0..len).filter(|&idx| !left_out[idx]).collect();
+        }
+        let mut spans: Vec<Range<usize>> = vec![];
+        for idx in selected {
+            match spans.last_mut() {
+                Some(span) if span.end == idx => span.end += 1,
+                _ => spans.push(idx..idx + 1),
+            }
+        }
+        spans.into_iter()
+    }
+}
+
+#[derive(Debug)]
+pub enum Extract {
+    Fields(Selection),
+    Bytes(Selection),
+    Chars(Selection),
+}
+
+/// The command line that [`main`] parses, for generating the man page.
+pub fn command() -> clap::Command {
+    Args::command()
+}
+
+/// What the man page tells beyond --help.
+pub const MAN_DESCRIPTION: &str = "\
+Exactly one of --bytes, --chars and --fields must be given. A LIST is made of \
+numbers and ranges separated by commas, counted from 1 and printed in the \
+order given unless --complement is used.\n\n\
+A range followed by ‘:STEP’ selects every STEPth position of it, from its \
+first. An item prefixed with ‘!’ is left out of what the rest of the list \
+selects, or of everything when the list has nothing else; such a list prints \
+no position twice. --complement then applies to the result.\n\n\
+An input that can't be read or cut is reported and the others are still \
+processed; the exit status is then 1. A failed write to standard output stops \
+everything at once.";
+
+pub fn main(args: impl IntoIterator<Item = OsString>) {
+    match run(parse_args(args)) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            learnr::exit_on_broken_pipe(&e);
+            eprintln!("cutr: {e:#}");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn parse_args(args: impl IntoIterator<Item = OsString>) -> Args {
+    let mut args = Args::parse_from(args);
+    if args.whitespace {
+        args.delimiter = Delimiter::Whitespace;
+    }
+    args
+}
+
+/// Stdout, buffered and shared by all the inputs. A failed write is
+/// remembered, so that it can be told apart from errors reading an input:
+/// it ends the whole run, while a bad input only fails itself.
+struct Output<W: Write> {
+    out: W,
+    failed: bool,
+}
+
+impl<W: Write> Output<W> {
+    fn new(out: W) -> Self {
+        Output { out, failed: false }
+    }
+}
+
+impl<W: Write> Write for Output<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.out.write(buf);
+        self.failed |= written.is_err();
+        written
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let flushed = self.out.flush();
+        self.failed |= flushed.is_err();
+        flushed
+    }
+}
+
+/// Returns whether every input was read and cut without errors; those that
+/// weren't have already been reported. Bad arguments and failed writes are
+/// the only `Err`s, as they end the run.
+fn run(args: Args) -> Result<bool> {
+    let extract = build_extract(&args.extract, args.complement)?;
+    if args.csv && args.delimiter.byte().is_none() {
+        bail!("the delimiter must be a single byte with --csv");
+    }
+    if let Some(delimiter) = &args.output_delimiter
+        && args.csv
+        && delimiter.len() != 1
+    {
+        bail!("the output delimiter must be a single byte with --csv");
+    }
+    let mut stats = args.stats.then(Stats::default);
+    let mut out = Output::new(io::BufWriter::new(io::stdout().lock()));
+    let mut all_ok = true;
+    for filename in &args.files {
+        match cut_file(filename, &extract, &args, &mut stats, &mut out) {
+            Ok(ok) => all_ok &= ok,
+            Err(e) if out.failed => return Err(e.context("write error")),
+            Err(e) => {
+                eprintln!("{filename}: {e}");
+                all_ok = false;
+            }
+        }
+    }
+    if let Some(stats) = stats {
+        write!(out, "{stats}").context("write error")?;
+    }
+    out.flush().context("write error")?;
+    Ok(all_ok)
+}
+
+/// Cuts a single input, or adds it to `stats`. Returns whether every record
+/// could be handled; the bad ones are reported and skipped.
+fn cut_file(
+    filename: &str,
+    extract: &Extract,
+    args: &Args,
+    stats: &mut Option<Stats>,
+    out: &mut impl Write,
+) -> Result<bool> {
+    let mut file = open(filename)?;
+    skip_header(
+        &mut file,
+        args.skip,
+        args.eol(),
+        args.passthrough_header,
+        out,
+    )?;
+    match (stats, extract) {
+        (Some(stats), Extract::Fields(fl)) => collect_stats(filename, &mut file, fl, args, stats),
+        _ => extract_file(filename, &mut file, extract, args, out),
+    }
+}
+
+/// Reads the first `lines` lines of `file`, copying them to `out` as they
+/// are with `passthrough`.
+fn skip_header(
+    file: &mut impl BufRead,
+    lines: usize,
+    eol: u8,
+    passthrough: bool,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let mut line = Vec::new();
+    for _ in 0..lines {
+        line.clear();
+        if file.read_until(eol, &mut line)? == 0 {
+            break;
+        }
+        if passthrough {
+            out.write_all(&line)?;
+        }
+    }
+    Ok(())
+}
+
+/// At most this many different values are remembered per field
+const DISTINCT_CAP: usize = 10_000;
+
+/// What --stats reports about one selected field.
+#[derive(Debug, Default, PartialEq)]
+struct FieldStats {
+    non_empty: u64,
+    distinct: HashSet<String>,
+    /// More than `DISTINCT_CAP` different values were seen
+    distinct_overflow: bool,
+    min_len: Option<usize>,
+    max_len: Option<usize>,
+    /// `(min, sum, max, count)` of the values that parse as numbers
+    numeric: Option<(f64, f64, f64, u64)>,
+}
+
+impl FieldStats {
+    fn add(&mut self, value: &str) {
+        if !value.is_empty() {
+            self.non_empty += 1;
+        }
+        if !self.distinct.contains(value) {
+            if self.distinct.len() < DISTINCT_CAP {
+                self.distinct.insert(value.to_string());
+            } else {
+                self.distinct_overflow = true;
+            }
+        }
+        let len = value.chars().count();
+        self.min_len = Some(self.min_len.map_or(len, |min| min.min(len)));
+        self.max_len = Some(self.max_len.map_or(len, |max| max.max(len)));
+        if let Ok(num) = value.trim().parse::<f64>()
+            && num.is_finite()
+        {
+            let (min, sum, max, count) = self.numeric.unwrap_or((num, 0.0, num, 0));
+            self.numeric = Some((min.min(num), sum + num, max.max(num), count + 1));
+        }
+    }
+}
+
+/// Statistics for each selected field, by its zero-based index.
+#[derive(Debug, Default)]
+struct Stats {
+    fields: BTreeMap<usize, FieldStats>,
+}
+
+impl Stats {
+    fn add_record<'a>(
+        &mut self,
+        record: impl IntoIterator<Item = &'a str>,
+        fields_pos: &Selection,
+    ) {
+        for (i, value) in record.into_iter().enumerate() {
+            if fields_pos.contains(i) {
+                self.fields.entry(i).or_default().add(value);
+            }
+        }
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "field\tnon-empty\tdistinct\tmin-len\tmax-len\tmin\tmean\tmax"
+        )?;
+        for (i, stats) in &self.fields {
+            let distinct = if stats.distinct_overflow {
+                format!(">{DISTINCT_CAP}")
+            } else {
+                stats.distinct.len().to_string()
+            };
+            let numeric = match stats.numeric {
+                Some((min, sum, max, count)) => format!("{min}\t{:.2}\t{max}", sum / count as f64),
+                None => "-\t-\t-".to_string(),
+            };
+            writeln!(
+                f,
+                "{}\t{}\t{distinct}\t{}\t{}\t{numeric}",
+                i + 1,
+                stats.non_empty,
+                stats.min_len.unwrap_or(0),
+                stats.max_len.unwrap_or(0),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Feeds the selected fields of every record in `file` to `stats`, split the
+/// same way as for printing them. Returns whether every record could be read.
+fn collect_stats(
+    filename: &str,
+    file: &mut impl BufRead,
+    fields_pos: &Selection,
+    args: &Args,
+    stats: &mut Stats,
+) -> Result<bool> {
+    if !args.csv {
+        for line in file.split(args.eol()) {
+            let line = line?;
+            let fields: Vec<_> = args
+                .delimiter
+                .split(&line)
+                .map(String::from_utf8_lossy)
+                .collect();
+            if !args.only_delimited || is_delimited(fields.len()) {
+                stats.add_record(fields.iter().map(|field| field.as_ref()), fields_pos);
+            }
+        }
+        return Ok(true);
+    }
+    let mut all_ok = true;
+    for record in csv_reader(file, args).records() {
+        match record {
+            Ok(record) if args.only_delimited && !is_delimited(record.len()) => {}
+            Ok(record) => stats.add_record(&record, fields_pos),
+            Err(e) => {
+                eprintln!("{filename}: bad record {e}");
+                all_ok = false;
+            }
+        }
+    }
+    Ok(all_ok)
+}
+
+fn build_extract(args: &ArgsExtract, complement: bool) -> Result<Extract> {
+    let selection = |ranges: &PositionList| Selection {
+        ranges: ranges.clone(),
+        complement,
+    };
+    match args {
+        ArgsExtract {
+            fields: Some(fs), ..
+        } => Ok(Extract::Fields(selection(fs))),
+        ArgsExtract {
+            chars: Some(cs), ..
+        } => Ok(Extract::Chars(selection(cs))),
+        ArgsExtract {
+            bytes: Some(bs), ..
+        } => Ok(Extract::Bytes(selection(bs))),
+        _ => unreachable!("clap must ensure that there is exactly one option set in '{args:?}'"),
+    }
+}
+
+/// Writes what `extract` selects from each record of `file` to `out`.
+/// Returns whether every record could be cut.
+fn extract_file(
+    filename: &str,
+    file: &mut impl BufRead,
+    extract: &Extract,
+    args: &Args,
+    out: &mut impl Write,
+) -> Result<bool> {
+    match extract {
+        Extract::Chars(pl) => {
+            let mut starts = Vec::new();
+            print_records(filename, file, args.eol(), out, |record, output| {
+                extract_chars(std::str::from_utf8(record)?, pl, &mut starts, output);
+                Ok(true)
+            })
+        }
+        Extract::Bytes(bl) => print_records(filename, file, args.eol(), out, |record, output| {
+            extract_bytes(record, bl, output);
+            Ok(true)
+        }),
+        Extract::Fields(fl) if args.csv => extract_fields_from_file(filename, file, fl, args, out),
+        Extract::Fields(fl) => {
+            let output_delimiter = match &args.output_delimiter {
+                Some(delimiter) => delimiter,
+                None => args.delimiter.output(),
+            };
+            print_records(filename, file, args.eol(), out, |record, output| {
+                let fields: Vec<&[u8]> = args.delimiter.split(record).collect();
+                if is_delimited(fields.len()) {
+                    extract_raw_fields(&fields, fl, output_delimiter, output);
+                } else if args.only_delimited {
+                    return Ok(false);
+                } else {
+                    output.extend_from_slice(record);
+                }
+                Ok(true)
+            })
+        }
+    }
+}
+
+/// Writes what `extract` makes of each record of `file`, the bytes before
+/// each `eol`, followed by the same terminator. `extract` appends its output
+/// to the buffer it is given, and returns `false` for records that are left
+/// out. Those it fails on are reported and skipped, which the result tells
+/// with `false`.
+///
+/// The record and output buffers are reused from one record to the next, so
+/// that huge inputs don't cost an allocation per line.
+fn print_records(
+    filename: &str,
+    file: &mut impl BufRead,
+    eol: u8,
+    out: &mut impl Write,
+    mut extract: impl FnMut(&[u8], &mut Vec<u8>) -> Result<bool>,
+) -> Result<bool> {
+    let mut all_ok = true;
+    let mut record = Vec::new();
+    let mut output = Vec::new();
+    loop {
+        record.clear();
+        if file.read_until(eol, &mut record)? == 0 {
+            break;
+        }
+        if record.last() == Some(&eol) {
+            record.pop();
+        }
+        output.clear();
+        match extract(&record, &mut output) {
+            Ok(true) => {
+                output.push(eol);
+                out.write_all(&output)?;
+            }
+            Ok(false) => {}
+            Err(e) => {
+                eprintln!("{filename}: bad line {e}");
+                all_ok = false;
+            }
+        }
+    }
+    Ok(all_ok)
+}
+
+/// What a position list counts, for error messages worded like GNU cut's.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Unit {
+    Field,
+    Position,
+}
+
+impl Unit {
+    fn plural(self) -> &'static str {
+        match self {
+            Unit::Field => "fields",
+            Unit::Position => "byte/character positions",
+        }
+    }
+
+    fn value(self) -> &'static str {
+        match self {
+            Unit::Field => "field value",
+            Unit::Position => "byte/character position",
+        }
+    }
+
+    fn number(self) -> &'static str {
+        match self {
+            Unit::Field => "field number",
+            Unit::Position => "byte/character offset",
+        }
+    }
+
+    fn range(self) -> &'static str {
+        match self {
+            Unit::Field => "field range",
+            Unit::Position => "byte or character range",
+        }
+    }
+}
+
+fn parse_fields(pos: &str) -> Result<PositionList> {
+    parse_pos(pos, Unit::Field)
+}
+
+fn parse_positions(pos: &str) -> Result<PositionList> {
+    parse_pos(pos, Unit::Position)
+}
+
+fn parse_single_position(s: &str, unit: Unit) -> Result<usize> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        bail!("invalid {} '{s}'", unit.value());
+    }
+    match s.parse::<usize>() {
+        Ok(0) => bail!("{} are numbered from 1", unit.plural()),
+        Ok(num) => Ok(num),
+        Err(_) => bail!("{} '{s}' is too large", unit.number()),
+    }
+}
+
+/// Parses a list like ‘1,3-5,7-’ into zero-based ranges. A range without a
+/// start begins at 1, one without an end goes on to the end of the line.
+/// The list keeps its order, and overlapping ranges are merged so that they
+/// don't print anything twice. A single position can still be repeated on
+/// purpose, as in ‘1,1’.
+///
+/// Items can also take a step, as ‘1-9:2’ for every other position, or be
+/// negated with a leading ‘!’, leaving them out of what the others select,
+/// or of everything when there are only negations.
+fn parse_pos(pos: &str, unit: Unit) -> Result<PositionList> {
+    if !pos.contains([':', '!']) {
+        return parse_ranges(pos, unit).map(PositionList::Ranges);
+    }
+    let mut items = vec![];
+    for item in pos.split(',') {
+        let (negated, item) = match item.strip_prefix('!') {
+            Some(item) => (true, item),
+            None => (false, item),
+        };
+        let (item, step) = match item.split_once(':') {
+            Some((item, step)) => match step.parse::<usize>() {
+                Ok(num) if num > 0 && step.bytes().all(|b| b.is_ascii_digit()) => (item, num),
+                _ => bail!("invalid step '{step}'"),
+            },
+            None => (item, 1),
+        };
+        let range = parse_range(item, unit)?;
+        let stepped = Stepped { range, step };
+        items.push(match negated {
+            true => PositionItem::Exclude(stepped),
+            false => PositionItem::Include(stepped),
+        });
+    }
+    Ok(PositionList::Items(items))
+}
+
+/// Parses a list of plain ranges, as [`parse_pos`] describes.
+fn parse_ranges(pos: &str, unit: Unit) -> Result<Vec<Range<usize>>> {
+    // Each range, and whether it was written as one
+    let mut list: Vec<(Range<usize>, bool)> = vec![];
+    for item in pos.split(',') {
+        add_range(&mut list, parse_range(item, unit)?, item.contains('-'));
+    }
+    Ok(list.into_iter().map(|(range, _)| range).collect())
+}
+
+/// Parses a single position or range of a list into a zero-based range.
+fn parse_range(item: &str, unit: Unit) -> Result<Range<usize>> {
+    Ok(match item.split_once('-') {
+        None if item.is_empty() => bail!("{} are numbered from 1", unit.plural()),
+        None => {
+            let num = parse_single_position(item, unit)?;
+            num - 1..num
+        }
+        Some(("", "")) => bail!("invalid range with no endpoint: -"),
+        Some((_, end)) if end.contains('-') => bail!("invalid {}", unit.range()),
+        Some((start, end)) => {
+            let start = match start {
+                "" => 1,
+                _ => parse_single_position(start, unit)?,
+            };
+            let end = match end {
+                "" => usize::MAX,
+                _ => parse_single_position(end, unit)?,
+            };
+            if start > end {
+                bail!("invalid decreasing range");
+            }
+            start - 1..end
+        }
+    })
+}
+
+/// Adds `range` to `list`, merging it with everything it overlaps, unless
+/// both are single positions. The result takes the place of the first of them.
+fn add_range(list: &mut Vec<(Range<usize>, bool)>, mut range: Range<usize>, mut is_range: bool) {
+    let mut place: Option<usize> = None;
+    let mut idx = 0;
+    while idx < list.len() {
+        let (other, other_is_range) = &list[idx];
+        if (is_range || *other_is_range) && other.start < range.end && range.start < other.end {
+            range = other.start.min(range.start)..other.end.max(range.end);
+            is_range = true;
+            list.remove(idx);
+            place = Some(place.map_or(idx, |place| place.min(idx)));
+            // The range grew, so it may overlap ones that were checked already
+            idx = 0;
+        } else {
+            idx += 1;
+        }
+    }
+    list.insert(place.unwrap_or(list.len()), (range, is_range));
+}
+
+fn csv_reader<R: BufRead>(file: R, args: &Args) -> csv::Reader<R> {
+    let delimiter = args
+        .delimiter
+        .byte()
+        .expect("run() only allows single byte delimiters with --csv");
+    csv::ReaderBuilder::new()
+        .has_headers(false)
+        .delimiter(delimiter)
+        .quote(args.quote)
+        .escape(args.escape)
+        .double_quote(args.escape.is_none())
+        .flexible(true)
+        // Without -z, the default: a newline with or without a carriage return
+        .terminator(match args.zero_terminated {
+            true => csv::Terminator::Any(b'\0'),
+            false => csv::Terminator::CRLF,
+        })
+        .from_reader(file)
+}
+
+fn extract_fields_from_file(
+    filename: &str,
+    file: &mut impl BufRead,
+    fields_pos: &Selection,
+    args: &Args,
+    out: &mut impl Write,
+) -> Result<bool> {
+    let mut rdr = csv_reader(file, args);
+
+    let output_delimiter = match &args.output_delimiter {
+        Some(delimiter) => delimiter[0],
+        None => args.delimiter.output()[0],
+    };
+    let mut wtr = csv::WriterBuilder::new()
+        .delimiter(output_delimiter)
+        .quote_style(args.quote_style.into())
+        .quote(args.quote)
+        .escape(args.escape.unwrap_or(b'\\'))
+        .double_quote(args.escape.is_none())
+        // Records don't all have the same number of fields selected
+        .flexible(true)
+        .terminator(csv::Terminator::Any(args.eol()))
+        .from_writer(out);
+
+    let mut all_ok = true;
+    let mut line = csv::StringRecord::new();
+    loop {
+        match rdr.read_record(&mut line) {
+            Ok(false) => break,
+            Ok(true) if !is_delimited(line.len()) => {
+                if !args.only_delimited {
+                    wtr.write_record(&line)?;
+                }
+            }
+            Ok(true) => wtr.write_record(extract_fields(&line, fields_pos))?,
+            Err(e) => {
+                eprintln!("{filename}: bad record {e}");
+                all_ok = false;
+            }
+        }
+    }
+
+    wtr.flush()?;
+    Ok(all_ok)
+}
+
+/// Whether a line split into `fields` had any delimiter in it. Like ‘cut’,
+/// lines without one are left alone: printed whole, or dropped with -s.
+fn is_delimited(fields: usize) -> bool {
+    fields > 1
+}
+
+/// Appends the selected `fields` to `out`, separated by `delimiter`.
+fn extract_raw_fields(
+    fields: &[&[u8]],
+    fields_pos: &Selection,
+    delimiter: &[u8],
+    out: &mut Vec<u8>,
+) {
+    for (i, idx) in fields_pos.indices(fields.len()).enumerate() {
+        if i > 0 {
+            out.extend_from_slice(delimiter);
+        }
+        out.extend_from_slice(fields[idx]);
+    }
+}
+
+fn extract_fields<'a>(line: &'a csv::StringRecord, fields_pos: &Selection) -> Vec<&'a str> {
+    fields_pos
+        .indices(line.len())
+        .map(|idx| &line[idx])
+        .collect()
+}
+
+/// Appends the selected characters of `line` to `out`. `starts` is only
+/// scratch space for where each character starts, passed in so that it is
+/// allocated once rather than for every line.
+fn extract_chars(line: &str, char_pos: &Selection, starts: &mut Vec<usize>, out: &mut Vec<u8>) {
+    // Every character is a byte, so there's no need to find where they start
+    if line.is_ascii() {
+        return extract_bytes(line.as_bytes(), char_pos, out);
+    }
+    starts.clear();
+    starts.extend(line.char_indices().map(|(start, _)| start));
+    starts.push(line.len());
+    for span in char_pos.spans(starts.len() - 1) {
+        out.extend_from_slice(&line.as_bytes()[starts[span.start]..starts[span.end]]);
+    }
+}
+
+/// Appends the selected bytes of `record` to `out`, a run at a time.
+fn extract_bytes(record: &[u8], byte_pos: &Selection, out: &mut Vec<u8>) {
+    for span in byte_pos.spans(record.len()) {
+        out.extend_from_slice(&record[span]);
+    }
+}
+
+/// Parses a field delimiter, a single byte being the usual kind.
+fn parse_delimiter(s: &str) -> Result<Delimiter, String> {
+    match unescape(s)? {
+        bytes if bytes.is_empty() => Err("the delimiter must not be empty".to_string()),
+        bytes if bytes.len() == 1 => Ok(Delimiter::Byte(bytes[0])),
+        bytes => Ok(Delimiter::Str(bytes)),
+    }
+}
+
+fn parse_output_delimiter(s: &str) -> Result<Box<[u8]>, String> {
+    unescape(s).map(Vec::into_boxed_slice)
+}
+
+/// Replaces ‘\t’, ‘\r’, ‘\0’, ‘\\’ and ‘\xHH’ in `s` with the bytes they
+/// stand for. A backslash at the end stands for itself.
+fn unescape(s: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            bytes.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('t') => bytes.push(b'\t'),
+            Some('r') => bytes.push(b'\r'),
+            Some('0') => bytes.push(b'\0'),
+            Some('\\') => bytes.push(b'\\'),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                if hex.len() != 2 || !hex.chars().all(|ch| ch.is_ascii_hexdigit()) {
+                    return Err("‘\\x’ must be followed by two hex digits".to_string());
+                }
+                bytes.push(u8::from_str_radix(&hex, 16).expect("checked hex digits"));
+            }
+            Some(other) => return Err(format!("unknown escape sequence ‘\\{other}’")),
+            // As in ‘cut -d '\'’, a backslash that escapes nothing is itself
+            None => bytes.push(b'\\'),
+        }
+    }
+    Ok(bytes)
+}
+
+fn parse_byte(s: &str) -> Result<u8, String> {
+    match s.len() {
+        1 => s
+            .as_bytes()
+            .first()
+            .map_or(Err("must be a single byte".to_string()), |b| Ok(*b)),
+        _ => Err("must be a single byte".to_string()),
+    }
+}
+
+fn open(filename: &str) -> Result<Box<dyn BufRead>> {
+    match filename {
+        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
+        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::single_range_in_vec_init)]
+    use assertables::*;
+    use csv::StringRecord;
+    use learnr::assert_err_str_contains;
+
+    use crate::*;
+
+    #[test]
+    fn byte_value_parser() {
+        assert_eq!(Ok(46), parse_byte("."));
+        assert_eq!(Err("must be a single byte".to_string()), parse_byte(",,"));
+    }
+
+    #[test]
+    fn delimiter_value_parser() {
+        assert_eq!(Ok(Delimiter::Byte(b'.')), parse_delimiter("."));
+        assert_eq!(Ok(Delimiter::Byte(b'\t')), parse_delimiter(r"\t"));
+        assert_eq!(Ok(Delimiter::Byte(0)), parse_delimiter(r"\0"));
+        assert_eq!(Ok(Delimiter::Byte(0x1f)), parse_delimiter(r"\x1F"));
+        assert_eq!(Ok(Delimiter::Byte(b'\\')), parse_delimiter(r"\\"));
+        assert_eq!(Ok(Delimiter::Str(b"::".to_vec())), parse_delimiter("::"));
+        assert_eq!(
+            Ok(Delimiter::Str(b"\t|\t".to_vec())),
+            parse_delimiter(r"\t|\t")
+        );
+        assert_eq!(
+            Ok(Delimiter::Str("🏰".as_bytes().to_vec())),
+            parse_delimiter("🏰")
+        );
+        assert_eq!(
+            Err("the delimiter must not be empty".to_string()),
+            parse_delimiter("")
+        );
+        assert_eq!(
+            Err("unknown escape sequence ‘\\q’".to_string()),
+            parse_delimiter(r"\q")
+        );
+        assert_eq!(
+            Err("‘\\x’ must be followed by two hex digits".to_string()),
+            parse_delimiter(r"\x+f")
+        );
+        assert_eq!(Ok(Delimiter::Byte(b'\\')), parse_delimiter("\\"));
+        assert_eq!(Ok(Delimiter::Str(b"a\\".to_vec())), parse_delimiter(r"a\"));
+    }
+
+    fn test_parse_pos(s: &str, exp: Vec<(usize, usize)>) {
+        let PositionList::Ranges(pr) = parse_fields(s).unwrap() else {
+            panic!("{s} has neither steps nor negations");
+        };
+        assert_eq!(
+            exp.iter()
+                .map(|(start, end)| Range {
+                    start: *start,
+                    end: *end
+                })
+                .collect::<Vec<_>>(),
+            pr
+        );
+    }
+
+    #[test]
+    fn parse_pos_single() {
+        test_parse_pos("5", vec![(4, 5)]);
+        test_parse_pos("5,1", vec![(4, 5), (0, 1)]);
+    }
+
+    #[test]
+    fn parse_pos_range() {
+        test_parse_pos("9-15", vec![(8, 15)]);
+        test_parse_pos("9-15,20-31,8", vec![(8, 15), (19, 31), (7, 8)]);
+    }
+
+    #[test]
+    fn parse_pos_open_ranges() {
+        test_parse_pos("3-", vec![(2, usize::MAX)]);
+        test_parse_pos("-5", vec![(0, 5)]);
+        test_parse_pos("-5,7-", vec![(0, 5), (6, usize::MAX)]);
+        test_parse_pos("1-1", vec![(0, 1)]);
+    }
+
+    #[test]
+    fn parse_pos_merges_overlaps() {
+        test_parse_pos("1,1", vec![(0, 1), (0, 1)]);
+        test_parse_pos("1,1-2,1", vec![(0, 2)]);
+        test_parse_pos("9-15,14-31,8", vec![(8, 31), (7, 8)]);
+        test_parse_pos("3-", vec![(2, usize::MAX)]);
+        test_parse_pos("5,3-,1", vec![(2, usize::MAX), (0, 1)]);
+        // The merged range can reach ranges before the one it replaces
+        test_parse_pos("2,6,4,1-7", vec![(0, 7)]);
+        test_parse_pos("8,2,6,5-7", vec![(7, 8), (1, 2), (4, 7)]);
+    }
+
+    #[test]
+    fn parse_pos_gnu_errors() {
+        assert_err_str_contains!(parse_fields("2-1"), "invalid decreasing range");
+        assert_err_str_contains!(parse_fields("-"), "invalid range with no endpoint: -");
+        assert_err_str_contains!(parse_fields("1-2-3"), "invalid field range");
+        assert_err_str_contains!(parse_positions("1-2-3"), "invalid byte or character range");
+        assert_err_str_contains!(
+            parse_positions("0"),
+            "byte/character positions are numbered from 1"
+        );
+        assert_err_str_contains!(parse_positions("x"), "invalid byte/character position 'x'");
+        assert_err_str_contains!(
+            parse_fields("99999999999999999999999"),
+            "field number '99999999999999999999999' is too large"
+        );
+    }
+
+    #[test]
+    fn test_parse_pos_from_book() {
+        // The empty string is an error
+        assert_err!(parse_fields(""));
+
+        // Zero is an error
+        assert_err_str_contains!(parse_fields("0"), "fields are numbered from 1");
+        assert_err_str_contains!(parse_fields("0-1"), "fields are numbered from 1");
+
+        // A leading "+" is an error
+        assert_err_str_contains!(parse_fields("+1"), "invalid field value '+1'");
+        assert_err_str_contains!(parse_fields("+1-2"), "invalid field value '+1'");
+        assert_err_str_contains!(parse_fields("1-+2"), "invalid field value '+2'");
+
+        // Any non-number is an error
+        assert_err_str_contains!(parse_fields("a"), "invalid field value 'a'");
+        assert_err_str_contains!(parse_fields("1,a"), "invalid field value 'a'");
+        assert_err_str_contains!(parse_fields("1-a"), "invalid field value 'a'");
+        assert_err_str_contains!(parse_fields("a-1"), "invalid field value 'a'");
+
+        // Wonky ranges
+        assert_err!(parse_fields("-"));
+        assert_err!(parse_fields(","));
+        assert_err!(parse_fields("1,"));
+        assert_err!(parse_fields("1-1-1"));
+        assert_err!(parse_fields("1-1-a"));
+
+        // Ranges must not decrease
+        assert_err_str_contains!(parse_fields("2-1"), "invalid decreasing range");
+
+        // All the following are acceptable
+        let res = parse_fields("1");
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), PositionList::Ranges(vec![0..1]));
+        let res = parse_fields("01");
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), PositionList::Ranges(vec![0..1]));
+        let res = parse_fields("1,3");
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), PositionList::Ranges(vec![0..1, 2..3]));
+        let res = parse_fields("001,0003");
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), PositionList::Ranges(vec![0..1, 2..3]));
+        let res = parse_fields("1-3");
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), PositionList::Ranges(vec![0..3]));
+        let res = parse_fields("0001-03");
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), PositionList::Ranges(vec![0..3]));
+        let res = parse_fields("1,7,3-5");
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), PositionList::Ranges(vec![0..1, 6..7, 2..5]));
+        let res = parse_fields("15,19-20");
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), PositionList::Ranges(vec![14..15, 18..20]));
+    }
+
+    #[test]
+    fn parse_pos_steps_and_negations() {
+        let stepped = |range: Range<usize>, step| Stepped { range, step };
+        assert_eq!(
+            parse_fields("1-20:2,!3-5").unwrap(),
+            PositionList::Items(vec![
+                PositionItem::Include(stepped(0..20, 2)),
+                PositionItem::Exclude(stepped(2..5, 1)),
+            ])
+        );
+        assert_eq!(
+            parse_positions("!2,4-:3").unwrap(),
+            PositionList::Items(vec![
+                PositionItem::Exclude(stepped(1..2, 1)),
+                PositionItem::Include(stepped(3..usize::MAX, 3)),
+            ])
+        );
+        assert_err_str_contains!(parse_fields("1-9:0"), "invalid step '0'");
+        assert_err_str_contains!(parse_fields("1-9:+2"), "invalid step '+2'");
+        assert_err_str_contains!(parse_fields("1-9:"), "invalid step ''");
+        assert_err_str_contains!(parse_fields("!"), "fields are numbered from 1");
+        assert_err_str_contains!(parse_fields("!5-2"), "invalid decreasing range");
+    }
+
+    #[test]
+    fn test_position_list_resolve() {
+        let resolve = |pos, len| parse_fields(pos).unwrap().resolve(len);
+        assert_eq!(resolve("1-9:2", 6), [0, 2, 4]);
+        assert_eq!(resolve("1-:3", 10), [0, 3, 6, 9]);
+        assert_eq!(resolve("1-20:2,!3-5", 10), [0, 6, 8]);
+        // Only negations leave out of everything
+        assert_eq!(resolve("!2", 4), [0, 2, 3]);
+        assert_eq!(resolve("!1-,2", 4), [] as [usize; 0]);
+        // The order is kept, but nothing comes twice
+        assert_eq!(resolve("5,1-:2,!3", 6), [4, 0]);
+        assert_eq!(resolve("3-5", 4), [2, 3]);
+
+        let list = parse_fields("2-9:3,!8").unwrap();
+        let selected: Vec<_> = (0..12).filter(|&idx| list.contains(idx)).collect();
+        assert_eq!(selected, [1, 4]);
+    }
+
+    #[test]
+    fn test_selection_resolved_spans() {
+        let sel = Selection {
+            ranges: parse_positions("1-:2,!5,5-6").unwrap(),
+            complement: false,
+        };
+        assert_eq!(sel.spans(8).collect::<Vec<_>>(), [0..1, 2..3, 6..7, 5..6]);
+        let sel = Selection {
+            complement: true,
+            ..sel
+        };
+        assert_eq!(sel.spans(8).collect::<Vec<_>>(), [1..2, 3..5, 7..8]);
+        assert_eq!(bytes(b"abcdefgh", &sel), b"bdeh");
+    }
+
+    fn list(ranges: &[Range<usize>]) -> Selection {
+        Selection {
+            ranges: PositionList::Ranges(ranges.to_vec()),
+            complement: false,
+        }
+    }
+
+    fn chars(line: &str, char_pos: &Selection) -> String {
+        let mut out = Vec::new();
+        extract_chars(line, char_pos, &mut Vec::new(), &mut out);
+        String::from_utf8(out).unwrap()
+    }
+
+    fn bytes(record: &[u8], byte_pos: &Selection) -> Vec<u8> {
+        let mut out = Vec::new();
+        extract_bytes(record, byte_pos, &mut out);
+        out
+    }
+
+    #[test]
+    fn test_chars() {
+        assert_eq!(
+            chars("", &list(&[Range { start: 0, end: 1 }])),
+            "".to_string()
+        );
+        assert_eq!(chars("ábc", &list(&[0..1])), "á".to_string());
+        assert_eq!(chars("ábc", &list(&[0..1, 2..3])), "ác".to_string());
+        assert_eq!(chars("ábc", &list(&[0..3])), "ábc".to_string());
+        assert_eq!(chars("ábc", &list(&[2..3, 1..2])), "cb".to_string());
+        assert_eq!(chars("ábc", &list(&[0..1, 1..2, 4..5])), "áb".to_string());
+    }
+    #[test]
+    fn test_extract_bytes() {
+        let record = "ábc".as_bytes();
+        assert_eq!(bytes(record, &list(&[0..1])), b"\xc3");
+        assert_eq!(bytes(record, &list(&[0..2])), "á".as_bytes());
+        assert_eq!(bytes(record, &list(&[0..3])), "áb".as_bytes());
+        assert_eq!(bytes(record, &list(&[0..4])), "ábc".as_bytes());
+        assert_eq!(bytes(record, &list(&[3..4, 2..3])), b"cb");
+        assert_eq!(bytes(record, &list(&[0..2, 5..6])), "á".as_bytes());
+        // Binary records come out unchanged
+        assert_eq!(bytes(b"\x00\xff\n\xfe", &list(&[1..4])), b"\xff\n\xfe");
+    }
+
+    fn raw_fields(fields: &[&[u8]], fields_pos: &Selection) -> Vec<u8> {
+        let mut out = Vec::new();
+        extract_raw_fields(fields, fields_pos, b"|", &mut out);
+        out
+    }
+
+    /// What cutr prints for `input` with `args`.
+    fn cut(args: &[&str], input: &[u8]) -> Vec<u8> {
+        let args = parse_args(["cutr"].iter().chain(args).map(OsString::from));
+        let extract = build_extract(&args.extract, args.complement).unwrap();
+        let mut out = Vec::new();
+        extract_file("-", &mut io::Cursor::new(input), &extract, &args, &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_extract_whitespace_fields() {
+        let line = b"  root     1  0.0 /sbin/init  splash \n";
+        assert_eq!(cut(&["-w", "-f", "1"], line), b"root\n");
+        assert_eq!(cut(&["-w", "-f", "2-3"], line), b"1 0.0\n");
+        assert_eq!(cut(&["-w", "-f", "4,1"], line), b"/sbin/init root\n");
+        assert_eq!(cut(&["-w", "-f", "5-9"], line), b"splash\n");
+        assert_eq!(cut(&["-w", "-f", "2"], b"\tone\t\ttwo\n"), b"two\n");
+        assert_eq!(cut(&["-w", "-f", "1"], b"   \n"), b"   \n");
+        // Unicode whitespace splits too, bytes that aren't UTF-8 don't
+        assert_eq!(
+            cut(
+                &["-w", "-f", "2,3"],
+                "one\u{a0}two\u{3000}th\u{e9}ree\n".as_bytes()
+            ),
+            "two th\u{e9}ree\n".as_bytes()
+        );
+        assert_eq!(
+            cut(&["-w", "-f", "2"], b"\xe3 \x80\x80\xff x\n"),
+            b"\x80\x80\xff\n"
+        );
+    }
+
+    #[test]
+    fn test_extract_fields() {
+        let rec = StringRecord::from(vec!["Captain", "Sham", "12345"]);
+        assert_eq!(extract_fields(&rec, &list(&[0..1])), &["Captain"]);
+        assert_eq!(extract_fields(&rec, &list(&[1..2])), &["Sham"]);
+        assert_eq!(
+            extract_fields(&rec, &list(&[0..1, 2..3])),
+            &["Captain", "12345"]
+        );
+        assert_eq!(extract_fields(&rec, &list(&[0..1, 3..4])), &["Captain"]);
+        assert_eq!(
+            extract_fields(&rec, &list(&[1..2, 0..1])),
+            &["Sham", "Captain"]
+        );
+    }
+
+    #[test]
+    fn test_extract_raw_fields() {
+        let line = br#"1,"a,b",x"#;
+        let fields: Vec<_> = Delimiter::Byte(b',').split(line).collect();
+        assert_eq!(fields, [&b"1"[..], b"\"a", b"b\"", b"x"]);
+        assert_eq!(raw_fields(&fields, &list(&[1..3])), br#""a|b""#);
+        assert_eq!(raw_fields(&fields, &list(&[3..4, 0..1])), b"x|1");
+        assert_eq!(raw_fields(&fields, &list(&[5..6])), b"");
+        assert_eq!(Delimiter::Byte(b',').split(b"").count(), 1);
+    }
+
+    #[test]
+    fn test_split_str_delimiter() {
+        let delimiter = Delimiter::Str(b"::".to_vec());
+        let fields: Vec<_> = delimiter.split(b"a::b:c::::d::").collect();
+        assert_eq!(fields, [&b"a"[..], b"b:c", b"", b"d", b""]);
+        assert_eq!(delimiter.split(b"").collect::<Vec<_>>(), [b""]);
+        assert_eq!(delimiter.split(b"none").count(), 1);
+    }
+
+    #[test]
+    fn test_selection_spans() {
+        let sel = list(&[4..6, 0..2, 1..3, 8..usize::MAX]);
+        assert_eq!(sel.spans(10).collect::<Vec<_>>(), [4..6, 0..2, 1..3, 8..10]);
+        assert_eq!(sel.spans(5).collect::<Vec<_>>(), [4..5, 0..2, 1..3]);
+
+        // The runs of a complement are the gaps, however the ranges overlap
+        let sel = Selection {
+            complement: true,
+            ..sel
+        };
+        assert_eq!(sel.spans(10).collect::<Vec<_>>(), [3..4, 6..8]);
+        assert_eq!(sel.spans(7).collect::<Vec<_>>(), [3..4, 6..7]);
+        assert_eq!(sel.spans(2).count(), 0);
+        for len in 0..12 {
+            let selected: Vec<_> = (0..len).filter(|&idx| sel.contains(idx)).collect();
+            assert_eq!(sel.indices(len).collect::<Vec<_>>(), selected);
+        }
+    }
+
+    #[test]
+    fn test_selection_complement() {
+        let sel = Selection {
+            ranges: PositionList::Ranges(vec![1..2, 3..usize::MAX]),
+            complement: true,
+        };
+        assert!(sel.contains(0) && sel.contains(2));
+        assert!(!sel.contains(1) && !sel.contains(1000));
+        assert_eq!(sel.indices(6).collect::<Vec<_>>(), [0, 2]);
+        assert_eq!(chars("ábcde", &sel), "ác");
+
+        let rec = StringRecord::from(vec!["Captain", "Sham", "12345"]);
+        let sel = Selection {
+            ranges: PositionList::Ranges(vec![2..3, 0..1]),
+            complement: true,
+        };
+        assert_eq!(extract_fields(&rec, &sel), &["Sham"]);
+        assert_eq!(bytes("ábc".as_bytes(), &sel), b"\xa1c");
+    }
+
+    #[test]
+    fn test_field_stats() {
+        let mut stats = FieldStats::default();
+        ["3", "", "x", "-1.5", "3"]
+            .iter()
+            .for_each(|v| stats.add(v));
+        assert_eq!(stats.non_empty, 4);
+        assert_eq!(stats.distinct.len(), 4);
+        assert_eq!((stats.min_len, stats.max_len), (Some(0), Some(4)));
+        assert_eq!(stats.numeric, Some((-1.5, 4.5, 3.0, 3)));
+
+        let mut stats = FieldStats::default();
+        (0..=DISTINCT_CAP).for_each(|n| stats.add(&n.to_string()));
+        assert_eq!(stats.distinct.len(), DISTINCT_CAP);
+        assert!(stats.distinct_overflow);
+    }
+
+    #[test]
+    fn test_stats_add_record() {
+        let mut stats = Stats::default();
+        stats.add_record(["a", "b", "c", "d"], &list(&[0..1, 2..4]));
+        assert_eq!(stats.fields.keys().copied().collect::<Vec<_>>(), [0, 2, 3]);
+    }
+}
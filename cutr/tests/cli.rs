@@ -8,6 +8,8 @@ use std::fs;
 const CSV: &str = "tests/inputs/movies1.csv";
 const TSV: &str = "tests/inputs/movies1.tsv";
 const BOOKS: &str = "tests/inputs/books.tsv";
+const QUOTES: &str = "tests/inputs/quotes.csv";
+const PS: &str = "tests/inputs/ps.txt";
 
 // --------------------------------------------------
 fn random_string() -> String {
@@ -36,11 +38,49 @@ fn skips_bad_file() -> Result<()> {
     cargo_bin_cmd!()
         .args(["-f", "1", CSV, &bad, TSV])
         .assert()
-        .success()
+        .failure()
+        .code(1)
+        .stdout(predicate::str::contains("The Blues Brothers").count(2))
         .stderr(predicate::str::is_match(expected)?);
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+fn bad_line_fails_but_continues() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-c", "1"])
+        .write_stdin(&b"ab\n\xff\ncd\n"[..])
+        .assert()
+        .failure()
+        .code(1)
+        .stdout("a\nc\n")
+        .stderr(predicate::str::contains("-: bad line"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_on_write_error() -> Result<()> {
+    let full = fs::File::create("/dev/full")?;
+    for args in [
+        &["-f", "1", TSV][..],
+        &["--csv", "-f", "1", CSV],
+        &["--stats", "-f", "1", TSV],
+    ] {
+        let output = std::process::Command::new(assert_cmd::cargo::cargo_bin!())
+            .args(args)
+            .stdout(full.try_clone()?)
+            .output()?;
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(
+            String::from_utf8(output.stderr)?,
+            "cutr: write error: No space left on device (os error 28)\n"
+        );
+    }
+    Ok(())
+}
+
 // --------------------------------------------------
 fn dies(args: &[&str], expected: &str) -> Result<()> {
     cargo_bin_cmd!()
@@ -85,19 +125,82 @@ fn dies_bad_digit_chars() -> Result<()> {
 // --------------------------------------------------
 #[test]
 fn dies_empty_delimiter() -> Result<()> {
-    dies(&[CSV, "-f", "1", "-d", ""], r#"must be a single byte"#)
+    dies(
+        &[CSV, "-f", "1", "-d", ""],
+        "the delimiter must not be empty",
+    )
 }
 
 // --------------------------------------------------
 #[test]
 fn dies_bad_delimiter() -> Result<()> {
-    dies(&[CSV, "-f", "1", "-d", ",,"], r#"must be a single byte"#)
+    dies(
+        &[CSV, "--csv", "-f", "1", "-d", ",,"],
+        "the delimiter must be a single byte with --csv",
+    )?;
+    dies(
+        &[CSV, "-f", "1", "-d", r"\q"],
+        "unknown escape sequence ‘\\q’",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn unicode_delimiter() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-d", "🏰", "-f", "2,3"])
+        .write_stdin(
+            "a🏰b🏰c
+no castle
+",
+        )
+        .assert()
+        .success()
+        .stdout(
+            "b🏰c
+no castle
+",
+        );
+    Ok(())
 }
 
 // --------------------------------------------------
 #[test]
-fn dies_unicode_delimiter() -> Result<()> {
-    dies(&[CSV, "-f", "1", "-d", "🏰"], r#"must be a single byte"#)
+fn string_delimiter() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-d", "::", "-f", "3,1", "--output-delimiter", r"\t"])
+        .write_stdin(
+            "a::b:c::d
+::x
+",
+        )
+        .assert()
+        .success()
+        .stdout("d\ta\n\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn escaped_delimiters() -> Result<()> {
+    run(
+        &[TSV, "-d", r"\t", "-f", "2"],
+        "tests/expected/movies1.tsv.f2.out",
+    )?;
+    cargo_bin_cmd!()
+        .args(["-d", r"\0", "-f", "2"])
+        .write_stdin("one\0two\0three\n")
+        .assert()
+        .success()
+        .stdout("two\n");
+    // A lone backslash is the delimiter, as for ‘cut’
+    cargo_bin_cmd!()
+        .args(["-d", "\\", "-f", "2"])
+        .write_stdin("one\\two\\three\n")
+        .assert()
+        .success()
+        .stdout("two\n");
+    Ok(())
 }
 
 // --------------------------------------------------
@@ -152,14 +255,11 @@ fn run(args: &[&str], expected_file: &str) -> Result<()> {
 }
 
 // --------------------------------------------------
-fn run_lossy(args: &[&str], expected_file: &str) -> Result<()> {
-    let contents = fs::read(expected_file)?;
-    let expected = String::from_utf8_lossy(&contents);
+fn run_bytes(args: &[&str], expected_file: &str) -> Result<()> {
+    let expected = fs::read(expected_file)?;
     let output = cargo_bin_cmd!().args(args).output().expect("fail");
     assert!(output.status.success());
-
-    let stdout = String::from_utf8(output.stdout).expect("invalid UTF-8");
-    assert_eq!(stdout, expected);
+    assert_eq!(output.stdout, expected);
     Ok(())
 }
 
@@ -268,7 +368,7 @@ fn tsv_b2() -> Result<()> {
 // --------------------------------------------------
 #[test]
 fn tsv_b8() -> Result<()> {
-    run_lossy(&[TSV, "-b", "8"], "tests/expected/movies1.tsv.b8.out")
+    run_bytes(&[TSV, "-b", "8"], "tests/expected/movies1.tsv.b8.out")
 }
 
 // --------------------------------------------------
@@ -286,7 +386,7 @@ fn tsv_b2_3() -> Result<()> {
 // --------------------------------------------------
 #[test]
 fn tsv_b1_8() -> Result<()> {
-    run_lossy(&[TSV, "-b", "1-8"], "tests/expected/movies1.tsv.b1-8.out")
+    run_bytes(&[TSV, "-b", "1-8"], "tests/expected/movies1.tsv.b1-8.out")
 }
 
 // --------------------------------------------------
@@ -330,3 +430,457 @@ fn tsv_c1_8() -> Result<()> {
 fn repeated_value() -> Result<()> {
     run(&[BOOKS, "-c", "1,1"], "tests/expected/books.c1,1.out")
 }
+
+// --------------------------------------------------
+#[test]
+fn csv_quote_style_always() -> Result<()> {
+    run(
+        &[
+            QUOTES,
+            "--csv",
+            "-f",
+            "2",
+            "-d",
+            ",",
+            "--quote-style",
+            "always",
+        ],
+        "tests/expected/quotes.csv.f2.always.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn csv_quote_style_never() -> Result<()> {
+    run(
+        &[
+            QUOTES,
+            "--csv",
+            "-f",
+            "2",
+            "-d",
+            ",",
+            "--quote-style",
+            "never",
+        ],
+        "tests/expected/quotes.csv.f2.never.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn csv_escape() -> Result<()> {
+    run(
+        &[
+            "tests/inputs/quotes-escaped.csv",
+            "--csv",
+            "-f",
+            "1-2",
+            "-d",
+            ",",
+            "--escape",
+            "\\",
+        ],
+        "tests/expected/quotes-escaped.csv.f1-2.escape.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn csv_quote_char() -> Result<()> {
+    run(
+        &[
+            "tests/inputs/quotes-single.csv",
+            "--csv",
+            "-f",
+            "2",
+            "-d",
+            ",",
+            "--quote",
+            "'",
+        ],
+        "tests/expected/quotes-single.csv.f2.quote.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_quote_style() -> Result<()> {
+    dies(
+        &[QUOTES, "--csv", "-f", "1", "--quote-style", "sometimes"],
+        "invalid value 'sometimes'",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn whitespace_fields() -> Result<()> {
+    run(&[PS, "-w", "-f", "1,4"], "tests/expected/ps.txt.w.f1,4.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn whitespace_fields_output_delimiter() -> Result<()> {
+    run(
+        &[PS, "--whitespace", "-f", "2,1", "--output-delimiter", ","],
+        "tests/expected/ps.txt.w.f2,1.comma.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_whitespace_with_delimiter() -> Result<()> {
+    dies(
+        &[PS, "-w", "-d", ",", "-f", "1"],
+        "'--whitespace' cannot be used with '--delimiter <DELIMITER>'",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_whitespace_with_bytes() -> Result<()> {
+    dies(&[PS, "-w", "-b", "1"], "--fields <FIELDS>")
+}
+
+// --------------------------------------------------
+#[test]
+fn books_stats() -> Result<()> {
+    run(
+        &["--stats", "-f", "1-3", BOOKS],
+        "tests/expected/books.tsv.f1-3.stats.out",
+    )
+}
+
+#[test]
+fn ps_whitespace_stats() -> Result<()> {
+    run(
+        &["--stats", "-w", "-f", "2,3", PS],
+        "tests/expected/ps.txt.w.f2,3.stats.out",
+    )
+}
+
+#[test]
+fn dies_stats_with_chars() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--stats", "-c", "1", CSV])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_f2_open_end() -> Result<()> {
+    run(&[TSV, "-f", "2-"], "tests/expected/movies1.tsv.f2-3.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_c_open_start() -> Result<()> {
+    run(&[TSV, "-c", "-2"], "tests/expected/movies1.tsv.c1-2.out")?;
+    run(&[TSV, "--chars=-2"], "tests/expected/movies1.tsv.c1-2.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_f_open_start() -> Result<()> {
+    run(&[TSV, "-f", "-2"], "tests/expected/movies1.tsv.f1-2.out")?;
+    run(&[TSV, "-f-2"], "tests/expected/movies1.tsv.f1-2.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_b_open_start() -> Result<()> {
+    run(&[TSV, "-b", "-2"], "tests/expected/movies1.tsv.b1-2.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_f_overlapping_ranges() -> Result<()> {
+    run(
+        &[TSV, "-f", "1-2,2-3"],
+        "tests/expected/movies1.tsv.f1-3.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_decreasing_range() -> Result<()> {
+    dies(&[TSV, "-f", "3-2"], "invalid decreasing range")?;
+    dies(
+        &[TSV, "-c", "0-2"],
+        "byte/character positions are numbered from 1",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_f2_complement() -> Result<()> {
+    run(
+        &[TSV, "-f", "2", "--complement"],
+        "tests/expected/movies1.tsv.f2.complement.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn books_c_open_complement() -> Result<()> {
+    run(
+        &[BOOKS, "--complement", "-c", "2-"],
+        "tests/expected/books.c2-.complement.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn books_tsv_to_csv() -> Result<()> {
+    run(
+        &[BOOKS, "--csv", "-f", "3,1", "--output-delimiter", ","],
+        "tests/expected/books.tsv.f3,1.csv.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_long_output_delimiter() -> Result<()> {
+    dies(
+        &[BOOKS, "--csv", "-f", "1", "--output-delimiter", "::"],
+        "the output delimiter must be a single byte with --csv",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn non_delimited_lines() -> Result<()> {
+    let input = "a\tb\tc\nno tabs here\nd\te\n";
+    cargo_bin_cmd!()
+        .args(["-f", "2"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("b\nno tabs here\ne\n");
+    cargo_bin_cmd!()
+        .args(["-f", "2", "--only-delimited"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("b\ne\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn whitespace_only_delimited() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-w", "-s", "-f", "2"])
+        .write_stdin("one two\nalone\n  three   four \n")
+        .assert()
+        .success()
+        .stdout("two\nfour\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_only_delimited_with_chars() -> Result<()> {
+    dies(&[TSV, "-s", "-c", "1"], "--chars <CHARS>")
+}
+
+// --------------------------------------------------
+#[test]
+fn quotes_split_raw() -> Result<()> {
+    run(
+        &[QUOTES, "-d", ",", "-f", "2"],
+        "tests/expected/quotes.csv.f2.raw.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn raw_long_output_delimiter() -> Result<()> {
+    run(
+        &[
+            "tests/inputs/books.csv",
+            "-d",
+            ",",
+            "-f",
+            "1,3",
+            "--output-delimiter",
+            " :: ",
+        ],
+        "tests/expected/books.csv.f1,3.raw.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn raw_keeps_invalid_utf8() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-f", "2"])
+        .write_stdin(&b"a\t\xff\xfe\tc\n"[..])
+        .assert()
+        .success()
+        .stdout(&b"\xff\xfe\n"[..]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_quote_without_csv() -> Result<()> {
+    dies(&[QUOTES, "-f", "1", "--quote", "'"], "--csv")
+}
+
+// --------------------------------------------------
+#[test]
+fn zero_terminated_bytes_binary() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-z", "-b", "2-3"])
+        .write_stdin(&b"ab\0\xff\xfexy\0"[..])
+        .assert()
+        .success()
+        .stdout(&b"b\0\xfex\0"[..]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn fixed_width_binary_records() -> Result<()> {
+    // Newlines and invalid UTF-8 inside the records are just bytes
+    cargo_bin_cmd!()
+        .args(["-z", "-b", "5-8", "tests/inputs/records.bin"])
+        .assert()
+        .success()
+        .stdout(&b"\n\xff\x7f\x01\0\x10\n\xfe\x80\0"[..]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn zero_terminated_fields() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--zero-terminated", "-f", "2"])
+        .write_stdin("a\tb\nc\0d\te\0no tab\0")
+        .assert()
+        .success()
+        .stdout("b\nc\0e\0no tab\0");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn zero_terminated_csv() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--csv", "-d", ",", "-z", "-f", "2,3"])
+        .write_stdin("a,\"x\0y\",c\0d,e\0")
+        .assert()
+        .success()
+        .stdout("\"x\0y\",c\0e\0");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn skip_preamble() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--skip", "2", "-f", "2", "tests/inputs/preamble.tsv"])
+        .assert()
+        .success()
+        .stdout("b\nd\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn skip_passthrough_header_each_file() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--skip=1", "--passthrough-header", "-f", "2", TSV, TSV])
+        .assert()
+        .success()
+        .stdout(
+            "title\tyear\tdirector\n1980\n2019\n\
+             title\tyear\tdirector\n1980\n2019\n",
+        );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn skip_more_than_the_file() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--skip", "10", "-c", "1", TSV])
+        .assert()
+        .success()
+        .stdout("");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn skip_header_stats() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--skip", "1", "--stats", "-f", "2", TSV])
+        .assert()
+        .success()
+        .stdout(
+            "field\tnon-empty\tdistinct\tmin-len\tmax-len\tmin\tmean\tmax\n\
+             2\t2\t2\t4\t4\t1980\t1999.50\t2019\n",
+        );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_passthrough_header_without_skip() -> Result<()> {
+    dies(&[TSV, "-f", "1", "--passthrough-header"], "--skip <N>")
+}
+
+// --------------------------------------------------
+#[test]
+fn step_ranges() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-d", ",", "-f", "1-7:2"])
+        .write_stdin("1,2,3,4,5,6,7,8\na,b,c\n")
+        .assert()
+        .success()
+        .stdout("1,3,5,7\na,c\n");
+    cargo_bin_cmd!()
+        .args(["-c", "2-:3"])
+        .write_stdin("abcdefgh\n")
+        .assert()
+        .success()
+        .stdout("beh\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn negated_ranges() -> Result<()> {
+    let input = "1,2,3,4,5,6\n";
+    cargo_bin_cmd!()
+        .args(["-d", ",", "-f", "!2-3"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("1,4,5,6\n");
+    cargo_bin_cmd!()
+        .args(["-d", ",", "-f", "1-:2,!3"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("1,5\n");
+    // The complement of what the whole list selects
+    cargo_bin_cmd!()
+        .args(["-d", ",", "-f", "1-:2,!3", "--complement"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("2,3,4,6\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_step() -> Result<()> {
+    dies(&[TSV, "-f", "1-5:0"], "invalid step '0'")
+}
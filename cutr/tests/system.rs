@@ -0,0 +1,69 @@
+//! cutr against GNU cut on generated input, where GNU cut is installed.
+
+use std::sync::LazyLock;
+
+use learnr::compare::{Reference, ascii_text, text};
+use proptest::{
+    collection::{btree_set, vec},
+    prelude::*,
+};
+
+static CUT: LazyLock<Option<Reference>> =
+    LazyLock::new(|| Reference::find(assert_cmd::cargo::cargo_bin!("cutr"), "cut"));
+
+/// A list like ‘1,3-5,7-’. cutr prints positions in the order they are
+/// listed and GNU cut in the order of the line, so starts only go up.
+fn list() -> impl Strategy<Value = String> {
+    let starts = btree_set(1..10_usize, 1..5);
+    // How far each range goes past its start, 0 for a single position and 4
+    // for no end
+    let lengths = vec(0..5_usize, 5);
+    (starts, lengths).prop_map(|(starts, lengths)| {
+        starts
+            .into_iter()
+            .zip(lengths)
+            .map(|(start, length)| match length {
+                0 => start.to_string(),
+                4 => format!("{start}-"),
+                length => format!("{start}-{}", start + length),
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    })
+}
+
+proptest! {
+    #[test]
+    fn fields_same_as_cut(
+        input in text(),
+        list in list(),
+        only_delimited in any::<bool>(),
+        complement in any::<bool>(),
+    ) {
+        if let Some(cut) = &*CUT {
+            let mut args = vec!["-d,".to_string(), format!("-f{list}")];
+            if only_delimited {
+                args.push("-s".to_string());
+            }
+            if complement {
+                args.push("--complement".to_string());
+            }
+            cut.check(&args, &input)?;
+        }
+    }
+
+    #[test]
+    fn bytes_same_as_cut(input in text(), list in list()) {
+        if let Some(cut) = &*CUT {
+            cut.check(&[format!("-b{list}")], &input)?;
+        }
+    }
+
+    // GNU cut takes characters to be bytes
+    #[test]
+    fn chars_same_as_cut(input in ascii_text(), list in list()) {
+        if let Some(cut) = &*CUT {
+            cut.check(&[format!("-c{list}")], &input)?;
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use std::{
+    ffi::OsString,
+    io::{self, Write},
+};
+
+use clap::{Arg, ArgAction, Command, value_parser};
+
+/// The command line that [`main`] parses, also used for the man page.
+pub fn command() -> Command {
+    Command::new("echor")
+        .version("0.1.0")
+        .author("me")
+        .about("Rust echo")
+        .arg(
+            Arg::new("text")
+                .value_name("TEXT")
+                .help("Input text")
+                .required(true)
+                .num_args(1..)
+                .value_parser(value_parser!(OsString)),
+        )
+        .arg(
+            Arg::new("omit_newline")
+                .short('n')
+                .help("Do not print newline")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+/// What the man page tells beyond --help.
+pub const MAN_DESCRIPTION: &str = "\
+Each TEXT is printed, separated by single spaces and followed by a newline \
+unless -n is given. Backslash escapes are printed as they are.";
+
+pub fn main(args: impl IntoIterator<Item = OsString>) {
+    let matches = command().get_matches_from(args);
+    let text: Vec<_> = matches
+        .get_many::<OsString>("text")
+        .unwrap()
+        .map(|text| text.to_string_lossy())
+        .collect();
+    let omit_newline = matches.get_flag("omit_newline");
+    let ending = if omit_newline { "" } else { "\n" };
+    if let Err(err) = write!(io::stdout(), "{}{}", text.join(" "), ending) {
+        let err = anyhow::Error::from(err);
+        learnr::exit_on_broken_pipe(&err);
+        eprintln!("echor: {err}");
+        std::process::exit(1);
+    }
+}
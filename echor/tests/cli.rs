@@ -10,7 +10,7 @@ fn dies_no_args() -> TestResult {
     let mut cmd = cargo_bin_cmd!("echor");
     cmd.assert()
         .failure()
-        .stderr(predicate::str::contains("USAGE"));
+        .stderr(predicate::str::contains("Usage"));
     Ok(())
 }
 
@@ -0,0 +1,87 @@
+//! Times findr over a synthetic tree of 100k files, with predicates that do
+//! and don't need a stat of each entry.
+//!
+//! Set e.g. `FINDR_BENCH_FILES=1000000` for a bigger tree:
+//! `FINDR_BENCH_FILES=1000000 cargo bench -p findr`.
+
+use std::{
+    fs::{self, File},
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+
+/// Files per directory of the generated tree
+const DIR_SIZE: usize = 1000;
+
+/// Each expression is run by both programs, so that they can be compared
+const EXPRESSIONS: [(&str, &[&str], &[&str]); 3] = [
+    (
+        "name",
+        &["--name", r"^f1\d*\.txt$"],
+        &["-regex", r".*/f1[0-9]*\.txt"],
+    ),
+    ("type", &["--type", "d"], &["-type", "d"]),
+    (
+        "total size",
+        &["--total-size", "--type", "f"],
+        &["-type", "f", "-size", "+0"],
+    ),
+];
+
+fn generate(root: &Path, files: usize) {
+    for dir in 0..files.div_ceil(DIR_SIZE) {
+        let dir = root.join(format!("d{dir}"));
+        fs::create_dir(&dir).expect("create bench dir");
+        for file in 0..DIR_SIZE {
+            File::create(dir.join(format!("f{file}.txt"))).expect("create bench file");
+        }
+    }
+}
+
+fn run_find(cmd: &mut Command) {
+    let status = cmd.stdout(Stdio::null()).status().expect("run find");
+    assert!(status.success());
+}
+
+fn bench_find(c: &mut Criterion) {
+    let files = std::env::var("FINDR_BENCH_FILES")
+        .map(|s| s.parse().expect("FINDR_BENCH_FILES"))
+        .unwrap_or(100_000);
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    generate(dir.path(), files);
+
+    let have_gnu_find = Command::new("find")
+        .arg("--version")
+        .stdout(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success());
+
+    for (name, findr_args, find_args) in EXPRESSIONS {
+        let mut group = c.benchmark_group(format!("find {name}"));
+        group.sample_size(10);
+        group.throughput(Throughput::Elements(files as u64));
+
+        group.bench_function("findr", |b| {
+            b.iter(|| {
+                run_find(
+                    Command::new(env!("CARGO_BIN_EXE_findr"))
+                        .arg(dir.path())
+                        .args(findr_args),
+                )
+            })
+        });
+        if have_gnu_find {
+            group.bench_function("GNU find", |b| {
+                b.iter(|| run_find(Command::new("find").arg(dir.path()).args(find_args)))
+            });
+        }
+
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_find);
+criterion_main!(benches);
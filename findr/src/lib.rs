@@ -0,0 +1,365 @@
+use std::{
+    borrow::Cow,
+    ffi::OsStr,
+    ffi::OsString,
+    fs::FileType,
+    io::{self, BufRead, Write},
+    os::unix::ffi::OsStrExt,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow};
+use clap::{CommandFactory, Parser, ValueEnum, builder::PossibleValue};
+use learnr::{CLIInput, open};
+use regex::Regex;
+use tracing::{debug, debug_span};
+use walkdir::WalkDir;
+
+/// ‘find’ implementation in Rust
+#[derive(Debug, Parser)]
+#[command(version, about, author)]
+struct Args {
+    /// Starting points for search
+    #[arg(default_value = ".", value_name = "starting-point")]
+    paths: Vec<String>,
+
+    /// Expressions
+    #[arg(value_name = "expression", long("name"), short('n'), num_args(0..))]
+    names: Option<Vec<Regex>>,
+
+    /// File types
+    #[arg(long("type"), short('t'), value_name("TYPE"), num_args(0..))]
+    entry_types: Option<Vec<EntryType>>,
+
+    /// Read NUL-separated starting points from FILE (‘-’ for stdin)
+    #[arg(long, value_name = "FILE", conflicts_with = "paths")]
+    files0_from: Option<CLIInput>,
+
+    /// Separate printed paths with NUL instead of newline
+    #[arg(long)]
+    print0: bool,
+
+    /// Print only the total size and number of the regular files matched
+    #[arg(long)]
+    total_size: bool,
+
+    /// With --total-size, also print each match
+    #[arg(short, long, requires = "total_size")]
+    verbose: bool,
+
+    /// Print matches as canonical absolute paths, with symlinks resolved in
+    /// the directories leading to them; a matched symlink stays itself
+    #[arg(long, conflicts_with = "relative_to")]
+    absolute: bool,
+
+    /// Print matches relative to DIR
+    #[arg(long, value_name = "DIR")]
+    relative_to: Option<PathBuf>,
+
+    /// Drop the leading ‘./’ from printed paths
+    #[arg(long)]
+    no_leading_dot_slash: bool,
+
+    /// Skip hidden files and directories, whose names start with ‘.’, the
+    /// way ripgrep and fd do
+    #[arg(long)]
+    smart: bool,
+
+    /// With --smart, search hidden files and directories after all
+    #[arg(long, requires = "smart")]
+    hidden: bool,
+
+    /// Neither search nor print directories whose name matches NAME, a
+    /// regular expression like those of --name; may be repeated
+    #[arg(long, value_name = "NAME")]
+    prune: Vec<Regex>,
+
+    /// Explain on stderr why each entry is printed or skipped (RUST_LOG
+    /// allows finer filters)
+    #[arg(long)]
+    debug: bool,
+}
+
+/// Running totals for --total-size
+#[derive(Debug, Default)]
+struct Totals {
+    files: u64,
+    bytes: u64,
+}
+
+impl std::fmt::Display for Totals {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let noun = if self.files == 1 { "file" } else { "files" };
+        write!(
+            f,
+            "total {} in {} {noun}",
+            learnr::format_human_size(self.bytes),
+            self.files
+        )
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+enum EntryType {
+    Dir,
+    File,
+    Link,
+}
+
+impl ValueEnum for EntryType {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[EntryType::Dir, EntryType::File, EntryType::Link]
+    }
+
+    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+        Some(match self {
+            EntryType::Dir => PossibleValue::new("d"),
+            EntryType::File => PossibleValue::new("f"),
+            EntryType::Link => PossibleValue::new("l"),
+        })
+    }
+}
+
+/// The command line that [`main`] parses, for generating the man page.
+pub fn command() -> clap::Command {
+    Args::command()
+}
+
+/// What the man page tells beyond --help.
+pub const MAN_DESCRIPTION: &str = "\
+Every starting point is searched recursively, and each path below it that \
+matches all the tests is printed. Names given with --name are regular \
+expressions matched against the file name alone, and a path matches when any \
+one of them does; --type likewise accepts any of the types given.\n\n\
+With --smart, hidden files and directories are skipped before any test is \
+tried, and nothing below a hidden directory is searched, whatever it is \
+named; --hidden turns that off again. Starting points are searched even when \
+their own name starts with ‘.’, like ‘.config’ or ‘.’ itself. Without \
+--smart, hidden entries are found like any other, as find does.\n\n\
+A directory matched by --prune is left out along with everything below it, \
+before --smart and --hidden are even considered: --hidden never brings a \
+pruned directory back. Like --smart, --prune only applies below the starting \
+points, which are always searched.\n\n\
+Directories that can't be read are reported on standard error, and the search \
+goes on.";
+
+pub fn main(args: impl IntoIterator<Item = OsString>) -> Result<()> {
+    let args = Args::parse_from(args);
+    learnr::init_tracing(args.debug);
+    let mut totals = Totals::default();
+    match &args.files0_from {
+        Some(input) => {
+            let list = open(input)?;
+            for path in list.split(b'\0') {
+                let path = path?;
+                if path.is_empty() {
+                    eprintln!("{input}: invalid zero-length file name");
+                    continue;
+                }
+                find(OsStr::from_bytes(&path), &args, &mut totals)?;
+            }
+        }
+        None => {
+            for path in &args.paths {
+                find(path, &args, &mut totals)?;
+            }
+        }
+    }
+    if args.total_size {
+        writeln!(io::stdout(), "{totals}")?;
+    }
+    Ok(())
+}
+
+fn find(start: impl AsRef<Path>, args: &Args, totals: &mut Totals) -> Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    let _span = debug_span!("open", path = %start.as_ref().display()).entered();
+    let skip_hidden = args.smart && !args.hidden;
+    let walk = WalkDir::new(start).into_iter().filter_entry(|entry| {
+        // Starting points are searched whatever their name
+        if entry.depth() == 0 {
+            return true;
+        }
+        let name = entry.file_name().to_string_lossy();
+        if entry.file_type().is_dir() && args.prune.iter().any(|re| re.is_match(&name)) {
+            debug!(path = %entry.path().display(), "skipped, pruned by --prune");
+            return false;
+        }
+        if skip_hidden && name.starts_with('.') {
+            debug!(path = %entry.path().display(), "skipped, hidden under --smart");
+            return false;
+        }
+        true
+    });
+    for entry in walk {
+        match entry {
+            Ok(entry) => {
+                let path = entry_filename(&entry)?;
+                let _span = debug_span!("match", path = %entry.path().display()).entered();
+
+                // The type comes with the directory listing, so only the size
+                // of a file needs a stat of its own
+                if !select_name(path, &args.names) {
+                    debug!("skipped, no --name matches");
+                    continue;
+                }
+                if !select_type(entry.file_type(), &args.entry_types) {
+                    debug!("skipped, --type doesn't match");
+                    continue;
+                }
+                debug!("selected");
+                if args.total_size && entry.file_type().is_file() {
+                    // The file may be gone since it was listed, which is no
+                    // reason to stop the walk
+                    let metadata = match entry.metadata() {
+                        Ok(metadata) => metadata,
+                        Err(err) => {
+                            eprintln!("{}: {err}", entry.path().display());
+                            continue;
+                        }
+                    };
+                    totals.files += 1;
+                    totals.bytes += metadata.len();
+                }
+                if args.total_size && !args.verbose {
+                    continue;
+                }
+                let path = match format_path(entry.path(), args) {
+                    Ok(path) => path,
+                    Err(err) => {
+                        eprintln!("{}: {err}", entry.path().display());
+                        continue;
+                    }
+                };
+                if args.print0 {
+                    stdout.write_all(path.as_os_str().as_bytes())?;
+                    stdout.write_all(b"\0")?;
+                } else {
+                    writeln!(stdout, "{}", path.display())?;
+                }
+            }
+            Err(err) => eprint!("{err}"),
+        }
+    }
+    Ok(())
+}
+
+/// Rewrites a match into the form asked for by --absolute, --relative-to
+/// and --no-leading-dot-slash, after all the predicates have selected it.
+fn format_path<'a>(path: &'a Path, args: &Args) -> Result<Cow<'a, Path>> {
+    let path = if args.absolute {
+        Cow::Owned(canonical_path(path)?)
+    } else if let Some(base) = &args.relative_to {
+        Cow::Owned(relative_path(
+            &std::path::absolute(path)?,
+            &std::path::absolute(base)?,
+        ))
+    } else {
+        Cow::Borrowed(path)
+    };
+    if args.no_leading_dot_slash
+        && let Ok(stripped) = path.strip_prefix(".")
+        && !stripped.as_os_str().is_empty()
+    {
+        return Ok(Cow::Owned(stripped.to_path_buf()));
+    }
+    Ok(path)
+}
+
+/// The canonical absolute form of the directory `path` is in, joined with its
+/// name, so that a symlink is named rather than resolved to its target, which
+/// may not even exist.
+fn canonical_path(path: &Path) -> io::Result<PathBuf> {
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => {
+            let parent = if parent.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                parent
+            };
+            Ok(parent.canonicalize()?.join(name))
+        }
+        // ‘/’, ‘.’ and paths ending in ‘..’ name a directory, never a link
+        _ => path.canonicalize(),
+    }
+}
+
+/// Lexically computes `path` relative to `base`; both must be absolute.
+/// Symlinks are not resolved, so ‘..’ is taken to mean the parent directory
+/// as written.
+fn relative_path(path: &Path, base: &Path) -> PathBuf {
+    fn normalize(path: &Path) -> Vec<Component<'_>> {
+        let mut result = vec![];
+        for component in path.components() {
+            match component {
+                Component::CurDir => (),
+                Component::ParentDir if result.len() > 1 => {
+                    result.pop();
+                }
+                Component::ParentDir => (),
+                _ => result.push(component),
+            }
+        }
+        result
+    }
+    let path = normalize(path);
+    let base = normalize(base);
+    let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+
+    let mut result: PathBuf = base[common..]
+        .iter()
+        .map(|_| Component::ParentDir)
+        .collect();
+    result.extend(&path[common..]);
+    if result.as_os_str().is_empty() {
+        result.push(".");
+    }
+    result
+}
+
+fn select_name(path: &str, regexes: &Option<Vec<Regex>>) -> bool {
+    match regexes {
+        None => return true,
+        Some(regexes) => {
+            for re in regexes {
+                if re.is_match(path) {
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
+fn select_type(file_type: FileType, types: &Option<Vec<EntryType>>) -> bool {
+    match types {
+        None => return true,
+        Some(types) => {
+            for t in types {
+                match t {
+                    EntryType::Dir if file_type.is_dir() => return true,
+                    EntryType::Link if file_type.is_symlink() => return true,
+                    EntryType::File if file_type.is_file() => return true,
+                    _ => (),
+                }
+            }
+        }
+    }
+    false
+}
+
+fn entry_filename(entry: &walkdir::DirEntry) -> Result<&str> {
+    let path = entry.path();
+    if path == Path::new(".") {
+        return Ok(".");
+    } else if path == Path::new("..") {
+        return Ok("..");
+    }
+    match path.file_name() {
+        None => Err(anyhow!("Failed to get file_name from {path:?}")),
+        Some(file_name) => match file_name.to_str() {
+            None => Err(anyhow!("Failed to convert file_name {file_name:?} to str")),
+            Some(s) => Ok(s),
+        },
+    }
+}
@@ -33,6 +33,24 @@ fn skips_bad_dir() -> Result<()> {
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+fn debug_explains_skipped_entries() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--debug", "tests/inputs/a/b", "-n", "csv$", "-t", "d"])
+        .env_remove("RUST_LOG")
+        .assert()
+        .success()
+        .stdout("")
+        .stderr(predicate::str::contains(
+            "match{path=tests/inputs/a/b/b.csv}: skipped, --type doesn't match",
+        ))
+        .stderr(predicate::str::contains(
+            "match{path=tests/inputs/a/b/c}: skipped, no --name matches",
+        ));
+    Ok(())
+}
+
 // --------------------------------------------------
 #[test]
 fn dies_bad_name() -> Result<()> {
@@ -277,6 +295,83 @@ fn path_g() -> Result<()> {
     run(&["tests/inputs/g.csv"], "tests/expected/path_g.txt")
 }
 
+// --------------------------------------------------
+fn run_stdin(input: &[u8], args: &[&str], expected_file: &str) -> Result<()> {
+    let file = format_file_name(expected_file);
+    let contents = fs::read_to_string(file.as_ref())?;
+    let mut expected: Vec<&str> = contents.split('\n').filter(|s| !s.is_empty()).collect();
+    expected.sort();
+
+    let cmd = cargo_bin_cmd!()
+        .args(args)
+        .write_stdin(input)
+        .assert()
+        .success();
+    let out = cmd.get_output();
+    let stdout = String::from_utf8(out.stdout.clone())?;
+    let mut lines: Vec<&str> = stdout.split('\n').filter(|s| !s.is_empty()).collect();
+    lines.sort();
+
+    assert_eq!(lines, expected);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from_stdin() -> Result<()> {
+    run_stdin(
+        b"tests/inputs/a/b\0tests/inputs/d\0",
+        &["--files0-from", "-"],
+        "tests/expected/path_a_b_d.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from_stdin_type_f() -> Result<()> {
+    run_stdin(
+        b"tests/inputs/a/b\0tests/inputs/d",
+        &["--files0-from", "-", "--type", "f"],
+        "tests/expected/type_f_path_a_b_d.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from_skips_empty_name() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--files0-from", "-"])
+        .write_stdin(&b"\0tests/inputs/g.csv\0"[..])
+        .assert()
+        .success()
+        .stdout("tests/inputs/g.csv\n")
+        .stderr(predicate::str::contains("invalid zero-length file name"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_files0_from_with_paths() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--files0-from", "-", "tests/inputs"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn print0() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--print0", "tests/inputs/g.csv", "tests/inputs/a/b/b.csv"])
+        .assert()
+        .success()
+        .stdout("tests/inputs/g.csv\0tests/inputs/a/b/b.csv\0");
+    Ok(())
+}
+
 // --------------------------------------------------
 #[test]
 #[cfg(not(windows))]
@@ -304,3 +399,256 @@ fn unreadable_dir() -> Result<()> {
     assert!(stderr.contains("cant-touch-this: Permission denied"));
     Ok(())
 }
+
+// --------------------------------------------------
+/// A directory with a hidden file, and a hidden directory holding a file
+/// that isn't.
+fn hidden_dir() -> Result<tempfile::TempDir> {
+    let dir = tempfile::tempdir()?;
+    fs::write(dir.path().join("shown.txt"), "")?;
+    fs::write(dir.path().join(".hidden.txt"), "")?;
+    fs::create_dir(dir.path().join(".git"))?;
+    fs::write(dir.path().join(".git/config.txt"), "")?;
+    Ok(dir)
+}
+
+// --------------------------------------------------
+#[test]
+fn smart_skips_hidden() -> Result<()> {
+    let dir = hidden_dir()?;
+    let root = dir.path().to_str().unwrap();
+    cargo_bin_cmd!()
+        .args([root, "--smart", "-t", "f"])
+        .assert()
+        .success()
+        .stdout(format!("{root}/shown.txt\n"));
+
+    // Nor is anything found below a hidden directory
+    cargo_bin_cmd!()
+        .args([root, "--smart", "--name", "config"])
+        .assert()
+        .success()
+        .stdout("");
+
+    // A hidden starting point is still searched
+    let git = format!("{root}/.git");
+    cargo_bin_cmd!()
+        .args([&git, "--smart"])
+        .assert()
+        .success()
+        .stdout(format!("{git}\n{git}/config.txt\n"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn hidden_with_smart() -> Result<()> {
+    let dir = hidden_dir()?;
+    let root = dir.path().to_str().unwrap();
+    for args in [
+        &[root, "-t", "f"][..],
+        &[root, "-t", "f", "--smart", "--hidden"],
+    ] {
+        let output = cargo_bin_cmd!().args(args).output()?;
+        assert!(output.status.success());
+        let mut lines: Vec<_> = String::from_utf8(output.stdout)?
+            .lines()
+            .map(String::from)
+            .collect();
+        lines.sort();
+        assert_eq!(
+            lines,
+            [
+                format!("{root}/.git/config.txt"),
+                format!("{root}/.hidden.txt"),
+                format!("{root}/shown.txt"),
+            ]
+        );
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn prune() -> Result<()> {
+    let dir = hidden_dir()?;
+    fs::create_dir(dir.path().join("target"))?;
+    fs::write(dir.path().join("target/built.txt"), "")?;
+    let root = dir.path().to_str().unwrap();
+    // Pruning beats --hidden, and only applies to directories
+    let output = cargo_bin_cmd!()
+        .args([root, "--smart", "--hidden"])
+        .args(["--prune", r"^\.git$", "--prune", "target|hidden"])
+        .output()?;
+    assert!(output.status.success());
+    let mut lines: Vec<_> = String::from_utf8(output.stdout)?
+        .lines()
+        .map(String::from)
+        .collect();
+    lines.sort();
+    assert_eq!(
+        lines,
+        [
+            root.to_string(),
+            format!("{root}/.hidden.txt"),
+            format!("{root}/shown.txt"),
+        ]
+    );
+
+    // A starting point is searched even when its name matches
+    let git = format!("{root}/.git");
+    cargo_bin_cmd!()
+        .args([&git, "--prune", "git"])
+        .assert()
+        .success()
+        .stdout(format!("{git}\n{git}/config.txt\n"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_hidden_without_smart() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--hidden", "tests/inputs"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--smart"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn total_size() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["tests/inputs", "--total-size"])
+        .assert()
+        .success()
+        .stdout("total 16 in 8 files\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn total_size_verbose() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["tests/inputs/g.csv", "--total-size", "-v"])
+        .assert()
+        .success()
+        .stdout("tests/inputs/g.csv\ntotal 2 in 1 file\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn total_size_human() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    fs::write(dir.path().join("core.1"), vec![0; 3000])?;
+    fs::write(dir.path().join("core.2"), vec![0; 1024 * 1024])?;
+    fs::write(dir.path().join("notes.txt"), "keep")?;
+    cargo_bin_cmd!()
+        .arg(dir.path())
+        .args(["--name", "^core", "--total-size"])
+        .assert()
+        .success()
+        .stdout("total 1.1M in 2 files\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_verbose_without_total_size() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-v", "tests/inputs"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--total-size"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn relative_to() -> Result<()> {
+    cargo_bin_cmd!()
+        .args([
+            "tests/inputs/a/b",
+            "-t",
+            "f",
+            "--relative-to",
+            "tests/inputs/d/../a",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("b/b.csv\n").and(predicate::str::contains("b/c/c.mp3\n")));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn relative_to_itself() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["tests/inputs/f", "--relative-to", "tests/inputs/f"])
+        .assert()
+        .success()
+        .stdout(".\nf.txt\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn absolute() -> Result<()> {
+    let expected = fs::canonicalize("tests/inputs/g.csv")?;
+    cargo_bin_cmd!()
+        .args(["tests/inputs/./g.csv", "--absolute"])
+        .assert()
+        .success()
+        .stdout(format!("{}\n", expected.display()));
+    Ok(())
+}
+
+#[test]
+fn absolute_keeps_symlink() -> Result<()> {
+    let expected = fs::canonicalize("tests/inputs/d")?.join("b.csv");
+    cargo_bin_cmd!()
+        .args(["tests/inputs", "-t", "l", "--absolute"])
+        .assert()
+        .success()
+        .stdout(format!("{}\n", expected.display()));
+    Ok(())
+}
+
+#[test]
+fn absolute_dangling_symlink() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    std::os::unix::fs::symlink("missing", dir.path().join("broken"))?;
+    let expected = fs::canonicalize(dir.path())?.join("broken");
+    cargo_bin_cmd!()
+        .arg(dir.path())
+        .args(["-t", "l", "--absolute"])
+        .assert()
+        .success()
+        .stdout(format!("{}\n", expected.display()));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn no_leading_dot_slash() -> Result<()> {
+    cargo_bin_cmd!()
+        .current_dir("tests/inputs")
+        .args([".", "-n", "^f", "--no-leading-dot-slash"])
+        .assert()
+        .success()
+        .stdout("f\nf/f.txt\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_absolute_and_relative_to() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--absolute", "--relative-to", "/", "tests/inputs"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
@@ -0,0 +1,610 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    ffi::OsString,
+    fs,
+    io::{self, Write},
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow, bail};
+use clap::{CommandFactory, Parser, ValueEnum};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use walkdir::WalkDir;
+
+/// Rust version of ‘fortune’
+#[derive(Debug, Parser)]
+#[command[about, author, version]]
+struct CLIArgs {
+    /// Input files or directories
+    #[arg(value_name = "FILE", required = true)]
+    sources: Vec<String>,
+
+    /// Pattern
+    #[arg(short = 'm', long)]
+    pattern: Option<String>,
+
+    /// Case-insensitive pattern matching
+    #[arg(short, long)]
+    insensitive: bool,
+
+    /// Random seed
+    #[arg(short, long)]
+    seed: Option<u64>,
+
+    /// Only fortunes whose ‘#author:’ header matches PATTERN
+    #[arg(long, value_name = "PATTERN")]
+    author: Option<String>,
+
+    /// Only fortunes carrying TAG in their ‘#tags:’ header (can be repeated)
+    #[arg(long = "tag", value_name = "TAG")]
+    tags: Vec<String>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// Print each source with its number of fortunes and the chance of
+    /// picking one of them, instead of a fortune
+    #[arg(long, conflicts_with_all = ["pattern", "format"])]
+    show_odds: bool,
+
+    /// Like --show-odds, but also pick N fortunes and print how often each
+    /// source came up
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with_all = ["pattern", "format", "show_odds"],
+    )]
+    sample: Option<u64>,
+
+    /// Character encoding of the fortune files
+    #[arg(long, value_enum, default_value_t = Encoding::Auto)]
+    encoding: Encoding,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Format {
+    /// Plain text, with ‘%’ separators for -m matches
+    Text,
+    /// One JSON object per fortune and line
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Encoding {
+    /// UTF-8 if the whole file is valid UTF-8, Latin-1 otherwise
+    Auto,
+    /// Latin-1, as used by many classic fortune files
+    Latin1,
+    /// UTF-8, failing on anything else
+    Utf8,
+}
+
+#[derive(Debug)]
+struct Args {
+    sources: Vec<PathBuf>,
+    pattern: Option<Regex>,
+    seed: Option<u64>,
+    author: Option<Regex>,
+    tags: Vec<String>,
+    format: Format,
+    show_odds: bool,
+    sample: Option<u64>,
+    encoding: Encoding,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Fortune {
+    source: String,
+    text: String,
+    /// Position of the fortune in its source file, starting from 0
+    index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+}
+
+/// The command line that [`main`] parses, for generating the man page.
+pub fn command() -> clap::Command {
+    CLIArgs::command()
+}
+
+/// What the man page tells beyond --help.
+pub const MAN_DESCRIPTION: &str = "\
+Each FILE is a fortune file, or a directory of them, in which fortunes are \
+separated by lines holding a single ‘%’. Without --pattern one fortune is \
+picked at random, with the same chance for every fortune across all the files; \
+--seed makes the choice repeatable.\n\n\
+With --pattern every matching fortune is printed instead, and the name of the \
+file it came from goes to standard error.";
+
+pub fn main(args: impl IntoIterator<Item = OsString>) -> Result<()> {
+    let args = parse_args(args)?;
+    let mut fortunes = read_fortunes(&args.sources, args.encoding)?;
+    fortunes.retain(|fortune| select_fortune(fortune, &args));
+    let mut out = io::stdout().lock();
+    if (args.show_odds || args.sample.is_some()) && fortunes.is_empty() {
+        writeln!(out, "No fortunes found")?;
+        return Ok(());
+    }
+    if args.show_odds {
+        print_odds(&args.sources, &fortunes, &mut out)?;
+        return Ok(());
+    }
+    if let Some(draws) = args.sample {
+        print_sample(&args.sources, &fortunes, draws, args.seed, &mut out)?;
+        return Ok(());
+    }
+    match (&args.pattern, args.format) {
+        (None, Format::Text) => {
+            if fortunes.is_empty() {
+                writeln!(out, "No fortunes found")?;
+                return Ok(());
+            }
+            let fortune = pick_fortune(&fortunes, args.seed).unwrap();
+            writeln!(out, "{}", fortune.text)?;
+        }
+        (None, Format::Json) => match pick_fortune(&fortunes, args.seed) {
+            None => eprintln!("No fortunes found"),
+            Some(fortune) => writeln!(out, "{}", serde_json::to_string(fortune)?)?,
+        },
+        (Some(pattern), Format::Text) => {
+            let mut prev_source: Option<String> = None;
+            for Fortune { text, source, .. } in fortunes {
+                if pattern.is_match(&text) {
+                    if prev_source != Some(source.clone()) {
+                        eprintln!("({source})\n%");
+                        prev_source = Some(source.clone());
+                    }
+                    writeln!(out, "{}\n%", text)?;
+                }
+            }
+        }
+        (Some(pattern), Format::Json) => {
+            for fortune in fortunes.iter().filter(|f| pattern.is_match(&f.text)) {
+                writeln!(out, "{}", serde_json::to_string(fortune)?)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_args(args: impl IntoIterator<Item = OsString>) -> Result<Args> {
+    let CLIArgs {
+        sources,
+        pattern,
+        insensitive,
+        seed,
+        author,
+        tags,
+        format,
+        show_odds,
+        sample,
+        encoding,
+    } = CLIArgs::parse_from(args);
+
+    let build_regex = |pat: String| {
+        RegexBuilder::new(pat.as_str())
+            .case_insensitive(insensitive)
+            .build()
+    };
+    let pattern = pattern.map(build_regex).transpose()?;
+    let author = author.map(build_regex).transpose()?;
+
+    let sources = find_files(&sources)?;
+
+    Ok(Args {
+        sources,
+        pattern,
+        seed,
+        author,
+        tags,
+        format,
+        show_odds,
+        sample,
+        encoding,
+    })
+}
+
+fn select_fortune(fortune: &Fortune, args: &Args) -> bool {
+    if let Some(author) = &args.author
+        && !fortune.author.as_ref().is_some_and(|a| author.is_match(a))
+    {
+        return false;
+    }
+    args.tags
+        .iter()
+        .all(|tag| fortune.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+}
+
+fn find_single_source(path: &String) -> Result<Vec<PathBuf>> {
+    let mut result = vec![];
+    for file in WalkDir::new(path).sort_by_file_name() {
+        let file = file?;
+        if !file.file_type().is_file() {
+            continue;
+        }
+        if file.metadata()?.len() == 0 {
+            continue;
+        }
+        let path = file.into_path();
+
+        if let Some(ext) = path.extension()
+            && ext == "dat"
+        {
+            continue;
+        }
+
+        if let Some(name) = path.file_name()
+            && !name.is_empty()
+            && name.as_bytes()[0] == b'.'
+        {
+            continue;
+        }
+
+        result.push(path);
+    }
+    Ok(result)
+}
+
+fn find_files(paths: &[String]) -> Result<Vec<PathBuf>> {
+    let mut result = vec![];
+    for path in paths {
+        result.append(&mut find_single_source(path)?);
+    }
+    result.sort();
+    result.dedup();
+    Ok(result)
+}
+
+fn read_fortunes(paths: &[PathBuf], encoding: Encoding) -> Result<Vec<Fortune>> {
+    let mut result = vec![];
+
+    for path in paths {
+        let bytes = fs::read(path)?;
+        let text = decode(&bytes, encoding).map_err(|e| anyhow!("{}: {e}", path.display()))?;
+        let mut index = 0;
+        for chunk in text.split('%') {
+            let mut fortune = parse_fortune(chunk.trim_matches('\n'));
+            if fortune.text.is_empty() {
+                continue;
+            }
+            fortune.source = source_name(path);
+            fortune.index = index;
+            index += 1;
+            result.push(fortune);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Decodes a whole fortune file. The text is only borrowed when it already
+/// is valid UTF-8; a UTF-8 byte order mark is dropped.
+fn decode(bytes: &[u8], encoding: Encoding) -> Result<Cow<'_, str>> {
+    let utf8 = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    match encoding {
+        Encoding::Latin1 => Ok(latin1(bytes)),
+        Encoding::Utf8 => match std::str::from_utf8(utf8) {
+            Ok(text) => Ok(Cow::Borrowed(text)),
+            Err(e) => bail!(
+                "invalid UTF-8 at byte {}, try --encoding latin1",
+                e.valid_up_to() + bytes.len() - utf8.len()
+            ),
+        },
+        Encoding::Auto => {
+            Ok(std::str::from_utf8(utf8).map_or_else(|_| latin1(bytes), Cow::Borrowed))
+        }
+    }
+}
+
+/// Every byte is a character in Latin-1. This uses its WHATWG superset,
+/// windows-1252, which only differs in the rarely used C1 controls.
+fn latin1(bytes: &[u8]) -> Cow<'_, str> {
+    encoding_rs::WINDOWS_1252
+        .decode_without_bom_handling(bytes)
+        .0
+}
+
+fn source_name(path: &Path) -> String {
+    path.file_name()
+        .expect("source should have filename")
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Splits leading ‘#key: value’ header lines off the fortune text. Unknown
+/// keys are dropped, the first line that isn't a header starts the text.
+fn parse_fortune(mut text: &str) -> Fortune {
+    let mut fortune = Fortune::default();
+    while let Some(header) = text.strip_prefix('#') {
+        let (line, rest) = header.split_once('\n').unwrap_or((header, ""));
+        let Some((key, value)) = line.split_once(':') else {
+            break;
+        };
+        if key.is_empty()
+            || !key
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+        {
+            break;
+        }
+        let value = value.trim();
+        match key.to_lowercase().as_str() {
+            "author" => fortune.author = Some(value.to_string()),
+            "tag" | "tags" => fortune.tags.extend(
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|t| !t.is_empty())
+                    .map(String::from),
+            ),
+            _ => (),
+        }
+        text = rest;
+    }
+    fortune.text = text.trim_matches('\n').to_string();
+    fortune
+}
+
+fn new_rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_rng(rand::thread_rng()).expect("seeding from thread_rnd"),
+    }
+}
+
+fn pick_fortune(fortunes: &[Fortune], seed: Option<u64>) -> Option<&Fortune> {
+    if fortunes.is_empty() {
+        return None;
+    }
+    let pick = new_rng(seed).gen_range(0..fortunes.len());
+    Some(&fortunes[pick])
+}
+
+/// Number of `fortunes` from each of `sources`, in the order of `sources`.
+/// Sources left without fortunes by the filters are still listed.
+fn count_by_source(sources: &[PathBuf], fortunes: &[Fortune]) -> Vec<(String, u64)> {
+    let mut counts: Vec<(String, u64)> = sources.iter().map(|p| (source_name(p), 0)).collect();
+    counts.dedup_by(|a, b| a.0 == b.0);
+    for fortune in fortunes {
+        if let Some((_, n)) = counts.iter_mut().find(|(name, _)| *name == fortune.source) {
+            *n += 1;
+        }
+    }
+    counts
+}
+
+fn percent(part: u64, total: u64) -> f64 {
+    part as f64 * 100.0 / total as f64
+}
+
+/// Every fortune is equally likely, so a source is as likely as its share
+/// of the fortunes.
+fn print_odds(sources: &[PathBuf], fortunes: &[Fortune], out: &mut impl Write) -> io::Result<()> {
+    let total = fortunes.len() as u64;
+    for (source, count) in count_by_source(sources, fortunes) {
+        writeln!(out, "{:6.2}% {count:>5} {source}", percent(count, total))?;
+    }
+    Ok(())
+}
+
+/// Picks `draws` fortunes the way a normal run does, from one generator,
+/// and prints the odds of each source next to the share it got.
+fn print_sample(
+    sources: &[PathBuf],
+    fortunes: &[Fortune],
+    draws: u64,
+    seed: Option<u64>,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let mut rng = new_rng(seed);
+    let mut hits: HashMap<&str, u64> = HashMap::new();
+    for _ in 0..draws {
+        let pick = &fortunes[rng.gen_range(0..fortunes.len())];
+        *hits.entry(&pick.source).or_default() += 1;
+    }
+
+    let total = fortunes.len() as u64;
+    let width = draws.to_string().len();
+    for (source, count) in count_by_source(sources, fortunes) {
+        let got = hits.get(source.as_str()).copied().unwrap_or(0);
+        writeln!(
+            out,
+            "{:6.2}% {:6.2}% {got:>width$} {source}",
+            percent(count, total),
+            percent(got, draws),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assertables::*;
+    use learnr::assert_err_str_contains;
+
+    #[test]
+    fn test_find_files() {
+        // Verify that the function finds a file known to exist
+        let res = find_files(&["./tests/inputs/jokes".to_string()]);
+        assert!(res.is_ok());
+        let files = res.unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(
+            files.first().unwrap().to_string_lossy(),
+            "./tests/inputs/jokes"
+        );
+        // Fails to find a bad file
+        let res = find_files(&["/path/does/not/exist".to_string()]);
+        assert_err!(res);
+        // Finds all the input files, excludes ".dat"
+        let res = find_files(&["./tests/inputs".to_string()]);
+        assert!(res.is_ok());
+        // Check number and order of files
+        let files = res.unwrap();
+        assert_eq!(files.len(), 4);
+        let first = files.first().unwrap().display().to_string();
+        assert!(first.contains("ascii-art"));
+        let last = files.last().unwrap().display().to_string();
+        assert!(last.contains("quotes"));
+        // Test for multiple sources, path must be unique and sorted
+
+        let res = find_files(&[
+            "./tests/inputs/jokes".to_string(),
+            "./tests/inputs/ascii-art".to_string(),
+            "./tests/inputs/jokes".to_string(),
+        ]);
+        assert!(res.is_ok());
+        let files = res.unwrap();
+        assert_eq!(files.len(), 2);
+        if let Some(filename) = files.first().unwrap().file_name() {
+            assert_eq!(filename.to_string_lossy(), "ascii-art".to_string())
+        }
+        if let Some(filename) = files.last().unwrap().file_name() {
+            assert_eq!(filename.to_string_lossy(), "jokes".to_string())
+        }
+    }
+
+    #[test]
+    fn test_read_fortunes() {
+        // One input file
+        let res = read_fortunes(&[PathBuf::from("./tests/inputs/jokes")], Encoding::Auto);
+        assert!(res.is_ok());
+        if let Ok(fortunes) = res {
+            // Correct number and sorting
+            assert_eq!(fortunes.len(), 6);
+            assert_eq!(
+                fortunes.first().unwrap().text,
+                "Q. What do you call a head of lettuce in a shirt and tie?\n\
+A. Collared greens."
+            );
+            assert_eq!(
+                fortunes.last().unwrap().text,
+                "Q: What do you call a deer wearing an eye patch?\n\
+A: A bad idea (bad-eye deer)."
+            );
+        }
+        // Multiple input files
+        let res = read_fortunes(
+            &[
+                PathBuf::from("./tests/inputs/jokes"),
+                PathBuf::from("./tests/inputs/quotes"),
+            ],
+            Encoding::Auto,
+        );
+        assert!(res.is_ok());
+        let fortunes = res.unwrap();
+        assert_eq!(fortunes.len(), 11);
+        // Indexes count from the start of each source
+        assert_eq!(
+            fortunes
+                .iter()
+                .map(|f| (f.source.as_str(), f.index))
+                .collect::<Vec<_>>()[4..8],
+            [("jokes", 4), ("jokes", 5), ("quotes", 0), ("quotes", 1)]
+        );
+    }
+    #[test]
+    fn test_pick_fortune() {
+        // Create a slice of fortunes
+        let fortunes = &[
+            Fortune {
+                source: "fortunes".to_string(),
+                text: "You cannot achieve the impossible without \
+attempting the absurd."
+                    .to_string(),
+                ..Default::default()
+            },
+            Fortune {
+                source: "fortunes".to_string(),
+                text: "Assumption is the mother of all screw-ups.".to_string(),
+                ..Default::default()
+            },
+            Fortune {
+                source: "fortunes".to_string(),
+                text: "Neckties strangle clear thinking.".to_string(),
+                ..Default::default()
+            },
+        ];
+        // Pick a fortune with a seed
+        assert_eq!(
+            pick_fortune(fortunes, Some(1)).unwrap().text,
+            "Neckties strangle clear thinking.".to_string()
+        );
+    }
+
+    #[test]
+    fn test_count_by_source() {
+        let fortune = |source: &str| Fortune {
+            source: source.to_string(),
+            ..Default::default()
+        };
+        let sources = [PathBuf::from("a/jokes"), PathBuf::from("b/quotes")];
+        assert_eq!(
+            count_by_source(&sources, &[fortune("quotes"), fortune("quotes")]),
+            [("jokes".to_string(), 0), ("quotes".to_string(), 2)]
+        );
+        assert_eq!(percent(1, 4), 25.0);
+    }
+
+    #[test]
+    fn test_parse_fortune() {
+        let plain = parse_fortune("Neckties strangle clear thinking.");
+        assert_eq!(plain.text, "Neckties strangle clear thinking.");
+        assert_eq!(plain.author, None);
+        assert!(plain.tags.is_empty());
+
+        let annotated =
+            parse_fortune("#author: Mark Twain\n#tags: wit, travel\n#mood: sunny\nExplore. Dream.");
+        assert_eq!(annotated.text, "Explore. Dream.");
+        assert_eq!(annotated.author.as_deref(), Some("Mark Twain"));
+        assert_eq!(annotated.tags, vec!["wit", "travel"]);
+
+        // Not a header: no colon, or not a single word before it
+        let hashtag = parse_fortune("#1 rule: be nice\nto people");
+        assert_eq!(hashtag.text, "#1 rule: be nice\nto people");
+        let no_colon = parse_fortune("#nocolon\ntext");
+        assert_eq!(no_colon.text, "#nocolon\ntext");
+    }
+
+    #[test]
+    fn test_read_fortunes_with_headers() {
+        let res = read_fortunes(
+            &[PathBuf::from("./tests/annotated/authors")],
+            Encoding::Auto,
+        );
+        assert!(res.is_ok());
+        let fortunes = res.unwrap();
+        assert_eq!(fortunes.len(), 4);
+        assert_eq!(fortunes[0].author.as_deref(), Some("Mark Twain"));
+        assert_eq!(fortunes[0].tags, vec!["compliments"]);
+        assert_eq!(
+            fortunes[0].text,
+            "I can live for two months on a good compliment."
+        );
+        assert_eq!(fortunes[3].author, None);
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode("Après".as_bytes(), Encoding::Auto).unwrap(), "Après");
+        assert_eq!(decode(b"Apr\xe8s", Encoding::Auto).unwrap(), "Après");
+        assert_eq!(decode(b"\xef\xbb\xbfBOM", Encoding::Auto).unwrap(), "BOM");
+        assert_eq!(decode(b"Apr\xe8s", Encoding::Latin1).unwrap(), "Après");
+        // UTF-8 read as Latin-1 is what it is asked for
+        assert_eq!(decode("é".as_bytes(), Encoding::Latin1).unwrap(), "Ã©");
+        assert_eq!(decode("é".as_bytes(), Encoding::Utf8).unwrap(), "é");
+        assert_err_str_contains!(
+            decode(b"Apr\xe8s", Encoding::Utf8),
+            "invalid UTF-8 at byte 3"
+        );
+    }
+}
@@ -9,6 +9,8 @@ const FORTUNE_DIR: &str = "./tests/inputs";
 const EMPTY_DIR: &str = "./tests/inputs/empty";
 const JOKES: &str = "./tests/inputs/jokes";
 const LITERATURE: &str = "./tests/inputs/literature";
+const ANNOTATED_DIR: &str = "./tests/annotated";
+const LATIN1_DIR: &str = "./tests/latin1";
 
 // --------------------------------------------------
 fn random_string() -> String {
@@ -223,3 +225,226 @@ fn mark_twain_lower_i() -> Result<()> {
         FORTUNE_DIR,
     )
 }
+
+// --------------------------------------------------
+#[test]
+fn tag_filter() -> Result<()> {
+    run!(
+        "It's like deja vu all over again.\n",
+        "--tag",
+        "time",
+        ANNOTATED_DIR,
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn author_filter() -> Result<()> {
+    run!(
+        "I can live for two months on a good compliment.\n",
+        "--author",
+        "Twain",
+        ANNOTATED_DIR,
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn author_and_tag_filter() -> Result<()> {
+    run!(
+        "It's like deja vu all over again.\n",
+        "--author",
+        "Berra",
+        "--tag",
+        "wisdom",
+        "--tag",
+        "TIME",
+        ANNOTATED_DIR,
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn author_filter_pattern_insensitive() -> Result<()> {
+    run_outfiles!(
+        "tests/expected/author_berra_i.out",
+        "tests/expected/author_berra_i.err",
+        "-i",
+        "--author",
+        "berra",
+        "-m",
+        ".",
+        ANNOTATED_DIR,
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn author_filter_no_match() -> Result<()> {
+    run!("No fortunes found\n", "--author", "Nobody", ANNOTATED_DIR)
+}
+
+// --------------------------------------------------
+#[test]
+fn json_seed_1() -> Result<()> {
+    run!(
+        concat!(
+            r#"{"source":"authors","text":"It's like deja vu all over again.","#,
+            r#""index":1,"author":"Yogi Berra","tags":["time","wisdom"]}"#,
+            "\n"
+        ),
+        ANNOTATED_DIR,
+        "--format",
+        "json",
+        "-s",
+        "1",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn json_pattern() -> Result<()> {
+    cargo_bin_cmd!()
+        .args([ANNOTATED_DIR, JOKES, "--format=json", "-m", "frogs"])
+        .assert()
+        .success()
+        .stdout(concat!(
+            r#"{"source":"authors","text":"Q: What happens when frogs park illegally?\nA: They get toad.","index":3}"#,
+            "\n",
+            r#"{"source":"jokes","text":"Q: What happens when frogs park illegally?\nA: They get toad.","index":4}"#,
+            "\n",
+        ))
+        .stderr("");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn json_no_fortunes_found() -> Result<()> {
+    cargo_bin_cmd!()
+        .args([EMPTY_DIR, "--format", "json"])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("No fortunes found\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn show_odds() -> Result<()> {
+    run!(
+        concat!(
+            " 21.05%     4 ascii-art\n",
+            " 31.58%     6 jokes\n",
+            " 21.05%     4 literature\n",
+            " 26.32%     5 quotes\n",
+        ),
+        FORTUNE_DIR,
+        "--show-odds",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn show_odds_filtered() -> Result<()> {
+    // Sources left without fortunes stay listed
+    run!(
+        "100.00%     1 authors\n  0.00%     0 jokes\n",
+        ANNOTATED_DIR,
+        JOKES,
+        "--tag",
+        "time",
+        "--show-odds",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn sample_seed_1() -> Result<()> {
+    run!(
+        concat!(
+            " 21.05%  20.80%  208 ascii-art\n",
+            " 31.58%  31.30%  313 jokes\n",
+            " 21.05%  21.80%  218 literature\n",
+            " 26.32%  26.10%  261 quotes\n",
+        ),
+        FORTUNE_DIR,
+        "--sample",
+        "1000",
+        "-s",
+        "1",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn sample_no_fortunes_found() -> Result<()> {
+    run!("No fortunes found\n", EMPTY_DIR, "--sample", "10")
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_sample_zero() -> Result<()> {
+    cargo_bin_cmd!()
+        .args([JOKES, "--sample", "0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "invalid value '0' for '--sample <N>'",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn latin1_auto() -> Result<()> {
+    run!(
+        "Après la pluie, le beau temps.\n%\n",
+        LATIN1_DIR,
+        "-m",
+        "pluie"
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn latin1_pattern_matches_decoded_text() -> Result<()> {
+    run!(
+        concat!(
+            "A vaincre sans péril, on triomphe sans gloire.\n%\n",
+            "Il n'y a pas de fumée sans feu.\n%\n",
+        ),
+        LATIN1_DIR,
+        "--encoding",
+        "latin1",
+        "-m",
+        "é",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn utf8_auto_is_unchanged() -> Result<()> {
+    run!(
+        "Q: What do you call a deer wearing an eye patch?\nA: A bad idea (bad-eye deer).\n%\n",
+        JOKES,
+        "--encoding",
+        "auto",
+        "-m",
+        "deer",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_latin1_as_utf8() -> Result<()> {
+    cargo_bin_cmd!()
+        .args([LATIN1_DIR, "--encoding", "utf8"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "proverbes: invalid UTF-8 at byte 16, try --encoding latin1",
+        ));
+    Ok(())
+}
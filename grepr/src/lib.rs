@@ -0,0 +1,1064 @@
+use std::{
+    collections::VecDeque,
+    env,
+    ffi::OsString,
+    fmt::Display,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, ErrorKind, IsTerminal, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use ansi_term::Color::Purple;
+use anyhow::{Result, anyhow, bail};
+use clap::{ColorChoice, CommandFactory, Parser, ValueEnum};
+use glob::Pattern;
+use regex::Regex;
+use tracing::{debug, debug_span, trace};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Input {
+    File(String),
+    StdIn,
+}
+
+/// Rust version of ‘grep’
+///
+/// Default options are read from ‘$XDG_CONFIG_HOME/grepr/config’ (or
+/// ‘~/.config/grepr/config’) and then from the GREPR_OPTIONS environment
+/// variable; options given on the command line take precedence over both,
+/// though a flag set there can't be turned off.
+#[derive(Debug, Parser)]
+#[command(args_override_self = true)]
+struct Args {
+    /// Search pattern
+    #[arg(required = true)]
+    pattern: String, // XXX make Regex
+
+    /// Input files(s)
+    #[arg(default_value = "-", value_name = "FILE", value_parser = parse_input)]
+    files: Vec<Input>,
+
+    /// Search the files listed in LIST instead, one per line or separated
+    /// by NULs (‘-’ reads the list from standard input)
+    #[arg(long, value_name = "LIST", conflicts_with = "files")]
+    files_from: Option<String>,
+
+    /// Skip files whose name or path matches one of the glob patterns in
+    /// LIST, one per line
+    #[arg(long, value_name = "LIST")]
+    exclude_from: Option<String>,
+
+    /// Case-insensitive
+    #[arg(short, long)]
+    insensitive: bool,
+
+    /// Recursive search
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Count occurences
+    #[arg(short, long)]
+    count: bool,
+
+    /// Invert match
+    #[arg(short('v'), long("invert-match"))]
+    invert: bool,
+
+    /// Print every line, matching or not, still highlighting the matches
+    #[arg(long)]
+    passthru: bool,
+
+    /// Print only capture group N of each match; repeat to print several
+    /// groups, separated by tabs
+    #[arg(long, value_name = "N", conflicts_with_all = ["count", "invert", "passthru"])]
+    group: Vec<usize>,
+
+    /// Whether to use colored output
+    #[arg(long, value_name="WHEN", default_value_t = ColorChoice::Auto, value_enum)]
+    color: ColorChoice,
+
+    /// With --count, print files by descending count (ties by name) once all
+    /// of them have been searched
+    #[arg(long, requires = "count")]
+    sort_count: bool,
+
+    /// Descend at most N directory levels below the starting points
+    #[arg(long, value_name = "N", requires = "recursive")]
+    max_depth: Option<usize>,
+
+    /// Skip files larger than SIZE (e.g. 512K, 10M) during recursive search
+    #[arg(long, value_name = "SIZE", value_parser = learnr::parse_size, requires = "recursive")]
+    max_filesize: Option<u64>,
+
+    /// Order in which files found by --recursive are searched, and so
+    /// printed; with path or mtime it is the same on every run and every
+    /// filesystem
+    #[arg(long, value_name = "ORDER", value_enum, default_value_t = FileOrder::Path)]
+    sort: FileOrder,
+
+    /// Print NUM lines of context after each selected line
+    #[arg(short('A'), long, value_name = "NUM", conflicts_with_all = ["count", "group", "passthru"])]
+    after_context: Option<usize>,
+
+    /// Print NUM lines of context before each selected line
+    #[arg(short('B'), long, value_name = "NUM", conflicts_with_all = ["count", "group", "passthru"])]
+    before_context: Option<usize>,
+
+    /// Print NUM lines of context before and after each selected line; -A
+    /// and -B take precedence
+    #[arg(short('C'), long, value_name = "NUM", conflicts_with_all = ["count", "group", "passthru"])]
+    context: Option<usize>,
+
+    /// Print STR between groups of lines that aren't next to each other
+    #[arg(long, value_name = "STR", default_value = "--")]
+    context_separator: String,
+
+    /// Print nothing between groups of context lines
+    #[arg(long, conflicts_with = "context_separator")]
+    no_context_separator: bool,
+
+    /// Explain on stderr which files are searched or skipped and how many
+    /// lines each has selected (RUST_LOG allows finer filters, down to
+    /// single lines with ‘RUST_LOG=[match]=trace’)
+    #[arg(long)]
+    debug: bool,
+}
+
+impl Args {
+    fn context(&self) -> Context {
+        Context {
+            before: self.before_context.or(self.context).unwrap_or(0),
+            after: self.after_context.or(self.context).unwrap_or(0),
+        }
+    }
+
+    /// What goes between non-adjacent groups of lines, if anything: only
+    /// context output is split into groups.
+    fn separator(&self) -> Option<&str> {
+        let with_context = [self.after_context, self.before_context, self.context]
+            .iter()
+            .any(Option::is_some);
+        (with_context && !self.no_context_separator).then_some(self.context_separator.as_str())
+    }
+}
+
+/// How many unselected lines to print around each selected one, like
+/// ‘grep -B N -A M’.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Context {
+    before: usize,
+    after: usize,
+}
+
+/// The order of the files below each directory searched with --recursive.
+/// Files named on the command line keep their order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum)]
+enum FileOrder {
+    /// By path, name by name in byte order, each directory's files right
+    /// after it
+    #[default]
+    Path,
+    /// By modification time, oldest first, then by path
+    Mtime,
+    /// As the directories list them, which depends on the filesystem
+    None,
+}
+
+#[derive(Debug, Default)]
+struct FindOptions {
+    recursive: bool,
+    max_depth: Option<usize>,
+    max_filesize: Option<u64>,
+    sort: FileOrder,
+    /// From --exclude-from
+    exclude: Vec<Pattern>,
+}
+
+/// The command line that [`main`] parses, for generating the man page.
+pub fn command() -> clap::Command {
+    Args::command()
+}
+
+/// What the man page tells beyond --help.
+pub const MAN_DESCRIPTION: &str = "\
+PATTERN is a regular expression in the syntax of the Rust regex crate, and \
+lines that match are printed. With more than one FILE, or with --recursive, \
+each line is prefixed with the name of its file.\n\n\
+Files are searched in the order they are given, and those found by \
+--recursive in the order of --sort, by path unless told otherwise. Output \
+is then the same from one run or filesystem to the next.\n\n\
+In the configuration file and in GREPR_OPTIONS options are separated by \
+whitespace, and lines starting with ‘#’ are ignored. Only options can be \
+given there, never the pattern or a file. An option with a value, like \
+--color, is overridden by the same option on the command line, but there is \
+no way to turn off a flag like --recursive once a default sets it.\n\n\
+A --files-from LIST, such as the output of ‘find -print0’ or ‘git ls-files’, \
+takes the place of FILE, without the limits of the command line. Patterns of \
+--exclude-from are matched both against the whole path of each file and \
+against its name, so ‘*.log’ skips log files anywhere; directories are still \
+searched with --recursive.";
+
+pub fn main(args: impl IntoIterator<Item = OsString>) -> Result<()> {
+    let args = Args::parse_from(with_defaults(args, default_args()?));
+    learnr::init_tracing(args.debug);
+
+    let color_output = match args.color {
+        ColorChoice::Auto => std::io::stdin().is_terminal(),
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+    };
+
+    let pattern = regex::RegexBuilder::new(&args.pattern)
+        .case_insensitive(args.insensitive)
+        .build()
+        .map_err(|_e| anyhow!(r#"Invalid pattern "{}""#, args.pattern))?;
+    if let Some(group) = args.group.iter().find(|&&n| n >= pattern.captures_len()) {
+        bail!(r#"No capture group {group} in pattern "{}""#, args.pattern);
+    }
+
+    let files = match &args.files_from {
+        Some(list) => read_file_list(list)?,
+        None => args.files.clone(),
+    };
+    let exclude = match &args.exclude_from {
+        Some(list) => read_patterns(list)?,
+        None => vec![],
+    };
+    let entries = find_files(
+        &files,
+        &FindOptions {
+            recursive: args.recursive,
+            max_depth: args.max_depth,
+            max_filesize: args.max_filesize,
+            sort: args.sort,
+            exclude,
+        },
+    );
+    let show_filenames = entries.len() > 1;
+    let mut stdout = std::io::stdout();
+    let mut counts: Vec<(String, usize)> = vec![];
+    // The file index and line number of the last line printed, to tell
+    // where a separator goes
+    let mut last_printed: Option<(usize, usize)> = None;
+
+    for (index, entry) in entries.into_iter().enumerate() {
+        let mut do_file = |entry| -> Result<()> {
+            let input = entry?;
+            let _span = debug_span!("open", path = %input).entered();
+            let (prefix, context_prefix) = if show_filenames {
+                (format!("{input}:"), format!("{input}-"))
+            } else {
+                (String::new(), String::new())
+            };
+            let fh = open(&input)?;
+            let filtered = find_lines(fh, &pattern, args.invert, args.passthru, args.context())?;
+            let count = filtered.iter().filter(|m| m.selected).count();
+            debug!(selected = count, "searched");
+            if args.sort_count {
+                counts.push((input.to_string(), count));
+            } else if args.count {
+                writeln!(stdout, "{prefix}{count}")?;
+            } else if !args.group.is_empty() {
+                for m in &filtered {
+                    print_groups(&mut stdout, &prefix, &m.line, &pattern, &args.group)?;
+                }
+            } else {
+                for m in &filtered {
+                    if let Some(separator) = args.separator()
+                        && last_printed.is_some_and(|last| last != (index, m.number - 1))
+                    {
+                        writeln!(stdout, "{separator}")?;
+                    }
+                    last_printed = Some((index, m.number));
+                    // With --passthru every line is printed as a selected one
+                    let prefix = if m.selected || args.passthru {
+                        &prefix
+                    } else {
+                        &context_prefix
+                    };
+                    print_match(&mut stdout, prefix, m, color_output)?;
+                }
+            }
+            Ok(())
+        };
+        if let Err(e) = do_file(entry) {
+            learnr::exit_on_broken_pipe(&e);
+            eprintln!("{e:?}");
+        }
+    }
+
+    sort_counts(&mut counts);
+    for (name, count) in counts {
+        if show_filenames {
+            writeln!(stdout, "{name}:{count}")?;
+        } else {
+            writeln!(stdout, "{count}")?;
+        }
+    }
+    Ok(())
+}
+
+/// Orders `(file name, count)` pairs by descending count, then by file name.
+fn sort_counts(counts: &mut [(String, usize)]) {
+    counts.sort_by(|(name1, count1), (name2, count2)| {
+        count2.cmp(count1).then_with(|| name1.cmp(name2))
+    });
+}
+
+/// Collects the default options from the config file and GREPR_OPTIONS, in
+/// that order.
+fn default_args() -> Result<Vec<String>> {
+    let mut defaults = vec![];
+
+    if let Some(path) = config_path() {
+        match fs::read_to_string(&path) {
+            Ok(text) => defaults.extend(options_only(
+                split_options(&text),
+                &path.display().to_string(),
+            )?),
+            Err(err) if err.kind() == ErrorKind::NotFound => (),
+            Err(err) => bail!("{}: {err}", path.display()),
+        }
+    }
+
+    match env::var("GREPR_OPTIONS") {
+        Ok(opts) => defaults.extend(options_only(split_options(&opts), "GREPR_OPTIONS")?),
+        Err(env::VarError::NotPresent) => (),
+        Err(err) => bail!("GREPR_OPTIONS: {err}"),
+    }
+
+    Ok(defaults)
+}
+
+fn config_path() -> Option<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("grepr").join("config"))
+}
+
+/// Fails on the first of `defaults`, read from `source`, that clap would
+/// take for the pattern: it would silently replace the one on the command
+/// line, which would become a file.
+fn options_only(defaults: Vec<String>, source: &str) -> Result<Vec<String>> {
+    // Stands for the command line, which always has a pattern
+    const PATTERN: &str = "\0pattern";
+    let matches = Args::command()
+        .no_binary_name(true)
+        .try_get_matches_from(defaults.iter().map(String::as_str).chain(["--", PATTERN]));
+    // Bad options are left for parsing the whole command line to report
+    if let Ok(matches) = matches
+        && let Some(pattern) = matches.get_one::<String>("pattern")
+        && pattern != PATTERN
+    {
+        bail!("{source}: '{pattern}' is not an option; only options can be defaults");
+    }
+    Ok(defaults)
+}
+
+/// Splits option text on whitespace, skipping blank lines and lines
+/// starting with ‘#’.
+fn split_options(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .flat_map(str::split_whitespace)
+        .map(String::from)
+        .collect()
+}
+
+/// Inserts the defaults right after the program name, so that clap sees the
+/// explicit arguments last and lets them override the defaults.
+fn with_defaults(args: impl IntoIterator<Item = OsString>, defaults: Vec<String>) -> Vec<OsString> {
+    let mut args = args.into_iter();
+    args.next()
+        .into_iter()
+        .chain(defaults.into_iter().map(OsString::from))
+        .chain(args)
+        .collect()
+}
+
+fn parse_input(filename: &str) -> Result<Input> {
+    match filename {
+        "-" => Ok(Input::StdIn),
+        _ => Ok(Input::File(filename.to_string())),
+    }
+}
+
+/// Reads LIST of --files-from or --exclude-from, from stdin for ‘-’.
+fn read_list(list: &str) -> Result<Vec<u8>> {
+    let mut contents = vec![];
+    match list {
+        "-" => io::stdin().read_to_end(&mut contents),
+        _ => File::open(list).and_then(|mut file| file.read_to_end(&mut contents)),
+    }
+    .map_err(|err| anyhow!("{list}: {err}"))?;
+    Ok(contents)
+}
+
+/// The files named in `list`, separated by NULs if there are any, as
+/// ‘find -print0’ writes them, or else one per line. Empty names are left
+/// out.
+fn read_file_list(list: &str) -> Result<Vec<Input>> {
+    let contents = read_list(list)?;
+    let separator = if contents.contains(&b'\0') {
+        b'\0'
+    } else {
+        b'\n'
+    };
+    contents
+        .split(|&byte| byte == separator)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            let name =
+                std::str::from_utf8(name).map_err(|_| anyhow!("{list}: file name is not UTF-8"))?;
+            parse_input(name)
+        })
+        .collect()
+}
+
+/// The glob patterns in `list`, one per line, leaving out blank lines.
+fn read_patterns(list: &str) -> Result<Vec<Pattern>> {
+    let contents = String::from_utf8(read_list(list)?).map_err(|err| anyhow!("{list}: {err}"))?;
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Pattern::new(line).map_err(|err| anyhow!("{list}: '{line}': {err}")))
+        .collect()
+}
+
+/// Whether `path` is one of those --exclude-from skips, by its whole path or
+/// by its name alone.
+fn is_excluded(path: &Path, exclude: &[Pattern]) -> bool {
+    let name = path.file_name().map(Path::new);
+    exclude.iter().any(|pattern| {
+        pattern.matches_path(path) || name.is_some_and(|name| pattern.matches_path(name))
+    })
+}
+
+fn find_files(paths: &[Input], opts: &FindOptions) -> Vec<Result<Input>> {
+    let mut result: Vec<Result<Input>> = Vec::new();
+
+    for input in paths {
+        let Input::File(path) = input else {
+            result.push(Ok(input.clone()));
+            continue;
+        };
+        if is_excluded(Path::new(path), &opts.exclude) {
+            debug!(path, "skipped, excluded by --exclude-from");
+            continue;
+        }
+
+        if !opts.recursive {
+            let single_res = std::fs::metadata(path)
+                .map_err(|err| anyhow!("{path}: {err}"))
+                .and_then(|metadata| {
+                    if metadata.is_dir() {
+                        Err(anyhow!("{path} is a directory"))
+                    } else {
+                        Ok(input.clone())
+                    }
+                });
+            result.push(single_res);
+            continue;
+        }
+
+        let _span = debug_span!("open", path = %path).entered();
+        let mut walk = walkdir::WalkDir::new(path);
+        if let Some(depth) = opts.max_depth {
+            walk = walk.max_depth(depth);
+        }
+        if opts.sort == FileOrder::Path {
+            walk = walk.sort_by_file_name();
+        }
+        // With --sort mtime, the files below `path` with their times
+        let mut by_mtime = vec![];
+        for res in walk {
+            match res {
+                Err(err) => result.push(Err(From::from(err))),
+                Ok(dent) => {
+                    if dent.file_type().is_file() {
+                        if is_excluded(dent.path(), &opts.exclude) {
+                            debug!(
+                                path = %dent.path().display(),
+                                "skipped, excluded by --exclude-from"
+                            );
+                            continue;
+                        }
+                        if let Some(limit) = opts.max_filesize {
+                            match dent.metadata() {
+                                Err(err) => {
+                                    result.push(Err(From::from(err)));
+                                    continue;
+                                }
+                                Ok(metadata) if metadata.len() > limit => {
+                                    debug!(
+                                        path = %dent.path().display(),
+                                        "skipped, {} bytes is over --max-filesize",
+                                        metadata.len()
+                                    );
+                                    continue;
+                                }
+                                Ok(_) => (),
+                            }
+                        }
+                        match dent.path().to_str() {
+                            None => result.push(Err(anyhow!(
+                                "Failed to convert dent path '{dent:?}' to string"
+                            ))),
+                            Some(s) if opts.sort == FileOrder::Mtime => {
+                                let mtime = dent
+                                    .metadata()
+                                    .map_err(anyhow::Error::from)
+                                    .and_then(|metadata| Ok(metadata.modified()?));
+                                match mtime {
+                                    Ok(mtime) => by_mtime.push((mtime, s.to_string())),
+                                    Err(err) => result.push(Err(err)),
+                                }
+                            }
+                            Some(s) => result.push(Ok(Input::File(s.to_string()))),
+                        }
+                    } else if !dent.file_type().is_dir() {
+                        debug!(path = %dent.path().display(), "skipped, not a regular file");
+                    }
+                }
+            }
+        }
+        by_mtime.sort();
+        result.extend(by_mtime.into_iter().map(|(_, path)| Ok(Input::File(path))));
+    }
+
+    result
+}
+
+impl Display for Input {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Input::StdIn => f.write_str("-"),
+            Input::File(file) => file.fmt(f),
+        }
+    }
+}
+
+fn open(input: &Input) -> Result<Box<dyn BufRead>> {
+    match input {
+        Input::StdIn => Ok(Box::new(BufReader::new(std::io::stdin()))),
+        Input::File(file) => Ok(Box::new(BufReader::new(File::open(file)?))),
+    }
+}
+
+struct Match {
+    line: String,
+    /// One-based, counted from the start of the input
+    number: usize,
+    matched: Option<(usize, usize)>,
+    selected: bool,
+}
+
+/// Writes a line, painting the matched part when colors are on.
+fn print_match(out: &mut impl Write, prefix: &str, m: &Match, color: bool) -> Result<()> {
+    let bytes = m.line.as_bytes();
+    out.write_all(prefix.as_bytes())?;
+    match m.matched {
+        Some((start, end)) if color => {
+            out.write_all(&bytes[0..start])?;
+            Purple.paint(&bytes[start..end]).write_to(out)?;
+            out.write_all(&bytes[end..])?;
+        }
+        _ => out.write_all(bytes)?,
+    }
+    Ok(())
+}
+
+/// The byte order mark some editors put at the start of UTF-8 files
+const BOM: &str = "\u{feff}";
+
+/// `line` without its ‘\n’ or ‘\r\n’ terminator.
+fn line_body(line: &str) -> &str {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+/// Collects the selected lines with their `context`, or with `passthru`
+/// every line; unselected lines are kept only so that they can be printed
+/// and highlighted. A byte order mark at the start of the input is dropped.
+///
+/// The context window only lives for one call, so nothing of it carries
+/// over from one input to the next.
+fn find_lines<T: BufRead>(
+    mut file: T,
+    pattern: &Regex,
+    invert: bool,
+    passthru: bool,
+    context: Context,
+) -> Result<Vec<Match>> {
+    let mut result = vec![];
+    let mut line = String::new();
+    let mut number = 0;
+    // Unselected lines that may yet turn out to come before a selected one
+    let mut before: VecDeque<Match> = VecDeque::with_capacity(context.before);
+    // How many of the coming lines are still after-context
+    let mut after_left = 0;
+    let _span = debug_span!("match").entered();
+    loop {
+        let bytes_read = file.read_line(&mut line)?;
+        if bytes_read == 0 {
+            break;
+        }
+        number += 1;
+        if number == 1 && line.starts_with(BOM) {
+            line.drain(..BOM.len());
+        }
+        // The terminator isn't matched, so that ‘$’ works for CRLF lines too,
+        // but it is kept for printing the line as it was
+        let matched = pattern.find(line_body(&line)).map(|m| (m.start(), m.end()));
+        let selected = matched.is_some() != invert;
+        trace!(number, selected);
+        if !(passthru || selected || after_left > 0 || context.before > 0) {
+            line.clear();
+            continue;
+        }
+        let m = Match {
+            line: std::mem::take(&mut line),
+            number,
+            // Inverted selections have nothing to highlight
+            matched: if invert && !passthru && selected {
+                None
+            } else {
+                matched
+            },
+            selected,
+        };
+        if passthru || selected {
+            result.extend(before.drain(..));
+            result.push(m);
+            after_left = context.after;
+        } else if after_left > 0 {
+            result.push(m);
+            after_left -= 1;
+        } else if context.before > 0 {
+            if before.len() == context.before {
+                before.pop_front();
+            }
+            before.push_back(m);
+        }
+    }
+    Ok(result)
+}
+
+/// Writes one line per match in `line` with the contents of `groups`, tab
+/// separated; groups that took no part in the match are left empty.
+fn print_groups(
+    out: &mut impl Write,
+    prefix: &str,
+    line: &str,
+    pattern: &Regex,
+    groups: &[usize],
+) -> Result<()> {
+    for caps in pattern.captures_iter(line_body(line)) {
+        let fields: Vec<&str> = groups
+            .iter()
+            .map(|&n| caps.get(n).map_or("", |m| m.as_str()))
+            .collect();
+        writeln!(out, "{prefix}{}", fields.join("\t"))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use assertables::*;
+    use rand::{Rng, distributions::Alphanumeric};
+    use regex::RegexBuilder;
+
+    const RECURSIVE: FindOptions = FindOptions {
+        recursive: true,
+        max_depth: None,
+        max_filesize: None,
+        sort: FileOrder::Path,
+        exclude: vec![],
+    };
+
+    #[test]
+    fn test_find_files() {
+        // "-" is a special case, we shouldn’t check whether it exists or not
+        let files = find_files(&[Input::StdIn], &FindOptions::default());
+        assert_eq!(files.len(), 1);
+        assert_eq!(*files[0].as_ref().unwrap(), Input::StdIn);
+
+        // Verify that the function finds a file known to exist
+        let files = find_files(
+            &[Input::File("./tests/inputs/fox.txt".to_string())],
+            &FindOptions::default(),
+        );
+        assert_eq!(files.len(), 1);
+        assert_eq!(
+            *files[0].as_ref().unwrap(),
+            Input::File("./tests/inputs/fox.txt".to_string())
+        );
+
+        // The function should reject a directory without the recursive option
+        let files = find_files(
+            &[Input::File("./tests/inputs".to_string())],
+            &FindOptions::default(),
+        );
+        assert_eq!(files.len(), 1);
+        assert_eq!(
+            files[0].as_ref().unwrap_err().to_string(),
+            "./tests/inputs is a directory"
+        );
+
+        // Verify the function recurses to find four files in the directory
+        let res = find_files(&[Input::File("./tests/inputs".to_string())], &RECURSIVE);
+        let mut files: Vec<String> = res
+            .iter()
+            .map(|r| {
+                let Ok(Input::File(f)) = r else {
+                    panic!("No {r:?} expected");
+                };
+                f
+            })
+            .map(|r| r.replace("\\", "/"))
+            .collect();
+        files.sort();
+        assert_eq!(files.len(), 4);
+        assert_eq!(
+            files,
+            vec![
+                "./tests/inputs/bustle.txt",
+                "./tests/inputs/empty.txt",
+                "./tests/inputs/fox.txt",
+                "./tests/inputs/nobody.txt",
+            ]
+        );
+
+        // Generate a random string to represent a nonexistent file
+        let bad: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(7)
+            .map(char::from)
+            .collect();
+        // Verify that the function returns the bad file as an error
+        let files = find_files(&[Input::File(bad)], &FindOptions::default());
+        assert_eq!(files.len(), 1);
+        assert_err!(&files[0]);
+    }
+
+    fn found_files(paths: &[&str], opts: &FindOptions) -> Vec<String> {
+        let inputs: Vec<Input> = paths.iter().map(|p| Input::File(p.to_string())).collect();
+        let mut files: Vec<String> = find_files(&inputs, opts)
+            .into_iter()
+            .map(|r| r.unwrap().to_string().replace("\\", "/"))
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn test_find_files_max_depth() {
+        let all = found_files(&["./tests/nested"], &RECURSIVE);
+        assert_eq!(
+            all,
+            vec![
+                "./tests/nested/a/b/deep.txt",
+                "./tests/nested/a/middle.txt",
+                "./tests/nested/top.txt",
+            ]
+        );
+
+        let shallow = FindOptions {
+            max_depth: Some(1),
+            ..RECURSIVE
+        };
+        assert_eq!(
+            found_files(&["./tests/nested"], &shallow),
+            vec!["./tests/nested/top.txt"]
+        );
+
+        let zero = FindOptions {
+            max_depth: Some(0),
+            ..RECURSIVE
+        };
+        assert_eq!(
+            found_files(&["./tests/nested"], &zero),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            found_files(&["./tests/nested/top.txt"], &zero),
+            vec!["./tests/nested/top.txt"]
+        );
+    }
+
+    #[test]
+    fn test_find_files_max_filesize() {
+        // nobody.txt is the only input bigger than 200 bytes
+        let small = FindOptions {
+            max_filesize: Some(200),
+            ..RECURSIVE
+        };
+        assert_eq!(
+            found_files(&["./tests/inputs"], &small),
+            vec![
+                "./tests/inputs/bustle.txt",
+                "./tests/inputs/empty.txt",
+                "./tests/inputs/fox.txt",
+            ]
+        );
+
+        let exact = FindOptions {
+            max_filesize: Some(0),
+            ..RECURSIVE
+        };
+        assert_eq!(
+            found_files(&["./tests/inputs"], &exact),
+            vec!["./tests/inputs/empty.txt"]
+        );
+    }
+
+    #[test]
+    fn test_read_lists() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let list = dir.path().join("list");
+        let list = list.to_str().unwrap();
+
+        fs::write(list, "a.txt\n\nb c.txt\n-\n")?;
+        assert_eq!(
+            read_file_list(list)?,
+            [
+                Input::File("a.txt".to_string()),
+                Input::File("b c.txt".to_string()),
+                Input::StdIn
+            ]
+        );
+        // With NULs, newlines are part of the names
+        fs::write(list, "a\nb\0c\0")?;
+        assert_eq!(
+            read_file_list(list)?,
+            [
+                Input::File("a\nb".to_string()),
+                Input::File("c".to_string())
+            ]
+        );
+
+        fs::write(list, "*.log\n\ntests/inputs/fox.txt\n")?;
+        let exclude = read_patterns(list)?;
+        assert_eq!(exclude.len(), 2);
+        assert!(is_excluded(Path::new("deep/down/x.log"), &exclude));
+        assert!(is_excluded(Path::new("tests/inputs/fox.txt"), &exclude));
+        assert!(!is_excluded(Path::new("other/fox.txt"), &exclude));
+
+        fs::write(list, "[\n")?;
+        learnr::assert_err_str_contains!(read_patterns(list), "'['");
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_options() {
+        assert_eq!(split_options(""), Vec::<String>::new());
+        assert_eq!(
+            split_options("# defaults\n--color=never\n\n  -i   --max-depth 2\n  # more\n"),
+            vec!["--color=never", "-i", "--max-depth", "2"]
+        );
+        assert_eq!(split_options("--count -v"), vec!["--count", "-v"]);
+    }
+
+    #[test]
+    fn test_options_only() {
+        let defaults = ["--color", "never", "-i", "--context-separator=x"].map(String::from);
+        assert_eq!(
+            options_only(defaults.to_vec(), "config").unwrap(),
+            defaults.to_vec()
+        );
+        learnr::assert_err_str_contains!(
+            options_only(
+                ["-i", "fox", "--count"].map(String::from).to_vec(),
+                "config"
+            ),
+            "config: 'fox' is not an option"
+        );
+        // Left for the whole command line to reject
+        assert_ok!(options_only(vec!["--no-such-flag".to_string()], "config"));
+    }
+
+    #[test]
+    fn test_with_defaults() {
+        let args = ["grepr", "--color=always", "fox"].map(OsString::from);
+        assert_eq!(
+            with_defaults(args.clone(), vec!["--color=never".to_string()]),
+            ["grepr", "--color=never", "--color=always", "fox"].map(OsString::from)
+        );
+        assert_eq!(with_defaults(args.clone(), vec![]), args);
+
+        // Explicit arguments win over the defaults
+        let args = Args::parse_from(with_defaults(args, vec!["--color=never".to_string()]));
+        assert_eq!(args.color, ColorChoice::Always);
+    }
+
+    #[test]
+    fn test_sort_counts() {
+        let mut counts = vec![
+            ("b.txt".to_string(), 1),
+            ("c.txt".to_string(), 3),
+            ("a.txt".to_string(), 1),
+            ("d.txt".to_string(), 0),
+        ];
+        sort_counts(&mut counts);
+        assert_eq!(
+            counts,
+            vec![
+                ("c.txt".to_string(), 3),
+                ("a.txt".to_string(), 1),
+                ("b.txt".to_string(), 1),
+                ("d.txt".to_string(), 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_lines() {
+        let text = b"Lorem\nIpsum\r\nDOLOR";
+        // The pattern _or_ should match the one line, "Lorem"
+        let re1 = Regex::new("or").unwrap();
+        let matches = find_lines(Cursor::new(&text), &re1, false, false, Context::default());
+        assert!(matches.is_ok());
+        assert_eq!(matches.unwrap().len(), 1);
+        // When inverted, the function should match the other two lines
+        let matches = find_lines(Cursor::new(&text), &re1, true, false, Context::default());
+        assert!(matches.is_ok());
+        assert_eq!(matches.unwrap().len(), 2);
+        // This regex will be case-insensitive
+        let re2 = RegexBuilder::new("or")
+            .case_insensitive(true)
+            .build()
+            .unwrap();
+        // The two lines "Lorem" and "DOLOR" should match
+        let matches = find_lines(Cursor::new(&text), &re2, false, false, Context::default());
+        assert!(matches.is_ok());
+        assert_eq!(matches.unwrap().len(), 2);
+        // When inverted, the one remaining line should match
+        let matches = find_lines(Cursor::new(&text), &re2, true, false, Context::default());
+        assert!(matches.is_ok());
+        assert_eq!(matches.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_print_groups() {
+        let re = Regex::new(r"(\w+)=(\d+)?").unwrap();
+        let mut out = Vec::new();
+        print_groups(&mut out, "f:", "a=1 b= c=3\r\n", &re, &[1, 2]).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "f:a\t1\nf:b\t\nf:c\t3\n");
+
+        let mut out = Vec::new();
+        print_groups(&mut out, "", "x=7\n", &re, &[2, 0, 2]).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "7\tx=7\t7\n");
+    }
+
+    #[test]
+    fn test_find_lines_passthru() {
+        let text = b"Lorem\nIpsum\r\nDOLOR";
+        let re = Regex::new("or").unwrap();
+        let matches = find_lines(Cursor::new(&text), &re, false, true, Context::default()).unwrap();
+        let found: Vec<_> = matches.iter().map(|m| (m.matched, m.selected)).collect();
+        assert_eq!(found, [(Some((1, 3)), true), (None, false), (None, false)]);
+
+        // Inverted, the matching line is kept for highlighting but not selected
+        let matches = find_lines(Cursor::new(&text), &re, true, true, Context::default()).unwrap();
+        let found: Vec<_> = matches.iter().map(|m| (m.matched, m.selected)).collect();
+        assert_eq!(found, [(Some((1, 3)), false), (None, true), (None, true)]);
+    }
+
+    #[test]
+    fn test_find_lines_bom_and_crlf() {
+        let text = "\u{feff}first\r\nsecond\nthird\r\n";
+        let re = Regex::new("^(first|third)$").unwrap();
+        let matches = find_lines(Cursor::new(text), &re, false, false, Context::default()).unwrap();
+        let lines: Vec<_> = matches.iter().map(|m| m.line.as_str()).collect();
+        assert_eq!(lines, ["first\r\n", "third\r\n"]);
+        assert_eq!(matches[0].matched, Some((0, 5)));
+
+        // Only a mark at the very start is special
+        let matches = find_lines(
+            Cursor::new("a\n\u{feff}b\n"),
+            &re,
+            true,
+            false,
+            Context::default(),
+        )
+        .unwrap();
+        assert_eq!(matches[1].line, "\u{feff}b\n");
+    }
+
+    #[test]
+    fn test_find_lines_context() {
+        let text = "a\nmatch\nb\nc\nd\ne\nmatch\n";
+        let re = Regex::new("match").unwrap();
+        let found = |context| {
+            find_lines(Cursor::new(text), &re, false, false, context)
+                .unwrap()
+                .iter()
+                .map(|m| (m.number, m.selected))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            found(Context {
+                before: 1,
+                after: 1
+            }),
+            [(1, false), (2, true), (3, false), (6, false), (7, true)]
+        );
+        // The windows of both matches meet, so every line is printed
+        assert_eq!(
+            found(Context {
+                before: 2,
+                after: 2
+            })
+            .len(),
+            7
+        );
+        assert_eq!(
+            found(Context {
+                before: 0,
+                after: 9
+            }),
+            [
+                (2, true),
+                (3, false),
+                (4, false),
+                (5, false),
+                (6, false),
+                (7, true)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_args_separator() {
+        let args = Args::parse_from(["grepr", "x"]);
+        assert_eq!(args.separator(), None);
+        let args = Args::parse_from(["grepr", "-A", "0", "x"]);
+        assert_eq!(args.separator(), Some("--"));
+        assert_eq!(args.context(), Context::default());
+        let args = Args::parse_from(["grepr", "-C3", "-A1", "--no-context-separator", "x"]);
+        assert_eq!(args.separator(), None);
+        assert_eq!(
+            args.context(),
+            Context {
+                before: 3,
+                after: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_line_body() {
+        assert_eq!(line_body("a\r\n"), "a");
+        assert_eq!(line_body("a\n"), "a");
+        assert_eq!(line_body("a\r"), "a");
+        assert_eq!(line_body("a"), "a");
+        assert_eq!(line_body("a\r\r\n"), "a\r");
+    }
+}
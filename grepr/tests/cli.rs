@@ -1,5 +1,5 @@
 use anyhow::Result;
-use assert_cmd::cargo::cargo_bin_cmd;
+use assert_cmd::{Command, cargo::cargo_bin_cmd};
 use predicates::prelude::*;
 use pretty_assertions::assert_eq;
 use rand::{Rng, distributions::Alphanumeric};
@@ -11,6 +11,19 @@ const EMPTY: &str = "tests/inputs/empty.txt";
 const FOX: &str = "tests/inputs/fox.txt";
 const NOBODY: &str = "tests/inputs/nobody.txt";
 const INPUTS_DIR: &str = "tests/inputs";
+const CONFIG_HOME: &str = "tests/config";
+const ONE: &str = "tests/context/one.txt";
+const TWO: &str = "tests/context/two.txt";
+
+// --------------------------------------------------
+/// grepr without the defaults of whoever runs the tests: there is no
+/// config file under tests/inputs and GREPR_OPTIONS is empty.
+fn grepr() -> Command {
+    let mut cmd = cargo_bin_cmd!();
+    cmd.env("XDG_CONFIG_HOME", INPUTS_DIR)
+        .env("GREPR_OPTIONS", "");
+    cmd
+}
 
 // --------------------------------------------------
 fn gen_bad_file() -> String {
@@ -30,7 +43,7 @@ fn gen_bad_file() -> String {
 // --------------------------------------------------
 #[test]
 fn dies_no_args() -> Result<()> {
-    cargo_bin_cmd!()
+    grepr()
         .assert()
         .failure()
         .stderr(predicate::str::contains("Usage"));
@@ -40,7 +53,7 @@ fn dies_no_args() -> Result<()> {
 // --------------------------------------------------
 #[test]
 fn dies_bad_pattern() -> Result<()> {
-    cargo_bin_cmd!()
+    grepr()
         .args(["*foo", FOX])
         .assert()
         .failure()
@@ -53,7 +66,7 @@ fn dies_bad_pattern() -> Result<()> {
 fn warns_bad_file() -> Result<()> {
     let bad = gen_bad_file();
     let expected = format!("{bad}: .* [(]os error 2[)]");
-    cargo_bin_cmd!()
+    grepr()
         .args(["foo", &bad])
         .assert()
         .stderr(predicate::str::is_match(expected)?);
@@ -73,7 +86,7 @@ macro_rules! run {
             };
 
             let expected = fs::read_to_string(expected_file).expect("input-fail");
-            let output = cargo_bin_cmd!().args(args).output().expect("fail");
+            let output = grepr().args(args).output().expect("fail");
             assert!(output.status.success());
 
             let stdout = String::from_utf8(output.stdout).expect("invalid UTF-8");
@@ -184,6 +197,71 @@ fn recursive_insensitive() -> Result<()> {
     )
 }
 
+// --------------------------------------------------
+/// A directory with a match in each of ‘b’, ‘a/z’, ‘a-c’ and ‘a/y’, created
+/// in that order and modified in the order ‘a/y’, ‘b’, ‘a-c’, ‘a/z’.
+fn sort_inputs() -> Result<tempfile::TempDir> {
+    let dir = tempfile::tempdir()?;
+    fs::create_dir(dir.path().join("a"))?;
+    let start = std::time::SystemTime::now() - std::time::Duration::from_secs(60);
+    for (name, age) in [("b", 3), ("a/z", 1), ("a-c", 2), ("a/y", 4)] {
+        let path = dir.path().join(name);
+        fs::write(&path, format!("{name}\n"))?;
+        fs::File::options()
+            .write(true)
+            .open(&path)?
+            .set_modified(start - std::time::Duration::from_secs(age))?;
+    }
+    Ok(dir)
+}
+
+// --------------------------------------------------
+#[test]
+fn recursive_sorted_by_path() -> Result<()> {
+    let dir = sort_inputs()?;
+    let output = grepr()
+        .current_dir(dir.path())
+        .args(["-r", "[a-z]", "."])
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        "./a/y:a/y\n./a/z:a/z\n./a-c:a-c\n./b:b\n"
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn recursive_sorted_by_mtime() -> Result<()> {
+    let dir = sort_inputs()?;
+    let output = grepr()
+        .current_dir(dir.path())
+        .args(["-r", "--sort", "mtime", "[a-z]", "."])
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        "./a/y:a/y\n./b:b\n./a-c:a-c\n./a/z:a/z\n"
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn recursive_unsorted() -> Result<()> {
+    let dir = sort_inputs()?;
+    let output = grepr()
+        .current_dir(dir.path())
+        .args(["-r", "--sort=none", "[a-z]", "."])
+        .output()?;
+    assert!(output.status.success());
+    let mut lines: Vec<_> = std::str::from_utf8(&output.stdout)?.lines().collect();
+    lines.sort();
+    assert_eq!(lines, ["./a-c:a-c", "./a/y:a/y", "./a/z:a/z", "./b:b"]);
+    Ok(())
+}
+
 // --------------------------------------------------
 #[test]
 fn sensitive_count_capital() -> Result<()> {
@@ -267,7 +345,7 @@ fn insensitive_count_multiple() -> Result<()> {
 fn warns_dir_not_recursive() -> Result<()> {
     let stdout = "tests/inputs/fox.txt:\
         The quick brown fox jumps over the lazy dog.";
-    cargo_bin_cmd!()
+    grepr()
         .args(["fox", INPUTS_DIR, FOX])
         .assert()
         .stderr(predicate::str::contains("tests/inputs is a directory"))
@@ -281,7 +359,7 @@ fn stdin() -> Result<()> {
     let input = fs::read_to_string(BUSTLE)?;
     let expected = fs::read_to_string("tests/expected/bustle.txt.the.capitalized")?;
 
-    let output = cargo_bin_cmd!()
+    let output = grepr()
         .arg("The")
         .write_stdin(input)
         .output()
@@ -306,7 +384,7 @@ fn stdin_insensitive_count() -> Result<()> {
     let expected_file = "tests/expected/the.recursive.insensitive.count.stdin";
     let expected = fs::read_to_string(expected_file)?;
 
-    let output = cargo_bin_cmd!()
+    let output = grepr()
         .args(["-ci", "the", "-"])
         .write_stdin(input)
         .output()
@@ -317,3 +395,487 @@ fn stdin_insensitive_count() -> Result<()> {
     assert_eq!(stdout, expected);
     Ok(())
 }
+
+// --------------------------------------------------
+#[test]
+fn recursive_max_depth() -> Result<()> {
+    run!(
+        "tests/expected/dog.nested.max-depth",
+        "-r",
+        "--max-depth",
+        "1",
+        "dog",
+        "tests/nested",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn recursive_max_filesize() -> Result<()> {
+    // nobody.txt is the only file over 200 bytes
+    run!(
+        "tests/expected/empty.foo",
+        "-ri",
+        "--max-filesize",
+        "200",
+        "nobody",
+        INPUTS_DIR,
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn debug_explains_skipped_files() -> Result<()> {
+    grepr()
+        .args([
+            "--debug",
+            "-ri",
+            "--max-filesize",
+            "200",
+            "nobody",
+            INPUTS_DIR,
+        ])
+        .env_remove("RUST_LOG")
+        .assert()
+        .success()
+        .stdout("")
+        .stderr(predicate::str::is_match(
+            "skipped, 248 bytes is over --max-filesize path=.*nobody.txt",
+        )?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_max_filesize() -> Result<()> {
+    grepr()
+        .args(["-r", "--max-filesize", "10X", "dog", INPUTS_DIR])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid suffix in size '10X'"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_max_depth_not_recursive() -> Result<()> {
+    grepr()
+        .args(["--max-depth", "1", "dog", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--recursive"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn config_file_defaults() -> Result<()> {
+    let output = grepr()
+        .env("XDG_CONFIG_HOME", CONFIG_HOME)
+        .env_remove("GREPR_OPTIONS")
+        .args(["the", BUSTLE])
+        .output()
+        .expect("fail");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("invalid UTF-8");
+    assert!(stdout.contains("\u{1b}[35mThe\u{1b}[0m bustle"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn config_file_overridden_by_args() -> Result<()> {
+    let expected = fs::read_to_string("tests/expected/bustle.txt.the.lowercase.insensitive")?;
+    grepr()
+        .env("XDG_CONFIG_HOME", CONFIG_HOME)
+        .env_remove("GREPR_OPTIONS")
+        .args(["--color=never", "the", BUSTLE])
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn grepr_options_env() -> Result<()> {
+    let expected = fs::read_to_string("tests/expected/bustle.txt.the.lowercase.insensitive.count")?;
+    grepr()
+        .env("XDG_CONFIG_HOME", CONFIG_HOME)
+        .env("GREPR_OPTIONS", "--count --color=never")
+        .args(["the", BUSTLE])
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_grepr_options() -> Result<()> {
+    grepr()
+        .env("XDG_CONFIG_HOME", CONFIG_HOME)
+        .env("GREPR_OPTIONS", "--no-such-flag")
+        .args(["the", BUSTLE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--no-such-flag"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_pattern_in_grepr_options() -> Result<()> {
+    grepr()
+        .env("GREPR_OPTIONS", "-i fox")
+        .args(["the", BUSTLE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "GREPR_OPTIONS: 'fox' is not an option",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn recursive_insensitive_sort_count() -> Result<()> {
+    run!(
+        "tests/expected/the.recursive.insensitive.sort-count",
+        "-rci",
+        "--sort-count",
+        "the",
+        INPUTS_DIR,
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn recursive_invert_sort_count() -> Result<()> {
+    run!(
+        "tests/expected/the.recursive.invert.sort-count",
+        "-rcv",
+        "--sort-count",
+        "the",
+        INPUTS_DIR,
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_sort_count_without_count() -> Result<()> {
+    grepr()
+        .args(["--sort-count", "the", BUSTLE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--count"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn passthru() -> Result<()> {
+    run!(BUSTLE, "--passthru", "--color=never", "The", BUSTLE)
+}
+
+// --------------------------------------------------
+#[test]
+fn passthru_color() -> Result<()> {
+    let output = grepr()
+        .args(["--passthru", "--color=always", "The", BUSTLE])
+        .output()
+        .expect("fail");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("invalid UTF-8");
+    let lines: Vec<_> = stdout.lines().collect();
+    assert_eq!(lines.len(), 9);
+    assert_eq!(lines[0], "\u{1b}[35mThe\u{1b}[0m bustle in a house");
+    assert_eq!(lines[2], "Is solemnest of industries");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn passthru_count() -> Result<()> {
+    run!(
+        "tests/expected/bustle.txt.the.capitalized.count",
+        "--passthru",
+        "--count",
+        "The",
+        BUSTLE
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn group() -> Result<()> {
+    grepr()
+        .args(["--color=never", "--group", "1", r"(\w+) fox", FOX])
+        .assert()
+        .success()
+        .stdout("brown\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn groups_tab_separated() -> Result<()> {
+    grepr()
+        .args([
+            "--color=never",
+            "--group",
+            "2",
+            "--group=1",
+            r"user=(\w+) id=(\d+)",
+        ])
+        .write_stdin("user=ann id=1 user=bob id=22\nnothing here\nuser=cy id=3\n")
+        .assert()
+        .success()
+        .stdout("1\tann\n22\tbob\n3\tcy\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_group() -> Result<()> {
+    grepr()
+        .args(["--group", "2", "(fox)", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            r#"No capture group 2 in pattern "(fox)""#,
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn bom_not_printed() -> Result<()> {
+    grepr()
+        .args(["--color=never", "^The", "tests/eol/bom.txt", FOX])
+        .assert()
+        .success()
+        .stdout(
+            "tests/eol/bom.txt:The first line starts with a mark\n\
+             tests/inputs/fox.txt:The quick brown fox jumps over the lazy dog.\n",
+        );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn bom_on_stdin() -> Result<()> {
+    grepr()
+        .args(["--color=never", "-c", "^the"])
+        .write_stdin("\u{feff}the first\nthe second\n")
+        .assert()
+        .success()
+        .stdout("2\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn crlf_end_anchor() -> Result<()> {
+    grepr()
+        .args(["--color=never", r"5\$$", "tests/eol/crlf.txt"])
+        .assert()
+        .success()
+        .stdout("costs 5$\r\n");
+    grepr()
+        .args(["--color=never", "-v", "5$", "tests/eol/crlf.txt"])
+        .assert()
+        .success()
+        .stdout("costs 5$\r\ncosts nothing\r\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn crlf_color_keeps_terminator() -> Result<()> {
+    grepr()
+        .args(["--color=always", "nothing$", "tests/eol/crlf.txt"])
+        .assert()
+        .success()
+        .stdout("costs \x1b[35mnothing\x1b[0m\r\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn context_across_files() -> Result<()> {
+    grepr()
+        .args(["--color=never", "-A1", "match", ONE, TWO])
+        .assert()
+        .success()
+        .stdout(
+            "tests/context/one.txt:match three\n\
+             tests/context/one.txt-gamma\n\
+             --\n\
+             tests/context/one.txt:match seven\n\
+             --\n\
+             tests/context/two.txt:match one\n\
+             tests/context/two.txt-zeta\n",
+        );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn after_context_resets_at_file_end() -> Result<()> {
+    // one.txt ends with a match, and none of fox.txt is its context
+    grepr()
+        .args(["--color=never", "-A2", "seven", ONE, FOX])
+        .assert()
+        .success()
+        .stdout("tests/context/one.txt:match seven\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn before_context_resets_at_file_start() -> Result<()> {
+    // two.txt starts with a match, and none of fox.txt is its context
+    grepr()
+        .args(["--color=never", "-B2", "match", FOX, TWO])
+        .assert()
+        .success()
+        .stdout("tests/context/two.txt:match one\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn context_merges_overlapping_windows() -> Result<()> {
+    grepr()
+        .args(["--color=never", "-C2", "match", ONE])
+        .assert()
+        .success()
+        .stdout("alpha\nbeta\nmatch three\ngamma\ndelta\nepsilon\nmatch seven\n");
+    grepr()
+        .args(["--color=never", "-C1", "-B0", "match", ONE])
+        .assert()
+        .success()
+        .stdout("match three\ngamma\n--\nmatch seven\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn context_separator() -> Result<()> {
+    grepr()
+        .args([
+            "--color=never",
+            "-A1",
+            "--context-separator",
+            "~~",
+            "match",
+            ONE,
+        ])
+        .assert()
+        .success()
+        .stdout("match three\ngamma\n~~\nmatch seven\n");
+    grepr()
+        .args([
+            "--color=never",
+            "-A1",
+            "--no-context-separator",
+            "match",
+            ONE,
+        ])
+        .assert()
+        .success()
+        .stdout("match three\ngamma\nmatch seven\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn no_separator_without_context() -> Result<()> {
+    grepr()
+        .args(["--color=never", "--context-separator", "~~", "match", ONE])
+        .assert()
+        .success()
+        .stdout("match three\nmatch seven\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_context_with_count() -> Result<()> {
+    grepr()
+        .args(["-c", "-C1", "match", ONE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn files_from() -> Result<()> {
+    let list = tempfile::NamedTempFile::new()?;
+    fs::write(list.path(), format!("{FOX}\n\n{EMPTY}\n"))?;
+    grepr()
+        .args(["--color=never", "--files-from"])
+        .arg(list.path())
+        .arg("dog")
+        .assert()
+        .success()
+        .stdout(format!(
+            "{FOX}:The quick brown fox jumps over the lazy dog.\n"
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn files_from_stdin_nul() -> Result<()> {
+    grepr()
+        .args(["--color=never", "-c", "--files-from", "-", "the"])
+        .write_stdin(format!("{FOX}\0{EMPTY}\0"))
+        .assert()
+        .success()
+        .stdout(format!("{FOX}:1\n{EMPTY}:0\n"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn exclude_from() -> Result<()> {
+    let list = tempfile::NamedTempFile::new()?;
+    fs::write(list.path(), "bustle.*\nnobody.txt\n")?;
+    grepr()
+        .args(["--color=never", "-ric", "--exclude-from"])
+        .arg(list.path())
+        .args(["the", INPUTS_DIR])
+        .assert()
+        .success()
+        .stdout(format!("{EMPTY}:0\n{FOX}:1\n"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_files_from_with_files() -> Result<()> {
+    grepr()
+        .args(["--files-from", "-", "the", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_missing_files_from() -> Result<()> {
+    let bad = gen_bad_file();
+    grepr()
+        .args(["--files-from", &bad, "the"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!("{bad}: ")));
+    Ok(())
+}
@@ -0,0 +1,392 @@
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    ffi::OsString,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+};
+
+use anyhow::{Result, anyhow, bail};
+use clap::{CommandFactory, Parser};
+use learnr::{HeaderFormat, HeaderedFiles};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+#[derive(Debug, Parser)]
+#[command(author, version, about)]
+/// Rust version of ‘head’
+///
+/// The obsolete ‘-NUM’ form is accepted as the first argument, meaning ‘-n NUM’.
+struct Args {
+    /// Input file(s)
+    #[arg(value_name = "FILE", default_value = "-")]
+    files: Vec<String>,
+
+    /// Number of lines to print (suffixes like K or MiB multiply); with a
+    /// leading ‘-’, print all but the last LINES lines
+    #[arg(
+        value_name("LINES"),
+        short('n'),
+        long,
+        default_value = "10",
+        value_parser = parse_count,
+        allow_hyphen_values = true,
+        conflicts_with("bytes")
+    )]
+    lines: Count,
+
+    /// Number of bytes to print (suffixes like K or MiB multiply); with a
+    /// leading ‘-’, print all but the last BYTES bytes
+    #[arg(
+        value_name("BYTES"),
+        short('c'),
+        long,
+        value_parser = parse_count,
+        allow_hyphen_values = true,
+    )]
+    bytes: Option<Count>,
+
+    /// Number of characters to print; multi-byte UTF-8 sequences are never
+    /// split
+    #[arg(
+        value_name("CHARS"),
+        short('m'),
+        long,
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with_all(["bytes", "lines"])
+    )]
+    chars: Option<u64>,
+
+    /// Cut each printed line to at most N display columns, ending the cut
+    /// ones with ‘…’
+    #[arg(
+        value_name("N"),
+        long,
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with_all(["bytes", "chars"])
+    )]
+    max_width: Option<u64>,
+
+    /// Write to FILE instead of standard output
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<String>,
+
+    /// How headers name each file, for programs reading the output
+    #[arg(long, value_name = "FORMAT", value_enum, default_value_t)]
+    header_format: HeaderFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Count {
+    First(u64),
+    AllButLast(u64),
+}
+
+/// Parses a count with an optional multiplier suffix (‘10K’, ‘2MB’, ‘1GiB’,
+/// see `learnr::parse_count`) and an optional leading ‘-’. Only all but the
+/// last can be 0, which means everything, as for GNU head.
+fn parse_count(arg: &str) -> Result<Count> {
+    let (size, all_but_last) = match arg.strip_prefix('-') {
+        Some(size) => (size, true),
+        None => (arg, false),
+    };
+    match learnr::parse_count(size)? {
+        0 if !all_but_last => bail!("the count must be at least 1"),
+        num if all_but_last => Ok(Count::AllButLast(num)),
+        num => Ok(Count::First(num)),
+    }
+}
+
+/// The command line that [`main`] parses, for generating the man page.
+pub fn command() -> clap::Command {
+    Args::command()
+}
+
+/// What the man page tells beyond --help.
+pub const MAN_DESCRIPTION: &str = "\
+Each FILE is printed up to the given number of lines, bytes or characters, 10 \
+lines by default, with a ‘==> FILE <==’ header before each when there are \
+several. With --header-format the header is ‘# FILE’ or a JSON object \
+instead, which programs reading the output can tell apart from the lines.\n\n\
+Lines stay intact, with their own terminators, so files with Windows line \
+endings or without a final newline are printed as they are.";
+
+pub fn main(args: impl IntoIterator<Item = OsString>) {
+    let args = Args::parse_from(learnr::expand_legacy_count(args));
+    if let Err(e) = run(args) {
+        learnr::exit_on_broken_pipe(&e);
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Args) -> Result<()> {
+    let mut out: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(BufWriter::new(
+            File::create(path).map_err(|err| anyhow!("{path}: {err}"))?,
+        )),
+        None => Box::new(io::stdout().lock()),
+    };
+
+    let max_width = args.max_width.map(|width| width as usize);
+    let mut headers = HeaderedFiles::new((args.files.len() > 1).then_some(args.header_format));
+    for filename in &args.files {
+        headers.start(&mut out, filename)?;
+        open(filename)
+            .and_then(|file| match args.chars {
+                Some(chars) => process_chars(file, chars, &mut out),
+                None => process_file(file, args.lines, args.bytes, max_width, &mut out),
+            })
+            .unwrap_or_else(|err| {
+                learnr::exit_on_broken_pipe(&err);
+                eprintln!("{filename}: {err}");
+            });
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn process_file(
+    file: Box<dyn BufRead>,
+    lines: Count,
+    bytes: Option<Count>,
+    max_width: Option<usize>,
+    out: &mut impl Write,
+) -> Result<()> {
+    match (bytes, lines) {
+        (Some(Count::First(bytes)), _) => process_bytes(file, bytes, out),
+        (Some(Count::AllButLast(bytes)), _) => skip_last_bytes(file, bytes, out),
+        (None, Count::First(lines)) => process_lines(file, lines, max_width, out),
+        (None, Count::AllButLast(lines)) => skip_last_lines(file, lines, max_width, out),
+    }
+}
+
+/// Cuts `line` to `max_width` display columns, the last of them taken by
+/// ‘…’, keeping its terminator. Lines that fit are returned as they are.
+fn fit_width(line: &[u8], max_width: Option<usize>) -> Cow<'_, [u8]> {
+    let Some(max_width) = max_width else {
+        return Cow::Borrowed(line);
+    };
+    let body_len = line.strip_suffix(b"\n").map_or(line.len(), <[u8]>::len);
+    let body_len = line[..body_len]
+        .strip_suffix(b"\r")
+        .map_or(body_len, <[u8]>::len);
+    let body = String::from_utf8_lossy(&line[..body_len]);
+    if body.width() <= max_width {
+        return Cow::Borrowed(line);
+    }
+
+    let mut cut = String::new();
+    let mut width = 0;
+    for ch in body.chars() {
+        let ch_width = ch.width().unwrap_or(0);
+        if width + ch_width > max_width - 1 {
+            break;
+        }
+        cut.push(ch);
+        width += ch_width;
+    }
+    cut.push('…');
+    let mut cut = cut.into_bytes();
+    cut.extend_from_slice(&line[body_len..]);
+    Cow::Owned(cut)
+}
+
+/// Prints all lines but the last `lines`, holding back only that many lines.
+fn skip_last_lines(
+    mut file: Box<dyn BufRead>,
+    lines: u64,
+    max_width: Option<usize>,
+    out: &mut impl Write,
+) -> Result<()> {
+    let mut held: VecDeque<Vec<u8>> = VecDeque::new();
+    loop {
+        let mut line = Vec::new();
+        if file.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        held.push_back(line);
+        if held.len() as u64 > lines
+            && let Some(line) = held.pop_front()
+        {
+            out.write_all(&fit_width(&line, max_width))?;
+        }
+    }
+    Ok(())
+}
+
+/// Prints all bytes but the last `bytes`, holding back only that many bytes.
+fn skip_last_bytes(mut file: Box<dyn BufRead>, bytes: u64, out: &mut impl Write) -> Result<()> {
+    let mut held: VecDeque<u8> = VecDeque::new();
+    loop {
+        let buf = file.fill_buf()?;
+        if buf.is_empty() {
+            break;
+        }
+        held.extend(buf);
+        let len = buf.len();
+        file.consume(len);
+
+        let excess = (held.len() as u64).saturating_sub(bytes) as usize;
+        let (front, back) = held.as_slices();
+        let from_front = excess.min(front.len());
+        out.write_all(&front[..from_front])?;
+        out.write_all(&back[..excess - from_front])?;
+        held.drain(..excess);
+    }
+    Ok(())
+}
+
+fn process_bytes(mut file: Box<dyn BufRead>, bytes: u64, out: &mut impl Write) -> Result<()> {
+    let mut bytes = bytes as usize;
+    loop {
+        assert!(bytes > 0);
+        let buf = file.fill_buf()?;
+
+        let bytes_read: usize = buf.len();
+
+        if bytes_read == 0 {
+            break;
+        }
+
+        if bytes <= bytes_read {
+            out.write_all(&buf[0..bytes])?;
+            break;
+        }
+
+        out.write_all(buf)?;
+        bytes -= bytes_read;
+
+        file.consume(bytes_read);
+    }
+    Ok(())
+}
+
+/// A character starts at every byte that isn't a UTF-8 continuation byte,
+/// so sequences are never split, even when they straddle two reads. Invalid
+/// bytes count as one character each.
+fn process_chars(mut file: Box<dyn BufRead>, chars: u64, out: &mut impl Write) -> Result<()> {
+    let mut remaining = chars;
+    loop {
+        let buf = file.fill_buf()?;
+        if buf.is_empty() {
+            break;
+        }
+
+        let end = buf.iter().position(|byte| {
+            if byte & 0b1100_0000 == 0b1000_0000 {
+                return false;
+            }
+            if remaining == 0 {
+                return true;
+            }
+            remaining -= 1;
+            false
+        });
+        out.write_all(&buf[..end.unwrap_or(buf.len())])?;
+        if end.is_some() {
+            break;
+        }
+
+        let len = buf.len();
+        file.consume(len);
+    }
+    Ok(())
+}
+
+fn process_lines(
+    mut file: Box<dyn BufRead>,
+    mut lines: u64,
+    max_width: Option<usize>,
+    out: &mut impl Write,
+) -> Result<()> {
+    let mut line = Vec::new();
+    while lines > 0 {
+        line.clear();
+        if file.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        out.write_all(&fit_width(&line, max_width))?;
+        lines -= 1;
+    }
+    Ok(())
+}
+
+fn open(filename: &str) -> Result<Box<dyn BufRead>> {
+    match filename {
+        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
+        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn head(input: &str, lines: Count, bytes: Option<Count>) -> String {
+        let file: Box<dyn BufRead> = Box::new(io::Cursor::new(input.to_string()));
+        let mut out = Vec::new();
+        process_file(file, lines, bytes, None, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn head_chars(input: &[u8], chars: u64, capacity: usize) -> Vec<u8> {
+        let file: Box<dyn BufRead> = Box::new(BufReader::with_capacity(
+            capacity,
+            io::Cursor::new(input.to_vec()),
+        ));
+        let mut out = Vec::new();
+        process_chars(file, chars, &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_process_chars() {
+        assert_eq!(head_chars("héllo".as_bytes(), 2, 1024), "hé".as_bytes());
+        assert_eq!(head_chars("héllo".as_bytes(), 10, 1024), "héllo".as_bytes());
+        // One byte at a time still never splits ‘é’ or the emoji
+        assert_eq!(head_chars("héllo".as_bytes(), 2, 1), "hé".as_bytes());
+        assert_eq!(head_chars("a🦀b".as_bytes(), 2, 1), "a🦀".as_bytes());
+        assert_eq!(head_chars(b"\xff\xfeab", 3, 1024), b"\xff\xfea");
+    }
+
+    #[test]
+    fn test_process_file() {
+        let input = "one\ntwo\nthree\n";
+        assert_eq!(head(input, Count::First(2), None), "one\ntwo\n");
+        assert_eq!(head(input, Count::First(10), None), input);
+        assert_eq!(head(input, Count::AllButLast(1), None), "one\ntwo\n");
+        assert_eq!(
+            head(input, Count::First(10), Some(Count::First(5))),
+            "one\nt"
+        );
+        assert_eq!(
+            head(input, Count::First(10), Some(Count::AllButLast(5))),
+            "one\ntwo\nt"
+        );
+    }
+
+    #[test]
+    fn test_process_lines_invalid_utf8() {
+        let file: Box<dyn BufRead> = Box::new(io::Cursor::new(b"caf\xe9\n\xff\nok\n".to_vec()));
+        let mut out = Vec::new();
+        process_lines(file, 2, None, &mut out).unwrap();
+        assert_eq!(out, b"caf\xe9\n\xff\n");
+    }
+
+    #[test]
+    fn test_fit_width() {
+        let fit = |line: &str, width| {
+            String::from_utf8(fit_width(line.as_bytes(), Some(width)).into_owned()).unwrap()
+        };
+        assert_eq!(fit("abcdef\n", 6), "abcdef\n");
+        assert_eq!(fit("abcdefg\n", 6), "abcde…\n");
+        assert_eq!(fit("abcdefg\r\n", 4), "abc…\r\n");
+        assert_eq!(fit("abcdefg", 1), "…");
+        // Wide characters take two columns, and are never half shown
+        assert_eq!(fit("日本語のテキスト\n", 7), "日本語…\n");
+        assert_eq!(fit("日本語のテキスト\n", 8), "日本語…\n");
+        assert_eq!(fit("日本語\n", 6), "日本語\n");
+        assert_eq!(fit_width(b"long line", None), &b"long line"[..]);
+    }
+}
@@ -5,6 +5,7 @@ use pretty_assertions::assert_eq;
 use rand::{Rng, distributions::Alphanumeric};
 use std::fs::{self, File};
 use std::io::prelude::*;
+use std::process::Stdio;
 
 const EMPTY: &str = "./tests/inputs/empty.txt";
 const ONE: &str = "./tests/inputs/one.txt";
@@ -37,14 +38,14 @@ fn dies_bad_bytes() -> Result<()> {
     let bad = random_string();
     let expected = format!(
         "invalid value '{bad}' for \
-        '--bytes <BYTES>': invalid digit found in string"
+        '--bytes <BYTES>': invalid (suffix in )?count '{bad}'"
     );
 
     cargo_bin_cmd!()
         .args(["-c", &bad, EMPTY])
         .assert()
         .failure()
-        .stderr(predicate::str::contains(expected));
+        .stderr(predicate::str::is_match(expected).unwrap());
 
     Ok(())
 }
@@ -55,13 +56,13 @@ fn dies_bad_lines() -> Result<()> {
     let bad = random_string();
     let expected = format!(
         "error: invalid value '{bad}' for \
-        '--lines <LINES>': invalid digit found in string"
+        '--lines <LINES>': invalid (suffix in )?count '{bad}'"
     );
     cargo_bin_cmd!()
         .args(["-n", &bad, EMPTY])
         .assert()
         .failure()
-        .stderr(predicate::str::contains(expected));
+        .stderr(predicate::str::is_match(expected).unwrap());
 
     Ok(())
 }
@@ -418,3 +419,313 @@ fn multiple_files_c4() -> Result<()> {
         "tests/expected/all.c4.out",
     )
 }
+
+// --------------------------------------------------
+#[test]
+fn twelve_attached_n2() -> Result<()> {
+    run(&["-n2", TWELVE], "tests/expected/twelve.txt.n2.out")
+}
+
+#[test]
+fn twelve_attached_c4() -> Result<()> {
+    run(&["-c4", TWELVE], "tests/expected/twelve.txt.c4.out")
+}
+
+#[test]
+fn twelve_legacy_n4() -> Result<()> {
+    run(&["-4", TWELVE], "tests/expected/twelve.txt.n4.out")
+}
+
+#[test]
+fn twelve_legacy_c2() -> Result<()> {
+    run(&["-2c", TWELVE], "tests/expected/twelve.txt.c2.out")
+}
+
+#[test]
+fn multiple_files_legacy_n2() -> Result<()> {
+    run(
+        &["-2", EMPTY, ONE, TWO, THREE, TWELVE],
+        "tests/expected/all.n2.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_legacy_zero() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-0", TWELVE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("'--lines <LINES>'"));
+    Ok(())
+}
+
+#[test]
+fn dies_bad_legacy_suffix() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-5x", TWELVE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unexpected argument '-5'"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn empty_all_but_last_n3() -> Result<()> {
+    run(&[EMPTY, "-n", "-3"], "tests/expected/empty.txt.n-3.out")
+}
+
+#[test]
+fn one_all_but_last_n3() -> Result<()> {
+    run(&[ONE, "-n", "-3"], "tests/expected/one.txt.n-3.out")
+}
+
+#[test]
+fn twelve_all_but_last_n3() -> Result<()> {
+    run(&[TWELVE, "-n", "-3"], "tests/expected/twelve.txt.n-3.out")
+}
+
+#[test]
+fn twelve_all_but_last_n3_stdin() -> Result<()> {
+    run_stdin(&["-n=-3"], TWELVE, "tests/expected/twelve.txt.n-3.out")
+}
+
+#[test]
+fn empty_all_but_last_c5() -> Result<()> {
+    run(&[EMPTY, "-c", "-5"], "tests/expected/empty.txt.c-5.out")
+}
+
+#[test]
+fn one_all_but_last_c5() -> Result<()> {
+    run(&[ONE, "-c", "-5"], "tests/expected/one.txt.c-5.out")
+}
+
+#[test]
+fn twelve_all_but_last_c5() -> Result<()> {
+    run(
+        &[TWELVE, "--bytes", "-5"],
+        "tests/expected/twelve.txt.c-5.out",
+    )
+}
+
+#[test]
+fn multiple_files_all_but_last_n2() -> Result<()> {
+    run(
+        &["-n", "-2", EMPTY, ONE, TWO, THREE, TWELVE],
+        "tests/expected/all.n-2.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_zero_count() -> Result<()> {
+    for flag in ["-n", "-c"] {
+        cargo_bin_cmd!()
+            .args([flag, "0", TWELVE])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("the count must be at least 1"));
+    }
+    Ok(())
+}
+
+#[test]
+fn all_but_zero() -> Result<()> {
+    // All but the last none is everything
+    let expected = fs::read_to_string(TWELVE)?;
+    for flag in ["-n", "-c"] {
+        cargo_bin_cmd!()
+            .args([flag, "-0", TWELVE])
+            .assert()
+            .success()
+            .stdout(expected.clone());
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn twelve_size_suffix_c1k() -> Result<()> {
+    run(&[TWELVE, "-c", "1K"], "tests/expected/twelve.txt.c1K.out")
+}
+
+#[test]
+fn twelve_size_suffix_n1kib() -> Result<()> {
+    run(&[TWELVE, "-n", "1KiB"], "tests/expected/twelve.txt.c1K.out")
+}
+
+#[test]
+fn twelve_size_suffix_all_but_last_c1b() -> Result<()> {
+    run(&[TWELVE, "-c", "-1b"], "tests/expected/empty.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_size_suffix() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-c", "10X", TWELVE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid suffix in count '10X'"));
+    Ok(())
+}
+
+#[test]
+fn dies_size_too_large() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-n", "16E", TWELVE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("count '16E' is too large"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn output_file() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let out = dir.path().join("head.out");
+    let out_path = out.to_str().unwrap();
+    cargo_bin_cmd!()
+        .args(["-n", "2", "-o", out_path, EMPTY, ONE, TWO, THREE, TWELVE])
+        .assert()
+        .success()
+        .stdout("");
+    let expected = fs::read_to_string("tests/expected/all.n2.out")?;
+    assert_eq!(fs::read_to_string(&out)?, expected);
+    Ok(())
+}
+
+#[test]
+fn dies_bad_output_file() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-o", "no/such/dir/head.out", TWELVE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "no/such/dir/head.out: No such file",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn broken_pipe_is_quiet() -> Result<()> {
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin!())
+        .args(["-n", "100000"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // Nobody reads the output, so the first flush hits a closed pipe
+    drop(child.stdout.take());
+    let mut stdin = child.stdin.take().unwrap();
+    let line = "x".repeat(1023) + "\n";
+    for _ in 0..1000 {
+        if stdin.write_all(line.as_bytes()).is_err() {
+            break;
+        }
+    }
+    drop(stdin);
+
+    let output = child.wait_with_output()?;
+    assert_eq!(output.status.code(), Some(learnr::BROKEN_PIPE_EXIT_CODE));
+    assert_eq!(String::from_utf8(output.stderr)?, "");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn chars_multibyte() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-m", "7"])
+        .write_stdin("naïve café\n")
+        .assert()
+        .success()
+        .stdout("naïve c");
+    Ok(())
+}
+
+#[test]
+fn dies_chars_and_bytes() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-m", "1", "-c", "2", TWELVE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "the argument '--chars <CHARS>' cannot be used with '--bytes <BYTES>'",
+        ));
+    Ok(())
+}
+
+#[test]
+fn dies_zero_chars() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-m", "0", TWELVE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("0 is not in 1.."));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn max_width_truncates_lines() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--max-width", "8", "-n", "2"])
+        .write_stdin("{\"name\":\"minified\",\"size\":42}\nshort\nnot printed\n")
+        .assert()
+        .success()
+        .stdout("{\"name\"…\nshort\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn max_width_all_but_last() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--max-width=4", "-n", "-1", THREE])
+        .assert()
+        .success()
+        .stdout("Thr…\r\nlin…\r\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_max_width_with_bytes() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--max-width", "4", "-c", "2", TWELVE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "the argument '--max-width <N>' cannot be used with '--bytes <BYTES>'",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn header_format() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-n", "1", "--header-format", "comment", ONE, TWO])
+        .assert()
+        .success()
+        .stdout(format!(
+            "# {ONE}\nÖne line, four words.\n# {TWO}\nTwo lines.\n"
+        ));
+    cargo_bin_cmd!()
+        .args(["-n", "1", "--header-format=json", ONE, TWO])
+        .assert()
+        .success()
+        .stdout(format!(
+            "{{\"file\":\"{ONE}\"}}\nÖne line, four words.\n{{\"file\":\"{TWO}\"}}\nTwo lines.\n"
+        ));
+    cargo_bin_cmd!()
+        .args(["--header-format", "json", ONE])
+        .assert()
+        .success()
+        .stdout("Öne line, four words.\n");
+    Ok(())
+}
@@ -0,0 +1,30 @@
+//! headr against GNU head on generated input, where GNU head is installed.
+
+use std::sync::LazyLock;
+
+use learnr::compare::{Reference, text};
+use proptest::prelude::*;
+
+static HEAD: LazyLock<Option<Reference>> =
+    LazyLock::new(|| Reference::find(assert_cmd::cargo::cargo_bin!("headr"), "head"));
+
+// headr refuses a count of 0, which GNU head takes to print nothing
+fn count(max: i32) -> impl Strategy<Value = i32> {
+    prop_oneof![-max..0, 1..max]
+}
+
+proptest! {
+    #[test]
+    fn lines_same_as_head(input in text(), n in count(10)) {
+        if let Some(head) = &*HEAD {
+            head.check(&[format!("--lines={n}")], &input)?;
+        }
+    }
+
+    #[test]
+    fn bytes_same_as_head(input in text(), n in count(40)) {
+        if let Some(head) = &*HEAD {
+            head.check(&[format!("--bytes={n}")], &input)?;
+        }
+    }
+}
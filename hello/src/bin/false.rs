@@ -1,4 +1,3 @@
 fn main() {
-    // std::process::exit(1);
-    std::process::abort();
+    hello::r#false();
 }
@@ -1 +1,3 @@
-fn main() {}
+fn main() {
+    hello::r#true();
+}
@@ -0,0 +1,11 @@
+pub fn main() {
+    println!("Hello, world!");
+}
+
+/// What ‘true’ does: nothing, successfully.
+pub fn r#true() {}
+
+/// What ‘false’ does: fail.
+pub fn r#false() -> ! {
+    std::process::exit(1);
+}
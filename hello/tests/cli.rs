@@ -15,5 +15,5 @@ fn true_ok() {
 
 #[test]
 fn false_not_ok() {
-    cargo_bin_cmd!("false").assert().failure();
+    cargo_bin_cmd!("false").assert().code(1);
 }
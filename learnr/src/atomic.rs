@@ -0,0 +1,135 @@
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use tempfile::NamedTempFile;
+
+/// New contents for the file at a path, written to a temporary file in the
+/// same directory and only moved over the original by [`AtomicFile::commit`].
+/// Until then, and if anything fails, the original stays as it was; dropping
+/// an `AtomicFile` without committing removes the temporary file.
+///
+/// This is what makes editing in place safe, unlike ‘tool f > f’, which
+/// empties ‘f’ before the tool has read it.
+#[derive(Debug)]
+pub struct AtomicFile {
+    path: PathBuf,
+    temp: NamedTempFile,
+}
+
+impl AtomicFile {
+    /// Starts replacing `path`, which must exist: the new file gets its
+    /// permissions.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let permissions = fs::metadata(path)?.permissions();
+        let dir = match path.parent() {
+            Some(dir) if dir != Path::new("") => dir,
+            _ => Path::new("."),
+        };
+        let temp = tempfile::Builder::new()
+            .prefix(&format!(
+                ".{}.",
+                path.file_name().unwrap_or_default().to_string_lossy()
+            ))
+            .tempfile_in(dir)?;
+        temp.as_file().set_permissions(permissions)?;
+        Ok(AtomicFile {
+            path: path.to_path_buf(),
+            temp,
+        })
+    }
+
+    /// Syncs the new contents to disk and renames them over the original in
+    /// one step. With `backup_suffix`, the original is kept as well, under
+    /// its name followed by the suffix, which mustn't be empty: then the
+    /// backup would be the original itself.
+    pub fn commit(self, backup_suffix: Option<&str>) -> io::Result<()> {
+        self.temp.as_file().sync_all()?;
+        if let Some(suffix) = backup_suffix {
+            let mut backup = self.path.clone().into_os_string();
+            backup.push(suffix);
+            if Path::new(&backup) == self.path {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "the backup would replace the file itself",
+                ));
+            }
+            // A link keeps the original whole without copying it, where the
+            // filesystem has them
+            match fs::remove_file(&backup) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                _ => (),
+            }
+            if fs::hard_link(&self.path, &backup).is_err() {
+                fs::copy(&self.path, &backup)?;
+            }
+        }
+        self.temp.persist(&self.path).map_err(|err| err.error)?;
+        // The rename itself only lasts once the directory is synced too
+        if let Some(dir) = self.path.parent().filter(|dir| *dir != Path::new("")) {
+            File::open(dir)?.sync_all()?;
+        }
+        Ok(())
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.temp.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.temp.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commit() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("file");
+        fs::write(&path, "old")?;
+
+        let mut file = AtomicFile::create(&path)?;
+        file.write_all(b"new")?;
+        // Nothing changes before the commit
+        assert_eq!(fs::read_to_string(&path)?, "old");
+        file.commit(Some(".bak"))?;
+        assert_eq!(fs::read_to_string(&path)?, "new");
+        assert_eq!(fs::read_to_string(dir.path().join("file.bak"))?, "old");
+
+        // Without a commit, the temporary file goes away
+        let mut file = AtomicFile::create(&path)?;
+        file.write_all(b"lost")?;
+        drop(file);
+        assert_eq!(fs::read_to_string(&path)?, "new");
+        assert_eq!(fs::read_dir(dir.path())?.count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_commit_empty_suffix() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("file");
+        fs::write(&path, "old")?;
+
+        let mut file = AtomicFile::create(&path)?;
+        file.write_all(b"new")?;
+        assert!(file.commit(Some("")).is_err());
+        assert_eq!(fs::read_to_string(&path)?, "old");
+        assert_eq!(fs::read_dir(dir.path())?.count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(AtomicFile::create(dir.path().join("missing")).is_err());
+    }
+}
@@ -0,0 +1,28 @@
+/// Renders a horizontal ASCII bar for `value` out of `max`, at most `width`
+/// characters long.
+///
+/// Non-zero values always get at least one character so that they stay
+/// visible next to much larger ones.
+pub fn render_bar(value: u64, max: u64, width: usize) -> String {
+    if value == 0 || max == 0 {
+        return String::new();
+    }
+    let len = (u128::from(value.min(max)) * width as u128).div_ceil(u128::from(max));
+    "#".repeat((len as usize).max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_bar() {
+        assert_eq!(render_bar(0, 10, 20), "");
+        assert_eq!(render_bar(5, 0, 20), "");
+        assert_eq!(render_bar(10, 10, 20), "#".repeat(20));
+        assert_eq!(render_bar(5, 10, 20), "#".repeat(10));
+        assert_eq!(render_bar(1, 1000, 20), "#");
+        assert_eq!(render_bar(11, 10, 4), "####");
+        assert_eq!(render_bar(u64::MAX, u64::MAX, 3), "###");
+    }
+}
@@ -0,0 +1,147 @@
+//! Property tests that run a tool and the GNU program it imitates on the
+//! same generated input, so that any difference in behavior shows up
+//! without anyone having to think of the case first.
+//!
+//! The GNU programs are looked up in `PATH` and the tests are skipped where
+//! they aren't installed, as on macOS or with BusyBox.
+
+use std::{
+    env,
+    ffi::OsStr,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use proptest::{
+    collection::vec,
+    prelude::{Just, Strategy, prop_oneof},
+    test_runner::TestCaseError,
+};
+
+/// What one run of a program printed, and how it exited.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub stdout: String,
+    /// `None` when killed by a signal
+    pub status: Option<i32>,
+}
+
+/// A tool of this workspace and the system program to hold it against.
+#[derive(Debug)]
+pub struct Reference {
+    ours: PathBuf,
+    theirs: PathBuf,
+}
+
+impl Reference {
+    /// Pairs the binary `ours` with GNU `name`, or returns `None` when there
+    /// is no `name` in `PATH` or it isn't the one from GNU coreutils.
+    pub fn find(ours: impl Into<PathBuf>, name: &str) -> Option<Self> {
+        let theirs = find_in_path(name)?;
+        let version = Command::new(&theirs).arg("--version").output().ok()?;
+        if !String::from_utf8_lossy(&version.stdout).contains("GNU coreutils") {
+            return None;
+        }
+        Some(Reference {
+            ours: ours.into(),
+            theirs,
+        })
+    }
+
+    /// Runs both programs with `args` and `stdin`, and fails the test case
+    /// unless they print the same and exit the same.
+    pub fn check<S: AsRef<OsStr>>(&self, args: &[S], stdin: &[u8]) -> Result<(), TestCaseError> {
+        self.check_with(args, stdin, |stdout| stdout.to_string())
+    }
+
+    /// Like [`Reference::check`], comparing the output after `normalize`,
+    /// for differences that are deliberate such as column widths.
+    pub fn check_with<S: AsRef<OsStr>>(
+        &self,
+        args: &[S],
+        stdin: &[u8],
+        normalize: impl Fn(&str) -> String,
+    ) -> Result<(), TestCaseError> {
+        let [ours, theirs] = [&self.ours, &self.theirs].map(|program| {
+            run(program, args, stdin).map(|outcome| Outcome {
+                stdout: normalize(&outcome.stdout),
+                ..outcome
+            })
+        });
+        let (ours, theirs) = (ours?, theirs?);
+        if ours == theirs {
+            Ok(())
+        } else {
+            Err(TestCaseError::fail(format!(
+                "{} differs from {}\n ours: {ours:?}\ntheirs: {theirs:?}",
+                self.ours.display(),
+                self.theirs.display()
+            )))
+        }
+    }
+}
+
+/// Where `name` would be found by a shell, if it's anywhere in `PATH`.
+pub fn find_in_path(name: &str) -> Option<PathBuf> {
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// Runs `program` in the C.UTF-8 locale, so that GNU tools count characters
+/// the way ours do and print untranslated messages.
+fn run<S: AsRef<OsStr>>(
+    program: &Path,
+    args: &[S],
+    stdin: &[u8],
+) -> Result<Outcome, TestCaseError> {
+    let failed = |err| TestCaseError::fail(format!("{}: {err}", program.display()));
+    let mut child = Command::new(program)
+        .args(args)
+        .env("LC_ALL", "C.UTF-8")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(failed)?;
+    // A program that stops reading early closes the pipe, which is fine
+    let _ = child.stdin.take().unwrap().write_all(stdin);
+    let output = child.wait_with_output().map_err(failed)?;
+    Ok(Outcome {
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        status: output.status.code(),
+    })
+}
+
+/// Text of a few short lines, made of the characters that tend to matter:
+/// blanks, a delimiter, multibyte characters, empty lines and sometimes no
+/// final newline, and now and then bytes that aren't UTF-8: a stray one and
+/// a character cut short.
+pub fn text() -> impl Strategy<Value = Vec<u8>> {
+    let char = prop_oneof![
+        4 => Just(&b"a"[..]),
+        2 => Just(&b"b"[..]),
+        2 => Just(&b" "[..]),
+        1 => Just(&b"\t"[..]),
+        1 => Just(&b","[..]),
+        1 => Just("é".as_bytes()),
+        1 => Just("日".as_bytes()),
+        1 => Just(&b"\xff"[..]),
+        1 => Just(&"日".as_bytes()[..2]),
+    ];
+    let line = vec(char, 0..12).prop_map(|chars| chars.concat());
+    (vec(line, 0..8), proptest::bool::ANY).prop_map(|(lines, final_newline)| {
+        let mut text = lines.join(&b'\n');
+        if final_newline && !text.is_empty() {
+            text.push(b'\n');
+        }
+        text
+    })
+}
+
+/// ASCII-only [`text`], for options where GNU counts bytes but ours count
+/// characters.
+pub fn ascii_text() -> impl Strategy<Value = Vec<u8>> {
+    text().prop_map(|text| text.into_iter().filter(u8::is_ascii).collect())
+}
@@ -0,0 +1,113 @@
+use std::{
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+use anyhow::Result;
+
+/// Compressed stream formats that can be read transparently.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+impl Compression {
+    /// Guesses the format from a ‘.gz’ or ‘.zst’ file name extension.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        match path.as_ref().extension()?.to_str()? {
+            "gz" => Some(Compression::Gzip),
+            "zst" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Recognizes the format from the magic number at the start of `buf`.
+    pub fn detect(buf: &[u8]) -> Option<Self> {
+        if buf.starts_with(GZIP_MAGIC) {
+            Some(Compression::Gzip)
+        } else if buf.starts_with(ZSTD_MAGIC) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+}
+
+/// Wraps `reader` so that reading from it yields the decompressed data.
+pub fn decompress(reader: Box<dyn BufRead>, compression: Compression) -> Result<Box<dyn BufRead>> {
+    Ok(match compression {
+        // Multi-member archives (‘cat a.gz b.gz’) decompress to the
+        // concatenation of their contents, like ‘zcat’ does
+        Compression::Gzip => Box::new(BufReader::new(flate2::bufread::MultiGzDecoder::new(reader))),
+        Compression::Zstd => Box::new(BufReader::new(zstd::stream::read::Decoder::with_buffer(
+            reader,
+        )?)),
+    })
+}
+
+/// Decompresses `reader` if it starts with a known magic number and passes
+/// it through untouched otherwise.
+pub fn auto_decompress(mut reader: Box<dyn BufRead>) -> Result<Box<dyn BufRead>> {
+    match Compression::detect(reader.fill_buf()?) {
+        Some(compression) => decompress(reader, compression),
+        None => Ok(reader),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Read, Write};
+
+    fn read_all(mut reader: Box<dyn BufRead>) -> String {
+        let mut out = String::new();
+        reader.read_to_string(&mut out).unwrap();
+        out
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        enc.write_all(data).unwrap();
+        enc.finish().unwrap()
+    }
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(Compression::from_path("a.txt.gz"), Some(Compression::Gzip));
+        assert_eq!(Compression::from_path("dir/a.zst"), Some(Compression::Zstd));
+        assert_eq!(Compression::from_path("a.txt"), None);
+        assert_eq!(Compression::from_path("gz"), None);
+    }
+
+    #[test]
+    fn test_decompress_gzip() {
+        let mut data = gzip(b"one\n");
+        data.extend(gzip(b"two\n"));
+        let reader = decompress(Box::new(Cursor::new(data)), Compression::Gzip).unwrap();
+        assert_eq!(read_all(reader), "one\ntwo\n");
+    }
+
+    #[test]
+    fn test_decompress_zstd() {
+        let data = zstd::encode_all(&b"hello\n"[..], 0).unwrap();
+        let reader = decompress(Box::new(Cursor::new(data)), Compression::Zstd).unwrap();
+        assert_eq!(read_all(reader), "hello\n");
+    }
+
+    #[test]
+    fn test_auto_decompress() {
+        let reader = auto_decompress(Box::new(Cursor::new(gzip(b"packed\n")))).unwrap();
+        assert_eq!(read_all(reader), "packed\n");
+
+        let data = zstd::encode_all(&b"packed\n"[..], 0).unwrap();
+        let reader = auto_decompress(Box::new(Cursor::new(data))).unwrap();
+        assert_eq!(read_all(reader), "packed\n");
+
+        let reader = auto_decompress(Box::new(Cursor::new("plain\n"))).unwrap();
+        assert_eq!(read_all(reader), "plain\n");
+    }
+}
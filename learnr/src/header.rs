@@ -0,0 +1,95 @@
+use std::io::{self, Write};
+
+/// How the name of each file is shown before its output, when the output
+/// of several files follows one another.
+#[derive(Debug, Clone, Copy, PartialEq, Default, clap::ValueEnum)]
+pub enum HeaderFormat {
+    /// ‘==> FILE <==’ after an empty line, as GNU prints it
+    #[default]
+    Plain,
+    /// ‘# FILE’
+    Comment,
+    /// ‘{"file":"FILE"}’, one JSON object per line
+    Json,
+}
+
+/// Writes the header of `name` in `format`. Only plain headers have an
+/// empty line before them, unless `first` is what gets printed first.
+pub fn write_header(
+    out: &mut impl Write,
+    format: HeaderFormat,
+    name: &str,
+    first: bool,
+) -> io::Result<()> {
+    match format {
+        HeaderFormat::Plain => {
+            if !first {
+                writeln!(out)?;
+            }
+            writeln!(out, "==> {name} <==")
+        }
+        HeaderFormat::Comment => writeln!(out, "# {name}"),
+        HeaderFormat::Json => writeln!(out, "{{\"file\":{}}}", serde_json::json!(name)),
+    }
+}
+
+/// Headers before the output of each of several files, keeping track of
+/// whether one was printed already.
+#[derive(Debug)]
+pub struct HeaderedFiles {
+    format: Option<HeaderFormat>,
+    first: bool,
+}
+
+impl HeaderedFiles {
+    /// Headers in `format`, or none at all without one.
+    pub fn new(format: Option<HeaderFormat>) -> Self {
+        HeaderedFiles {
+            format,
+            first: true,
+        }
+    }
+
+    /// Writes the header that comes before the output of `name`, if any.
+    pub fn start(&mut self, out: &mut impl Write, name: &str) -> io::Result<()> {
+        let Some(format) = self.format else {
+            return Ok(());
+        };
+        write_header(out, format, name, self.first)?;
+        self.first = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(format: HeaderFormat, names: &[&str]) -> String {
+        let mut headers = HeaderedFiles::new(Some(format));
+        let mut out = vec![];
+        for name in names {
+            headers.start(&mut out, name).unwrap();
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_headered_files() {
+        let names = ["a", "b \"c\""];
+        assert_eq!(
+            headers(HeaderFormat::Plain, &names),
+            "==> a <==\n\n==> b \"c\" <==\n"
+        );
+        assert_eq!(headers(HeaderFormat::Comment, &names), "# a\n# b \"c\"\n");
+        assert_eq!(
+            headers(HeaderFormat::Json, &names),
+            "{\"file\":\"a\"}\n{\"file\":\"b \\\"c\\\"\"}\n"
+        );
+
+        let mut none = HeaderedFiles::new(None);
+        let mut out = vec![];
+        none.start(&mut out, "a").unwrap();
+        assert!(out.is_empty());
+    }
+}
@@ -0,0 +1,60 @@
+use std::ffi::OsString;
+
+/// Rewrites the obsolete ‘head -5’ style count into options clap understands.
+///
+/// Like GNU ‘head’ and ‘tail’, only the first argument is considered: ‘-NUM’
+/// becomes ‘-n NUM’, and ‘-NUMc’ or ‘-NUMl’ pick bytes or lines explicitly.
+/// Anything else is passed through untouched, so clap still reports
+/// unknown flags as usual.
+pub fn expand_legacy_count(args: impl IntoIterator<Item = OsString>) -> Vec<OsString> {
+    let mut args: Vec<OsString> = args.into_iter().collect();
+    let Some(first) = args.get(1).and_then(|arg| arg.to_str()) else {
+        return args;
+    };
+    let Some(count) = first.strip_prefix('-') else {
+        return args;
+    };
+
+    let (digits, flag) = match count.strip_suffix('c') {
+        Some(digits) => (digits, "-c"),
+        None => (count.strip_suffix('l').unwrap_or(count), "-n"),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return args;
+    }
+
+    let rewritten = [OsString::from(flag), OsString::from(digits)];
+    args.splice(1..2, rewritten);
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(args: &[&str]) -> Vec<String> {
+        expand_legacy_count(args.iter().map(OsString::from))
+            .into_iter()
+            .map(|arg| arg.into_string().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_expand_legacy_count() {
+        assert_eq!(expand(&["head", "-5", "f"]), ["head", "-n", "5", "f"]);
+        assert_eq!(expand(&["head", "-5l"]), ["head", "-n", "5"]);
+        assert_eq!(expand(&["head", "-20c", "f"]), ["head", "-c", "20", "f"]);
+        assert_eq!(expand(&["head", "-0"]), ["head", "-n", "0"]);
+    }
+
+    #[test]
+    fn test_expand_legacy_count_passthrough() {
+        assert_eq!(expand(&["head"]), ["head"]);
+        assert_eq!(expand(&["head", "-n5"]), ["head", "-n5"]);
+        assert_eq!(expand(&["head", "-"]), ["head", "-"]);
+        assert_eq!(expand(&["head", "-c"]), ["head", "-c"]);
+        assert_eq!(expand(&["head", "-5x"]), ["head", "-5x"]);
+        assert_eq!(expand(&["head", "f", "-5"]), ["head", "f", "-5"]);
+        assert_eq!(expand(&["head", "--", "-5"]), ["head", "--", "-5"]);
+    }
+}
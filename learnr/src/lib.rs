@@ -5,12 +5,51 @@ use std::{
 
 use anyhow::{Result, anyhow};
 
+mod atomic;
+mod bar;
+#[cfg(feature = "compare")]
+pub mod compare;
+#[cfg(feature = "decompress")]
+mod decompress;
+mod header;
+mod legacy;
+mod pager;
+mod pipe;
+mod size;
+mod skip;
+mod term;
+#[cfg(feature = "trace")]
+mod trace;
+
+pub use atomic::AtomicFile;
+pub use bar::render_bar;
+#[cfg(feature = "decompress")]
+pub use decompress::{Compression, auto_decompress, decompress};
+pub use header::{HeaderFormat, HeaderedFiles, write_header};
+pub use legacy::expand_legacy_count;
+pub use pager::page;
+pub use pipe::{BROKEN_PIPE_EXIT_CODE, exit_on_broken_pipe, is_broken_pipe};
+pub use size::{format_human_size, format_si_size, parse_count, parse_size};
+pub use skip::key_start;
+pub use term::{DEFAULT_TERMINAL_WIDTH, terminal_height, terminal_width};
+#[cfg(feature = "trace")]
+pub use trace::init_tracing;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum CLIInput {
     StdIn,
     File(String),
 }
 
+impl std::fmt::Display for CLIInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CLIInput::StdIn => f.write_str("-"),
+            CLIInput::File(path) => path.fmt(f),
+        }
+    }
+}
+
 pub fn open(filename: &CLIInput) -> Result<Box<dyn BufRead>> {
     match filename {
         CLIInput::StdIn => Ok(Box::new(BufReader::new(std::io::stdin()))),
@@ -0,0 +1,135 @@
+use std::{
+    env,
+    ffi::OsString,
+    io::{self, ErrorKind, IsTerminal, Write},
+    process::{Command, Stdio},
+};
+
+use crate::terminal_height;
+
+/// The pager when `PAGER` isn't set
+const DEFAULT_PAGER: &str = "less";
+
+/// Options for less when `LESS` isn't set, the same as git's: quit if the
+/// text fits after all, keep colors, and leave the text on the screen.
+const DEFAULT_LESS: &str = "FRX";
+
+/// Writes `text` to standard output, through `PAGER` when it is `allowed`,
+/// standard output is a terminal and the text has more lines than fit on it.
+/// `PAGER` is run by the shell, so it may have options; when it is empty
+/// or can't be started the text is printed as it is.
+pub fn page(text: &str, allowed: bool) -> io::Result<()> {
+    let pager = pager_command(env::var_os("PAGER"));
+    let fits = terminal_height().is_none_or(|height| !too_tall(text, height));
+    match pager {
+        Some(pager) if allowed && !fits && io::stdout().is_terminal() => {
+            match run_pager(&pager, text) {
+                Err(err) if err.kind() == ErrorKind::NotFound => print(text),
+                result => result,
+            }
+        }
+        _ => print(text),
+    }
+}
+
+fn print(text: &str) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    out.write_all(text.as_bytes())?;
+    out.flush()
+}
+
+/// The command in `var`, as `PAGER` holds it, unless it is set to nothing.
+fn pager_command(var: Option<OsString>) -> Option<OsString> {
+    match var {
+        None => Some(DEFAULT_PAGER.into()),
+        Some(pager) if pager.is_empty() => None,
+        pager => pager,
+    }
+}
+
+/// Whether `text` needs more than `height` lines, keeping one for the
+/// prompt of the pager.
+fn too_tall(text: &str, height: usize) -> bool {
+    text.lines().count() >= height
+}
+
+/// Runs `pager` with `text` as its input, until it exits. Fails with
+/// `NotFound` if there is no such pager.
+fn run_pager(pager: &OsString, text: &str) -> io::Result<()> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(pager)
+        .env("LESS", env::var_os("LESS").unwrap_or(DEFAULT_LESS.into()))
+        .stdin(Stdio::piped())
+        .spawn()?;
+    // Ctrl-C is the pager's to handle, as less stops a search with it, and
+    // must not kill the text from under it; git does the same
+    let _interrupts = IgnoreInterrupts::new();
+    let written = child.stdin.take().unwrap().write_all(text.as_bytes());
+    // The shell exits with 127 when it finds no such command
+    if child.wait()?.code() == Some(127) {
+        return Err(ErrorKind::NotFound.into());
+    }
+    // Quitting the pager before the end closes the pipe, which is fine
+    match written {
+        Err(err) if err.kind() == ErrorKind::BrokenPipe => Ok(()),
+        written => written,
+    }
+}
+
+/// SIGINT is ignored for as long as this lives, then handled as before.
+struct IgnoreInterrupts(libc::sighandler_t);
+
+impl IgnoreInterrupts {
+    fn new() -> Self {
+        // SAFETY: SIG_IGN installs no handler, so nothing runs in signal context
+        IgnoreInterrupts(unsafe { libc::signal(libc::SIGINT, libc::SIG_IGN) })
+    }
+}
+
+impl Drop for IgnoreInterrupts {
+    fn drop(&mut self) {
+        // SAFETY: self.0 is the disposition signal returned, so restoring it
+        // changes nothing else
+        unsafe { libc::signal(libc::SIGINT, self.0) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_pager_command() {
+        assert_eq!(pager_command(None), Some("less".into()));
+        assert_eq!(pager_command(Some("".into())), None);
+        assert_eq!(
+            pager_command(Some("more -d".into())),
+            Some("more -d".into())
+        );
+    }
+
+    #[test]
+    fn test_run_pager() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("paged");
+        let pager = format!("cat > '{}'", path.display());
+        run_pager(&pager.into(), "one\ntwo\n")?;
+        assert_eq!(fs::read_to_string(&path)?, "one\ntwo\n");
+
+        // A pager that quits before reading everything is no error
+        run_pager(&"true".into(), &"line\n".repeat(100_000))?;
+
+        let err = run_pager(&"no-such-pager-anywhere".into(), "text").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        Ok(())
+    }
+
+    #[test]
+    fn test_too_tall() {
+        assert!(!too_tall("", 24));
+        assert!(!too_tall(&"line\n".repeat(23), 24));
+        assert!(too_tall(&"line\n".repeat(24), 24));
+    }
+}
@@ -0,0 +1,42 @@
+use std::io::{self, ErrorKind};
+
+/// The status a shell reports for a process killed by SIGPIPE (128 + 13).
+pub const BROKEN_PIPE_EXIT_CODE: i32 = 141;
+
+/// Whether `err`, or any error it wraps, is a write to a closed pipe, as
+/// happens when stdout is piped into something like ‘head -1’.
+pub fn is_broken_pipe(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause
+            .downcast_ref::<io::Error>()
+            .is_some_and(|err| err.kind() == ErrorKind::BrokenPipe)
+    })
+}
+
+/// Exits quietly with [`BROKEN_PIPE_EXIT_CODE`] if `err` is a broken pipe.
+///
+/// Rust ignores SIGPIPE, so instead of being killed like a C program the
+/// utility sees an error on the next write. Call this before reporting
+/// errors so that nobody gets a message about a reader that went away.
+pub fn exit_on_broken_pipe(err: &anyhow::Error) {
+    if is_broken_pipe(err) {
+        std::process::exit(BROKEN_PIPE_EXIT_CODE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_is_broken_pipe() {
+        let err = anyhow::Error::from(io::Error::from(ErrorKind::BrokenPipe));
+        assert!(is_broken_pipe(&err));
+        assert!(is_broken_pipe(&err.context("writing output")));
+
+        let err = anyhow::Error::from(io::Error::from(ErrorKind::NotFound));
+        assert!(!is_broken_pipe(&err));
+        assert!(!is_broken_pipe(&anyhow!("broken pipe")));
+    }
+}
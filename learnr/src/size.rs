@@ -0,0 +1,169 @@
+use anyhow::{Result, anyhow, bail};
+
+/// Multiplier suffixes as understood by GNU coreutils (‘head -c 10K’,
+/// ‘tail -n 2MB’ and so on): a bare letter or ‘iB’ means powers of 1024,
+/// while ‘B’ after the letter means powers of 1000.
+const SUFFIXES: [(&str, u64); 24] = [
+    ("b", 512),
+    ("kB", 1000),
+    ("KB", 1000),
+    ("K", 1 << 10),
+    ("k", 1 << 10),
+    ("KiB", 1 << 10),
+    ("kiB", 1 << 10),
+    ("MB", 1000 * 1000),
+    ("M", 1 << 20),
+    ("MiB", 1 << 20),
+    ("GB", 1000 * 1000 * 1000),
+    ("G", 1 << 30),
+    ("GiB", 1 << 30),
+    ("TB", 1000 * 1000 * 1000 * 1000),
+    ("T", 1 << 40),
+    ("TiB", 1 << 40),
+    ("PB", 1000 * 1000 * 1000 * 1000 * 1000),
+    ("P", 1 << 50),
+    ("PiB", 1 << 50),
+    ("EB", 1000 * 1000 * 1000 * 1000 * 1000 * 1000),
+    ("E", 1 << 60),
+    ("EiB", 1 << 60),
+    ("", 1),
+    ("B", 1),
+];
+
+/// Parses a non-negative size like ‘4096’, ‘10K’, ‘2MB’ or ‘1GiB’.
+pub fn parse_size(arg: &str) -> Result<u64> {
+    parse_scaled(arg, "size")
+}
+
+/// Parses a count of lines or bytes like [`parse_size`], as for ‘head -n 2k’
+/// or ‘tail -c 5M’; errors call it a count.
+pub fn parse_count(arg: &str) -> Result<u64> {
+    parse_scaled(arg, "count")
+}
+
+/// A number with a multiplier suffix, where `what` names it in errors.
+fn parse_scaled(arg: &str, what: &str) -> Result<u64> {
+    let digits_end = arg.find(|c: char| !c.is_ascii_digit()).unwrap_or(arg.len());
+    let (digits, suffix) = arg.split_at(digits_end);
+
+    if digits.is_empty() {
+        bail!("invalid {what} '{arg}'");
+    }
+
+    let multiplier = SUFFIXES
+        .iter()
+        .find_map(|(name, mult)| (*name == suffix).then_some(*mult))
+        .ok_or_else(|| anyhow!("invalid suffix in {what} '{arg}'"))?;
+
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|num| num.checked_mul(multiplier))
+        .ok_or_else(|| anyhow!("{what} '{arg}' is too large"))
+}
+
+/// Formats a byte count the way ‘ls -h’ and ‘du -h’ do: powers of 1024 with
+/// a single-letter suffix, one decimal below 10 and always rounding up.
+pub fn format_human_size(bytes: u64) -> String {
+    format_scaled(bytes, 1024, ["K", "M", "G", "T", "P", "E"])
+}
+
+/// Like [`format_human_size`] in powers of 1000, as for ‘ls --si’, where a
+/// kilobyte is a lowercase ‘k’.
+pub fn format_si_size(bytes: u64) -> String {
+    format_scaled(bytes, 1000, ["k", "M", "G", "T", "P", "E"])
+}
+
+fn format_scaled(bytes: u64, base: u64, units: [&str; 6]) -> String {
+    if bytes < base {
+        return bytes.to_string();
+    }
+    let mut value = bytes as f64;
+    for (i, unit) in units.iter().enumerate() {
+        value /= base as f64;
+        let rounded = if value < 10.0 {
+            (value * 10.0).ceil() / 10.0
+        } else {
+            value.ceil()
+        };
+        // Rounding up may reach the next unit, e.g. 1023.5K is shown as 1.0M
+        if rounded >= base as f64 && i + 1 < units.len() {
+            continue;
+        }
+        return if rounded < 10.0 {
+            format!("{rounded:.1}{unit}")
+        } else {
+            format!("{rounded:.0}{unit}")
+        };
+    }
+    unreachable!("the loop always returns on the last unit")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_err_str_contains;
+    use assertables::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_ok_eq_x!(parse_size("0"), 0);
+        assert_ok_eq_x!(parse_size("42"), 42);
+        assert_ok_eq_x!(parse_size("42B"), 42);
+        assert_ok_eq_x!(parse_size("2b"), 1024);
+        assert_ok_eq_x!(parse_size("10K"), 10 * 1024);
+        assert_ok_eq_x!(parse_size("10k"), 10 * 1024);
+        assert_ok_eq_x!(parse_size("10KiB"), 10 * 1024);
+        assert_ok_eq_x!(parse_size("10kB"), 10 * 1000);
+        assert_ok_eq_x!(parse_size("10KB"), 10 * 1000);
+        assert_ok_eq_x!(parse_size("3M"), 3 * 1024 * 1024);
+        assert_ok_eq_x!(parse_size("3MB"), 3 * 1000 * 1000);
+        assert_ok_eq_x!(parse_size("1G"), 1 << 30);
+        assert_ok_eq_x!(parse_size("1E"), 1 << 60);
+    }
+
+    #[test]
+    fn test_parse_size_errors() {
+        assert_err_str_contains!(parse_size(""), "invalid size ''");
+        assert_err_str_contains!(parse_size("K"), "invalid size 'K'");
+        assert_err_str_contains!(parse_size("-1"), "invalid size '-1'");
+        assert_err_str_contains!(parse_size("10X"), "invalid suffix in size '10X'");
+        assert_err_str_contains!(parse_size("10 K"), "invalid suffix");
+        assert_err_str_contains!(parse_size("16E"), "too large");
+        assert_err_str_contains!(parse_size("99999999999999999999"), "too large");
+    }
+
+    #[test]
+    fn test_parse_count() {
+        assert_ok_eq_x!(parse_count("42"), 42);
+        assert_ok_eq_x!(parse_count("2k"), 2048);
+        assert_err_str_contains!(parse_count("3.14"), "invalid suffix in count '3.14'");
+        assert_err_str_contains!(parse_count("foo"), "invalid count 'foo'");
+        assert_err_str_contains!(parse_count("16E"), "count '16E' is too large");
+    }
+
+    #[test]
+    fn test_format_human_size() {
+        assert_eq!(format_human_size(0), "0");
+        assert_eq!(format_human_size(1023), "1023");
+        assert_eq!(format_human_size(1024), "1.0K");
+        assert_eq!(format_human_size(1025), "1.1K");
+        assert_eq!(format_human_size(10 * 1024 - 1), "10K");
+        assert_eq!(format_human_size(10 * 1024), "10K");
+        assert_eq!(format_human_size(10 * 1024 + 1), "11K");
+        assert_eq!(format_human_size(1024 * 1024 - 1), "1.0M");
+        assert_eq!(format_human_size(1536 * 1024 * 1024), "1.5G");
+        assert_eq!(format_human_size(u64::MAX), "16E");
+    }
+
+    #[test]
+    fn test_format_si_size() {
+        assert_eq!(format_si_size(999), "999");
+        assert_eq!(format_si_size(1000), "1.0k");
+        assert_eq!(format_si_size(1024), "1.1k");
+        assert_eq!(format_si_size(4200), "4.2k");
+        assert_eq!(format_si_size(999_999), "1.0M");
+        assert_eq!(format_si_size(13_000_000), "13M");
+        assert_eq!(format_si_size(u64::MAX), "19E");
+    }
+}
@@ -0,0 +1,57 @@
+/// Finds where the comparison key of `line` starts, the way ‘uniq -f N -s M’
+/// does: first skip `fields` fields, each a run of blanks followed by
+/// non-blanks, then `chars` more characters.
+///
+/// The result is a byte offset. It always falls on a character boundary of
+/// valid UTF-8, so it can slice a `&str` as well as a `&[u8]`.
+pub fn key_start(line: &[u8], fields: usize, chars: usize) -> usize {
+    let is_blank = |b: &u8| *b == b' ' || *b == b'\t';
+
+    let mut pos = 0;
+    for _ in 0..fields {
+        pos += line[pos..].iter().take_while(|b| is_blank(b)).count();
+        pos += line[pos..].iter().take_while(|b| !is_blank(b)).count();
+    }
+    for _ in 0..chars {
+        if pos == line.len() {
+            break;
+        }
+        // Step over a whole UTF-8 sequence, not just its first byte
+        pos += 1;
+        pos += line[pos..]
+            .iter()
+            .take_while(|b| *b & 0b1100_0000 == 0b1000_0000)
+            .count();
+    }
+    pos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_start_fields() {
+        assert_eq!(key_start(b"a b c", 0, 0), 0);
+        assert_eq!(key_start(b"a b c", 1, 0), 1);
+        assert_eq!(key_start(b"a b c", 2, 0), 3);
+        assert_eq!(key_start(b"  a\t\tb c", 1, 0), 3);
+        assert_eq!(key_start(b"a b c", 5, 0), 5);
+        assert_eq!(key_start(b"", 1, 0), 0);
+    }
+
+    #[test]
+    fn test_key_start_chars() {
+        assert_eq!(key_start(b"abc", 0, 2), 2);
+        assert_eq!(key_start(b"abc", 0, 10), 3);
+        assert_eq!(key_start("été".as_bytes(), 0, 1), 2);
+        assert_eq!(&"été"[key_start("été".as_bytes(), 0, 2)..], "é");
+    }
+
+    #[test]
+    fn test_key_start_fields_and_chars() {
+        // Skipped fields leave the blanks before the next one in the key
+        assert_eq!(key_start(b"10:00 x msg", 1, 0), 5);
+        assert_eq!(key_start(b"10:00 x msg", 2, 1), 8);
+    }
+}
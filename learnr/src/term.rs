@@ -0,0 +1,72 @@
+/// Width used when the terminal can't tell, the traditional one
+pub const DEFAULT_TERMINAL_WIDTH: usize = 80;
+
+/// The width of the terminal in columns, as shells export it in `COLUMNS`,
+/// or else as the terminal on standard output tells, or
+/// [`DEFAULT_TERMINAL_WIDTH`] when neither gives a positive number.
+pub fn terminal_width() -> usize {
+    let tty_width = stdout_size().map(|size| size.ws_col as usize);
+    width_from(std::env::var("COLUMNS").ok().as_deref(), tty_width)
+}
+
+/// The height of the terminal in lines, from `LINES` or else from the
+/// terminal on standard output, if either knows.
+pub fn terminal_height() -> Option<usize> {
+    let tty_height = stdout_size().map(|size| size.ws_row as usize);
+    size_from(std::env::var("LINES").ok().as_deref(), tty_height)
+}
+
+fn width_from(columns: Option<&str>, tty_width: Option<usize>) -> usize {
+    size_from(columns, tty_width).unwrap_or(DEFAULT_TERMINAL_WIDTH)
+}
+
+/// The first positive one of the size in `var` and the size of the terminal.
+fn size_from(var: Option<&str>, tty_size: Option<usize>) -> Option<usize> {
+    var.and_then(|var| var.trim().parse().ok())
+        .filter(|size| *size > 0)
+        .or(tty_size.filter(|size| *size > 0))
+}
+
+/// The size of the terminal standard output is, if it is one.
+fn stdout_size() -> Option<libc::winsize> {
+    let mut size = libc::winsize {
+        ws_row: 0,
+        ws_col: 0,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // Fails with ENOTTY for anything that isn't a terminal
+    // SAFETY: `size` is a valid `winsize` for TIOCGWINSZ to fill
+    let ret = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+    (ret == 0).then_some(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_width_from() {
+        assert_eq!(width_from(Some("132"), None), 132);
+        assert_eq!(width_from(Some(" 40\n"), None), 40);
+        assert_eq!(width_from(Some("0"), None), DEFAULT_TERMINAL_WIDTH);
+        assert_eq!(width_from(Some("wide"), None), DEFAULT_TERMINAL_WIDTH);
+        assert_eq!(width_from(None, None), DEFAULT_TERMINAL_WIDTH);
+    }
+
+    #[test]
+    fn test_width_from_tty() {
+        assert_eq!(width_from(None, Some(100)), 100);
+        assert_eq!(width_from(Some("60"), Some(100)), 60);
+        assert_eq!(width_from(Some("wide"), Some(100)), 100);
+        // Serial consoles may not know their size
+        assert_eq!(width_from(None, Some(0)), DEFAULT_TERMINAL_WIDTH);
+    }
+
+    #[test]
+    fn test_size_from() {
+        assert_eq!(size_from(Some("24"), Some(50)), Some(24));
+        assert_eq!(size_from(Some("0"), Some(50)), Some(50));
+        assert_eq!(size_from(None, None), None);
+    }
+}
@@ -0,0 +1,34 @@
+//! Diagnostics on stderr through `tracing`, to find out why a tool skipped
+//! a file or printed what it did without changing its code.
+//!
+//! The tools that trace name their spans alike, so that one filter works
+//! for all of them:
+//! - `open`: reading one input or starting point, with its `path`
+//! - `seek`: moving to where the output of an input starts
+//! - `match`: deciding whether an entry or a line is selected
+//! - `write`: copying data to the output, as when following a file
+
+use std::io::{self, IsTerminal};
+
+use tracing_subscriber::EnvFilter;
+
+/// Sends events and spans to stderr as `RUST_LOG` asks, as in
+/// ‘RUST_LOG=[match]=debug’, or else at the debug level with `debug` (from a
+/// tool's --debug) and not at all without.
+///
+/// The switch is --debug rather than -v/--verbose because -v means
+/// something else already in every tool that traces: ‘--total-size -v’ in
+/// findr, --invert-match in grepr, and headers always in tailr, as in GNU
+/// find, grep and tail.
+pub fn init_tracing(debug: bool) {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(if debug { "debug" } else { "off" }));
+    // Only fails when already set, as when one process runs several tools
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(io::stderr)
+        .with_ansi(io::stderr().is_terminal())
+        .without_time()
+        .with_target(false)
+        .try_init();
+}
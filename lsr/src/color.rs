@@ -0,0 +1,200 @@
+//! Coloring names by the kind of file they are, as --color does, with the
+//! colors LS_COLORS sets.
+
+use std::{
+    collections::HashMap,
+    fs::{Metadata, metadata},
+    os::unix::fs::{FileTypeExt, PermissionsExt},
+    path::Path,
+};
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::Follow;
+
+/// The SGR codes used when LS_COLORS doesn't set them, the same as the
+/// defaults of GNU dircolors
+const DEFAULT_COLORS: &str = "di=01;34:ln=01;36:or=40;31;01:ex=01;32:pi=40;33:so=01;35:\
+bd=40;33;01:cd=40;33;01";
+
+/// What colors which names, parsed from the format of LS_COLORS: entries like
+/// ‘di=01;34’ for a kind of file, or ‘*.tar=01;31’ for names with a suffix,
+/// separated by colons.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Colors {
+    /// By two-letter kind, like ‘di’ for directories
+    kinds: HashMap<String, String>,
+    /// By the end of the name, in the order given
+    suffixes: Vec<(String, String)>,
+}
+
+impl Colors {
+    /// The default colors, with whatever `ls_colors` sets instead. Entries
+    /// that aren't understood are left out.
+    pub fn new(ls_colors: Option<&str>) -> Self {
+        let mut colors = Colors {
+            kinds: HashMap::new(),
+            suffixes: vec![],
+        };
+        colors.extend(DEFAULT_COLORS);
+        if let Some(ls_colors) = ls_colors {
+            colors.extend(ls_colors);
+        }
+        colors
+    }
+
+    fn extend(&mut self, spec: &str) {
+        for (key, codes) in spec.split(':').filter_map(|entry| entry.split_once('=')) {
+            match key.strip_prefix('*') {
+                Some(suffix) => {
+                    // A later entry for the same suffix wins
+                    self.suffixes.retain(|(known, _)| known != suffix);
+                    self.suffixes.push((suffix.to_string(), codes.to_string()));
+                }
+                None => {
+                    self.kinds.insert(key.to_string(), codes.to_string());
+                }
+            }
+        }
+    }
+
+    /// The SGR codes for `path`, if its name gets a color. A link that
+    /// `follow` follows is colored like what it points to; one that points
+    /// nowhere is ‘or’ where that is set, or else like any other link.
+    pub fn codes(&self, path: &Path, follow: &Follow) -> Option<&str> {
+        let Ok(meta) = follow.metadata(path) else {
+            return self.kind("mi");
+        };
+        let file_type = meta.file_type();
+        if file_type.is_symlink() {
+            return match metadata(path) {
+                Ok(_) => self.kind("ln"),
+                Err(_) => self.kind("or").or_else(|| self.kind("ln")),
+            };
+        }
+        if let Some(kind) = special_kind(&meta) {
+            return self.kind(kind);
+        }
+        if meta.permissions().mode() & 0o111 != 0 {
+            return self.kind("ex");
+        }
+        let name = path.file_name()?.to_string_lossy();
+        self.suffixes
+            .iter()
+            .find(|(suffix, _)| name.ends_with(suffix.as_str()))
+            .map(|(_, codes)| codes.as_str())
+            .filter(|codes| !codes.is_empty())
+            .or_else(|| self.kind("fi"))
+    }
+
+    fn kind(&self, kind: &str) -> Option<&str> {
+        self.kinds
+            .get(kind)
+            .map(String::as_str)
+            .filter(|codes| !codes.is_empty())
+    }
+
+    /// `name` in the color of `path`, if it has one.
+    pub fn paint(&self, path: &Path, name: &str, follow: &Follow) -> String {
+        match self.codes(path, follow) {
+            Some(codes) => format!("\x1b[{codes}m{name}\x1b[0m"),
+            None => name.to_string(),
+        }
+    }
+}
+
+/// The kind of anything that is neither a link nor a regular file.
+fn special_kind(meta: &Metadata) -> Option<&'static str> {
+    let file_type = meta.file_type();
+    if file_type.is_dir() {
+        Some("di")
+    } else if file_type.is_fifo() {
+        Some("pi")
+    } else if file_type.is_socket() {
+        Some("so")
+    } else if file_type.is_block_device() {
+        Some("bd")
+    } else if file_type.is_char_device() {
+        Some("cd")
+    } else {
+        None
+    }
+}
+
+/// How many columns `text` takes on a terminal, leaving out the escape
+/// sequences that color it.
+pub fn display_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut rest = text;
+    while let Some(start) = rest.find('\x1b') {
+        width += rest[..start].width();
+        rest = &rest[start..];
+        rest = match rest.find('m') {
+            Some(end) => &rest[end + 1..],
+            None => "",
+        };
+    }
+    width + rest.width()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, os::unix::fs::symlink};
+
+    #[test]
+    fn test_codes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = |name| dir.path().join(name);
+        fs::create_dir(path("dir")).unwrap();
+        fs::write(path("notes.txt"), "").unwrap();
+        fs::write(path("plain"), "").unwrap();
+        fs::write(path("run.txt"), "").unwrap();
+        fs::set_permissions(path("run.txt"), fs::Permissions::from_mode(0o755)).unwrap();
+        symlink("dir", path("link")).unwrap();
+        symlink("missing", path("broken")).unwrap();
+
+        let colors = Colors::new(None);
+        assert_eq!(colors.codes(&path("dir"), &Follow::Never), Some("01;34"));
+        assert_eq!(
+            colors.codes(&path("run.txt"), &Follow::Never),
+            Some("01;32")
+        );
+        assert_eq!(colors.codes(&path("link"), &Follow::Never), Some("01;36"));
+        assert_eq!(
+            colors.codes(&path("broken"), &Follow::Never),
+            Some("40;31;01")
+        );
+        assert_eq!(colors.codes(&path("plain"), &Follow::Never), None);
+        // A link that is followed is colored like its target
+        assert_eq!(colors.codes(&path("link"), &Follow::All), Some("01;34"));
+        let named = Follow::Named(vec![path("link")]);
+        assert_eq!(colors.codes(&path("link"), &named), Some("01;34"));
+        assert_eq!(
+            colors.codes(&path("broken"), &Follow::All),
+            Some("40;31;01")
+        );
+
+        let colors = Colors::new(Some("di=04:*.txt=33:or=:fi=0:bogus"));
+        assert_eq!(colors.codes(&path("dir"), &Follow::Never), Some("04"));
+        assert_eq!(colors.codes(&path("notes.txt"), &Follow::Never), Some("33"));
+        // Being executable matters more than the name
+        assert_eq!(
+            colors.codes(&path("run.txt"), &Follow::Never),
+            Some("01;32")
+        );
+        assert_eq!(colors.codes(&path("broken"), &Follow::Never), Some("01;36"));
+        assert_eq!(colors.codes(&path("plain"), &Follow::Never), Some("0"));
+        assert_eq!(
+            colors.paint(&path("dir"), "dir", &Follow::Never),
+            "\x1b[04mdir\x1b[0m".to_string()
+        );
+    }
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width("plain"), 5);
+        assert_eq!(display_width("\x1b[01;34m日本\x1b[0m"), 4);
+        assert_eq!(display_width("a\x1b[0mb\x1b[1"), 2);
+    }
+}
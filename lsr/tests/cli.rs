@@ -1,9 +1,13 @@
 use anyhow::Result;
 use assert_cmd::cargo::cargo_bin_cmd;
+use chrono::NaiveDate;
 use predicates::prelude::*;
 use pretty_assertions::assert_eq;
 use rand::{Rng, distributions::Alphanumeric};
-use std::fs;
+use std::{
+    fs,
+    os::unix::fs::{MetadataExt, PermissionsExt},
+};
 
 const HIDDEN: &str = "tests/inputs/.hidden";
 const EMPTY: &str = "tests/inputs/empty.txt";
@@ -191,8 +195,10 @@ macro_rules! dir_long {
         let cmd = cargo_bin_cmd!().args([$($args),*]).assert().success();
         let stdout = String::from_utf8(cmd.get_output().stdout.clone())
             .expect("invalid UTF-8");
-        let lines: Vec<&str> =
+        let mut lines: Vec<&str> =
             stdout.split('\n').filter(|s| !s.is_empty()).collect();
+        // Listings of directories start with the blocks their entries take
+        assert!(lines.remove(0).starts_with("total "));
         assert_eq!(lines.len(), expected.len());
 
         let mut check = vec![];
@@ -266,3 +272,599 @@ fn dir2_long_all() -> Result<()> {
         "--all"
     )
 }
+
+// --------------------------------------------------
+fn dir_sorted(args: &[&str], expected: &[&str]) -> Result<()> {
+    let cmd = cargo_bin_cmd!().args(args).assert().success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone()).expect("invalid UTF-8");
+    let lines: Vec<&str> = stdout.split('\n').filter(|s| !s.is_empty()).collect();
+    assert_eq!(lines, expected);
+    Ok(())
+}
+
+#[test]
+fn sort_name() -> Result<()> {
+    dir_sorted(
+        &["tests/sorting"],
+        &[
+            "tests/sorting/file02",
+            "tests/sorting/file1",
+            "tests/sorting/file10",
+            "tests/sorting/file2",
+            "tests/sorting/img10.png",
+            "tests/sorting/img9.png",
+        ],
+    )
+}
+
+#[test]
+fn sort_version() -> Result<()> {
+    let expected = &[
+        "tests/sorting/file1",
+        "tests/sorting/file02",
+        "tests/sorting/file2",
+        "tests/sorting/file10",
+        "tests/sorting/img9.png",
+        "tests/sorting/img10.png",
+    ];
+    dir_sorted(&["-v", "tests/sorting"], expected)?;
+    dir_sorted(&["--sort", "version", "tests/sorting"], expected)
+}
+
+#[test]
+fn dies_version_and_sort() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-v", "--sort", "name", "tests/sorting"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn long_time_birth() -> Result<()> {
+    let cmd = cargo_bin_cmd!()
+        .args(["--long", "--time", "birth", BUSTLE])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone()).expect("invalid UTF-8");
+    let parts: Vec<_> = stdout.split_whitespace().collect();
+    assert_eq!(parts.first().unwrap(), &"-rw-r--r--");
+    assert_eq!(parts.get(4).unwrap(), &"193");
+    assert_eq!(parts.last().unwrap(), &BUSTLE);
+    // The placeholder is used when the filesystem doesn't record birth times
+    let date = parts.get(5).unwrap();
+    assert!(
+        *date == "-" || NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok(),
+        "unexpected birth time {date:?}"
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_time() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--long", "--time", "bogus", BUSTLE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "invalid value 'bogus' for '--time <WORD>'",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+fn porcelain_line(path: &str) -> Result<String> {
+    let md = fs::metadata(path)?;
+    Ok(format!(
+        "-\t{:04o}\t1\t{}\t{}\t{}\t{}\t{path}",
+        md.mode() & 0o7777,
+        md.uid(),
+        md.gid(),
+        md.size(),
+        md.mtime(),
+    ))
+}
+
+#[test]
+fn porcelain() -> Result<()> {
+    let expected = format!("{}\n{}\n", porcelain_line(BUSTLE)?, porcelain_line(FOX)?);
+    assert!(expected.starts_with("-\t0644\t1\t"));
+    cargo_bin_cmd!()
+        .args(["--porcelain", FOX, BUSTLE])
+        .assert()
+        .success()
+        .stdout(expected.clone());
+    cargo_bin_cmd!()
+        .args(["--porcelain=v1", FOX, BUSTLE])
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+#[test]
+fn porcelain_zero() -> Result<()> {
+    let expected = format!("{}\0{}\0", porcelain_line(BUSTLE)?, porcelain_line(EMPTY)?);
+    cargo_bin_cmd!()
+        .args(["--porcelain", "-z", EMPTY, BUSTLE])
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+#[test]
+fn dies_zero_without_porcelain() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-z", BUSTLE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--porcelain"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn columns() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--columns", "size,name", BUSTLE, FOX])
+        .assert()
+        .success()
+        .stdout(format!("193  {BUSTLE}\n 45  {FOX}\n\n"));
+    Ok(())
+}
+
+#[test]
+fn columns_inode() -> Result<()> {
+    let ino = fs::metadata(BUSTLE)?.ino();
+    cargo_bin_cmd!()
+        .args(["--columns=name,inode", BUSTLE])
+        .assert()
+        .success()
+        .stdout(format!("{BUSTLE}  {ino}\n\n"));
+    Ok(())
+}
+
+#[test]
+fn columns_default_is_long() -> Result<()> {
+    let long = cargo_bin_cmd!().args(["-l", BUSTLE]).output()?;
+    cargo_bin_cmd!()
+        .args([
+            "--columns",
+            "perms,nlink,owner,group,size,mtime,name",
+            BUSTLE,
+        ])
+        .assert()
+        .success()
+        .stdout(String::from_utf8(long.stdout)?);
+    Ok(())
+}
+
+#[test]
+fn columns_target() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let file = dir.path().join("file");
+    let link = dir.path().join("link");
+    fs::write(&file, "data")?;
+    std::os::unix::fs::symlink("file", &link)?;
+    let file = file.to_str().unwrap();
+    let link = link.to_str().unwrap();
+    cargo_bin_cmd!()
+        .args(["--columns", "name,target,size", file, link])
+        .assert()
+        .success()
+        .stdout(format!("{file}        4\n{link}  file  4\n\n"));
+    Ok(())
+}
+
+#[test]
+fn dies_bad_column() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--columns", "size,bogus", BUSTLE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "invalid value 'bogus' for '--columns <LIST>'",
+        ));
+    Ok(())
+}
+
+#[test]
+fn dies_columns_and_porcelain() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--columns", "size", "--porcelain", BUSTLE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn human_readable_sizes() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let small = dir.path().join("small");
+    let big = dir.path().join("big");
+    fs::write(&small, "data")?;
+    fs::File::create(&big)?.set_len(4200)?;
+    let (small, big) = (small.to_str().unwrap(), big.to_str().unwrap());
+    cargo_bin_cmd!()
+        .args(["-h", "--columns", "size,name", big, small])
+        .assert()
+        .success()
+        .stdout(format!("4.2K  {big}\n   4  {small}\n\n"));
+    cargo_bin_cmd!()
+        .args(["--si", "--columns", "size,name", big])
+        .assert()
+        .success()
+        .stdout(format!("4.2k  {big}\n\n"));
+    // The last of them wins
+    cargo_bin_cmd!()
+        .args(["--si", "-h", "--columns", "size", big])
+        .assert()
+        .success()
+        .stdout("4.2K\n\n");
+    Ok(())
+}
+
+#[test]
+fn help_is_long_only() -> Result<()> {
+    cargo_bin_cmd!()
+        .arg("--help")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--human-readable"));
+    Ok(())
+}
+
+// --------------------------------------------------
+/// A directory with files named ‘a’ to ‘e’, listed in `dir/`.
+fn grid_dir() -> Result<(tempfile::TempDir, String)> {
+    let dir = tempfile::tempdir()?;
+    for name in ["a", "b", "c", "d", "e"] {
+        fs::write(dir.path().join(name), "")?;
+    }
+    let prefix = format!("{}/", dir.path().display());
+    Ok((dir, prefix))
+}
+
+#[test]
+fn grid_down() -> Result<()> {
+    let (dir, p) = grid_dir()?;
+    // Three columns of names that are this wide, two spaces apart
+    let width = 3 * (p.len() + 1) + 4;
+    cargo_bin_cmd!()
+        .env("COLUMNS", width.to_string())
+        .arg("-C")
+        .arg(dir.path())
+        .assert()
+        .success()
+        .stdout(format!("{p}a  {p}c  {p}e\n{p}b  {p}d\n"));
+    Ok(())
+}
+
+#[test]
+fn grid_across() -> Result<()> {
+    let (dir, p) = grid_dir()?;
+    let width = 3 * (p.len() + 1) + 4;
+    cargo_bin_cmd!()
+        .env("COLUMNS", width.to_string())
+        .arg("-x")
+        .arg(dir.path())
+        .assert()
+        .success()
+        .stdout(format!("{p}a  {p}b  {p}c\n{p}d  {p}e\n"));
+    Ok(())
+}
+
+#[test]
+fn one_per_line_wins() -> Result<()> {
+    let (dir, p) = grid_dir()?;
+    cargo_bin_cmd!()
+        .env("COLUMNS", "200")
+        .args(["-C", "-1"])
+        .arg(dir.path())
+        .assert()
+        .success()
+        .stdout(format!("{p}a\n{p}b\n{p}c\n{p}d\n{p}e\n"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn color_always() -> Result<()> {
+    cargo_bin_cmd!()
+        .env_remove("LS_COLORS")
+        .args(["--color", "-1", "tests/inputs"])
+        .assert()
+        .success()
+        .stdout(
+            "tests/inputs/bustle.txt\n\
+             \x1b[01;34mtests/inputs/dir\x1b[0m\n\
+             tests/inputs/empty.txt\n\
+             tests/inputs/fox.txt\n",
+        );
+    Ok(())
+}
+
+#[test]
+fn color_ls_colors() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    fs::write(dir.path().join("notes.txt"), "")?;
+    std::os::unix::fs::symlink("missing", dir.path().join("broken"))?;
+    let p = format!("{}/", dir.path().display());
+    cargo_bin_cmd!()
+        .env("LS_COLORS", "*.txt=33:or=31")
+        .args(["--color=always", "-1"])
+        .arg(dir.path())
+        .assert()
+        .success()
+        .stdout(format!(
+            "\x1b[31m{p}broken\x1b[0m\n\x1b[33m{p}notes.txt\x1b[0m\n"
+        ));
+    Ok(())
+}
+
+#[test]
+fn color_long() -> Result<()> {
+    cargo_bin_cmd!()
+        .env("LS_COLORS", "di=04")
+        .args(["--color=always", "--columns=name,size", "tests/inputs"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\x1b[04mtests/inputs/dir\x1b[0m        ",
+        ));
+    Ok(())
+}
+
+#[test]
+fn color_grid_widths() -> Result<()> {
+    let (dir, p) = grid_dir()?;
+    fs::create_dir(dir.path().join("f"))?;
+    let width = 3 * (p.len() + 1) + 4;
+    cargo_bin_cmd!()
+        .env("COLUMNS", width.to_string())
+        .env("LS_COLORS", "di=01")
+        .args(["-C", "--color=always"])
+        .arg(dir.path())
+        .assert()
+        .success()
+        .stdout(format!(
+            "{p}a  {p}c  {p}e\n{p}b  {p}d  \x1b[01m{p}f\x1b[0m\n"
+        ));
+    Ok(())
+}
+
+#[test]
+fn color_auto_not_on_pipe() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--color=auto", "tests/inputs/dir"])
+        .assert()
+        .success()
+        .stdout("tests/inputs/dir/spiders.txt\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+/// A directory with a file, a directory, and links to both, listed in `dir/`.
+fn link_dir() -> Result<(tempfile::TempDir, String)> {
+    let dir = tempfile::tempdir()?;
+    fs::write(dir.path().join("file"), "data")?;
+    fs::create_dir(dir.path().join("sub"))?;
+    fs::write(dir.path().join("sub/inner"), "")?;
+    // Whatever the umask
+    fs::set_permissions(dir.path().join("file"), fs::Permissions::from_mode(0o644))?;
+    fs::set_permissions(dir.path().join("sub"), fs::Permissions::from_mode(0o755))?;
+    std::os::unix::fs::symlink("file", dir.path().join("to_file"))?;
+    std::os::unix::fs::symlink("sub", dir.path().join("to_sub"))?;
+    let prefix = format!("{}/", dir.path().display());
+    Ok((dir, prefix))
+}
+
+#[test]
+fn long_symlinks() -> Result<()> {
+    let (dir, p) = link_dir()?;
+    cargo_bin_cmd!()
+        .args(["--columns", "perms,name"])
+        .arg(dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("total "))
+        .stdout(predicate::str::ends_with(format!(
+            "\n-rw-r--r--  {p}file\n\
+             drwxr-xr-x  {p}sub\n\
+             lrwxrwxrwx  {p}to_file -> file\n\
+             lrwxrwxrwx  {p}to_sub -> sub\n\n"
+        )));
+    Ok(())
+}
+
+#[test]
+fn dereference() -> Result<()> {
+    let (dir, p) = link_dir()?;
+    cargo_bin_cmd!()
+        .args(["-L", "--columns", "perms,size,name"])
+        .arg(dir.path().join("to_file"))
+        .assert()
+        .success()
+        .stdout(format!("-rw-r--r--  4  {p}to_file\n\n"));
+    Ok(())
+}
+
+#[test]
+fn dereference_command_line() -> Result<()> {
+    let (dir, p) = link_dir()?;
+    // Without -H the link itself is listed, with it the directory
+    cargo_bin_cmd!()
+        .args(["--columns", "name"])
+        .arg(dir.path().join("to_sub"))
+        .assert()
+        .success()
+        .stdout(format!("{p}to_sub -> sub\n\n"));
+    cargo_bin_cmd!()
+        .args(["-H", "--columns", "name"])
+        .arg(dir.path().join("to_sub"))
+        .assert()
+        .success()
+        .stdout(format!("total 0\n{p}to_sub/inner\n\n"));
+    Ok(())
+}
+
+#[test]
+fn short_follows_named_link() -> Result<()> {
+    let (dir, p) = link_dir()?;
+    cargo_bin_cmd!()
+        .arg(dir.path().join("to_sub"))
+        .assert()
+        .success()
+        .stdout(format!("{p}to_sub/inner\n"));
+    Ok(())
+}
+
+#[test]
+fn porcelain_symlink_type() -> Result<()> {
+    let (dir, _) = link_dir()?;
+    cargo_bin_cmd!()
+        .arg("--porcelain")
+        .arg(dir.path().join("to_file"))
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("l\t0777\t"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn directory_itself() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-d", "--columns", "perms,name", "tests/inputs/dir", FOX])
+        .assert()
+        .success()
+        .stdout("drwxr-xr-x  tests/inputs/dir\n-rw-------  tests/inputs/fox.txt\n\n");
+    cargo_bin_cmd!()
+        .args(["-d", "tests/inputs"])
+        .assert()
+        .success()
+        .stdout("tests/inputs\n");
+    Ok(())
+}
+
+#[test]
+fn total_blocks() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let file = dir.path().join("data");
+    fs::write(&file, vec![b'x'; 10_000])?;
+    let blocks = fs::metadata(&file)?.blocks();
+    let name = file.display().to_string();
+    for (env, total) in [(None, blocks.div_ceil(2)), (Some("1"), blocks)] {
+        let mut cmd = cargo_bin_cmd!();
+        match env {
+            Some(value) => cmd.env("POSIXLY_CORRECT", value),
+            None => cmd.env_remove("POSIXLY_CORRECT"),
+        };
+        cmd.args(["--columns", "name"])
+            .arg(dir.path())
+            .assert()
+            .success()
+            .stdout(format!("total {total}\n{name}\n\n"));
+    }
+    // A file given alone has no total
+    cargo_bin_cmd!()
+        .args(["--columns", "name"])
+        .arg(&file)
+        .assert()
+        .success()
+        .stdout(format!("{name}\n\n"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn all_times_header() -> Result<()> {
+    use std::time::{Duration, UNIX_EPOCH};
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("file");
+    let file = fs::File::create(&path)?;
+    file.set_times(
+        fs::FileTimes::new()
+            .set_modified(UNIX_EPOCH + Duration::from_secs(86_400))
+            .set_accessed(UNIX_EPOCH + Duration::from_secs(2 * 86_400)),
+    )?;
+    let cmd = cargo_bin_cmd!()
+        .env("TZ", "UTC")
+        .args(["--all-times", "--header"])
+        .arg(&path)
+        .assert()
+        .success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    let lines: Vec<&str> = stdout.lines().collect();
+    let titles: Vec<&str> = lines[0].split_whitespace().collect();
+    assert_eq!(
+        titles,
+        [
+            "PERMS", "NLINK", "OWNER", "GROUP", "SIZE", "MTIME", "ATIME", "CTIME", "NAME"
+        ]
+    );
+    // Each time is a date and a time of day
+    let fields: Vec<&str> = lines[1].split_whitespace().collect();
+    assert_eq!(fields[5..7], ["1970-01-02", "00:00:00"]);
+    assert_eq!(fields[7..9], ["1970-01-03", "00:00:00"]);
+    assert!(NaiveDate::parse_from_str(fields[9], "%Y-%m-%d").is_ok());
+    assert_eq!(fields[11], path.to_str().unwrap());
+    Ok(())
+}
+
+#[test]
+fn long_time_access() -> Result<()> {
+    use std::time::{Duration, UNIX_EPOCH};
+    let file = tempfile::NamedTempFile::new()?;
+    file.as_file().set_times(
+        fs::FileTimes::new().set_accessed(UNIX_EPOCH + Duration::from_secs(3 * 86_400)),
+    )?;
+    cargo_bin_cmd!()
+        .env("TZ", "UTC")
+        .args(["--time=atime", "--columns", "mtime"])
+        .arg(file.path())
+        .assert()
+        .success()
+        .stdout("1970-01-04 00:00:00\n\n");
+    Ok(())
+}
+
+#[test]
+fn header_time_access() -> Result<()> {
+    let output = cargo_bin_cmd!()
+        .args(["--time=atime", "--header", "--columns", "mtime,name", FOX])
+        .output()?;
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    let titles: Vec<&str> = stdout.lines().next().unwrap().split_whitespace().collect();
+    assert_eq!(titles, ["ATIME", "NAME"]);
+    Ok(())
+}
+
+#[test]
+fn dies_header_without_long() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--header", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "the following required arguments were not provided",
+        ));
+    Ok(())
+}
+
+#[test]
+fn dies_all_times_with_time() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--all-times", "--time", "birth", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
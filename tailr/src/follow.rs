@@ -0,0 +1,432 @@
+//! Printing what is appended to files after their tail, for --follow.
+
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{Result, bail};
+use learnr::HeaderFormat;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use tracing::{debug, trace, trace_span};
+
+use crate::watch::WatchedDirs;
+
+/// A file that is being followed, read up to where it was printed.
+#[derive(Debug)]
+pub struct Followed {
+    /// As given on the command line
+    pub name: String,
+    /// `None` while the file can't be opened, which only happens with
+    /// --retry or -F
+    pub file: Option<File>,
+    /// How many passes in a row found nothing new in it
+    pub unchanged: u32,
+}
+
+#[derive(Debug)]
+pub struct FollowOptions {
+    /// How long to wait before looking for new data again, when polling
+    pub sleep_interval: Duration,
+    /// Poll even where the filesystem can report changes
+    pub use_polling: bool,
+    /// How to print a header whenever output switches to another file, if
+    /// at all
+    pub headers: Option<HeaderFormat>,
+    /// With `by_name` and polling, how many passes without new data go by
+    /// before checking whether the file was replaced
+    pub max_unchanged_stats: u32,
+    /// Follow whatever file has the name, instead of the file that was
+    /// opened, so that a rotated log is picked up again
+    pub by_name: bool,
+    /// Keep trying to open files that can't be opened
+    pub retry: bool,
+    /// Stop once this process has exited
+    pub pid: Option<i32>,
+    /// With --glob, where files to follow come from and go
+    pub watch: Option<WatchedDirs>,
+}
+
+/// Prints whatever gets appended to `files`, forever. `last` is the index
+/// of the file whose output was printed last, so that its header isn't
+/// repeated before more of the same.
+///
+/// A file that can't be read any more is reported and no longer followed,
+/// unless it is followed by name; it's an error when none are left, unless
+/// more may yet appear in watched directories. With a pid, returns after
+/// the last pass once the process is gone.
+pub fn follow(
+    mut files: Vec<Followed>,
+    mut last: Option<usize>,
+    opts: &FollowOptions,
+) -> Result<()> {
+    let mut out = io::stdout().lock();
+    let mut buf = [0_u8; 8192];
+    let waiter = Waiter::new(&files, opts);
+    // Notifications tell when a file is replaced, polling has to look
+    let recheck_after = match waiter {
+        Waiter::Poll(_) => opts.max_unchanged_stats,
+        Waiter::Notify { .. } => 0,
+    };
+    loop {
+        if let Some(watch) = &opts.watch {
+            rescan(&mut files, &mut last, watch, &mut out)?;
+        } else if files.is_empty() {
+            bail!("no files remaining");
+        }
+        // Checked before the pass, so that it prints what the process wrote
+        // just before it exited
+        let writer_gone = opts.pid.is_some_and(|pid| !is_running(pid));
+        follow_pass(
+            &mut files,
+            &mut last,
+            opts,
+            recheck_after,
+            &mut out,
+            &mut buf,
+        )?;
+        out.flush()?;
+        if writer_gone {
+            debug!(pid = opts.pid, "process has exited");
+            return Ok(());
+        }
+        waiter.wait();
+    }
+}
+
+/// Prints what every file of `files` has got since the last pass. Followed
+/// by name, a file is checked for having been replaced once it hasn't
+/// changed for `recheck_after` passes.
+fn follow_pass(
+    files: &mut Vec<Followed>,
+    last: &mut Option<usize>,
+    opts: &FollowOptions,
+    recheck_after: u32,
+    out: &mut impl Write,
+    buf: &mut [u8],
+) -> Result<()> {
+    let mut idx = 0;
+    while idx < files.len() {
+        let followed = &mut files[idx];
+        if opts.by_name && (followed.file.is_none() || followed.unchanged >= recheck_after) {
+            followed.unchanged = 0;
+            reopen_if_replaced(followed, idx, last, opts, out, buf)?;
+        } else if followed.file.is_none() && opts.retry {
+            open_if_appeared(followed, out)?;
+        }
+        rewind_if_truncated(followed, out)?;
+
+        if copy_new_data(followed, idx, last, opts, out, buf)? {
+            idx += 1;
+        } else if opts.by_name {
+            // Opened again by name once it can be read
+            followed.file = None;
+            idx += 1;
+        } else {
+            remove(files, idx, last);
+        }
+    }
+    Ok(())
+}
+
+/// Stops following `files[idx]`.
+fn remove(files: &mut Vec<Followed>, idx: usize, last: &mut Option<usize>) {
+    files.remove(idx);
+    // Indices after the removed file shift down by one
+    *last = match *last {
+        Some(last) if last == idx => None,
+        Some(last) if last > idx => Some(last - 1),
+        last => last,
+    };
+}
+
+/// Starts following the files that have appeared in the watched
+/// directories, from their start, and stops following those that are gone.
+fn rescan(
+    files: &mut Vec<Followed>,
+    last: &mut Option<usize>,
+    watch: &WatchedDirs,
+    out: &mut impl Write,
+) -> Result<()> {
+    let mut idx = 0;
+    while idx < files.len() {
+        let name = &files[idx].name;
+        if watch.contains(name) && !fs::exists(name).unwrap_or(true) {
+            out.flush()?;
+            eprintln!("'{name}' has been removed;  no longer following");
+            remove(files, idx, last);
+        } else {
+            idx += 1;
+        }
+    }
+    for name in watch.scan() {
+        if files.iter().all(|followed| followed.name != name) {
+            out.flush()?;
+            eprintln!("'{name}' has appeared;  following new file");
+            let mut followed = Followed {
+                name,
+                file: None,
+                unchanged: 0,
+            };
+            open_file(&mut followed);
+            files.push(followed);
+        }
+    }
+    Ok(())
+}
+
+/// Whether there is a process `pid`, even one this user can't signal.
+fn is_running(pid: i32) -> bool {
+    // Signal 0 only checks that the process exists
+    // SAFETY: signal 0 sends nothing, and pid is a plain integer
+    let ret = unsafe { libc::kill(pid, 0) };
+    ret == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Waits between passes over the files, for as long as nothing changes.
+enum Waiter {
+    Poll(Duration),
+    /// Woken by inotify, kqueue or whatever the platform has, or after
+    /// `timeout` in any case: some filesystems, like NFS and FUSE ones,
+    /// take a watch but never report anything, and a --pid process has to
+    /// be checked on. The watcher stops when dropped, so it is kept along
+    /// with its events.
+    Notify {
+        _watcher: RecommendedWatcher,
+        events: Receiver<notify::Result<notify::Event>>,
+        timeout: Duration,
+    },
+}
+
+impl Waiter {
+    /// Watches the directories of `files` rather than the files themselves,
+    /// so that files created or renamed there, as by log rotation, wake it
+    /// up too. Falls back to polling where that can't be done.
+    fn new(files: &[Followed], opts: &FollowOptions) -> Self {
+        if opts.use_polling {
+            debug!("polling every {:?}", opts.sleep_interval);
+            return Waiter::Poll(opts.sleep_interval);
+        }
+        let (tx, events) = mpsc::channel();
+        let watched = notify::recommended_watcher(tx).and_then(|mut watcher| {
+            let mut dirs: Vec<&Path> = files
+                .iter()
+                .map(|f| match Path::new(&f.name).parent() {
+                    Some(dir) if dir != Path::new("") => dir,
+                    _ => Path::new("."),
+                })
+                .chain(
+                    opts.watch
+                        .iter()
+                        .flat_map(|watch| watch.dirs.iter().map(PathBuf::as_path)),
+                )
+                .collect();
+            dirs.sort();
+            dirs.dedup();
+            for dir in dirs {
+                debug!(dir = %dir.display(), "watching");
+                watcher.watch(dir, RecursiveMode::NonRecursive)?;
+            }
+            Ok(watcher)
+        });
+        match watched {
+            Ok(watcher) => Waiter::Notify {
+                _watcher: watcher,
+                events,
+                timeout: opts.sleep_interval,
+            },
+            Err(err) => {
+                eprintln!("cannot watch for changes, reverting to polling: {err}");
+                Waiter::Poll(opts.sleep_interval)
+            }
+        }
+    }
+
+    fn wait(&self) {
+        match self {
+            Waiter::Poll(interval) => thread::sleep(*interval),
+            Waiter::Notify {
+                events, timeout, ..
+            } => {
+                // Timing out is not an error: the files are checked again
+                // all the same
+                let received = events.recv_timeout(*timeout);
+                if matches!(received, Err(RecvTimeoutError::Disconnected)) {
+                    // The watcher is gone, which shouldn't happen
+                    thread::sleep(Duration::from_secs(1));
+                }
+                // One pass takes care of everything that piled up
+                while let Ok(event) = events.try_recv() {
+                    trace!(?event, "woken up");
+                }
+            }
+        }
+    }
+}
+
+/// Whether both are the same file, as far as they can tell.
+fn same_file(a: &fs::Metadata, b: &fs::Metadata) -> bool {
+    (a.dev(), a.ino()) == (b.dev(), b.ino())
+}
+
+/// Switches to the file that now has the name of `followed`, if it isn't
+/// the one open, and reports the change the way ‘tail -F’ does. Whatever
+/// was still appended to the old file is printed first; the new one is
+/// printed from its start.
+fn reopen_if_replaced(
+    followed: &mut Followed,
+    idx: usize,
+    last: &mut Option<usize>,
+    opts: &FollowOptions,
+    out: &mut impl Write,
+    buf: &mut [u8],
+) -> Result<()> {
+    let current = fs::metadata(&followed.name);
+    let open = followed.file.as_ref().map(File::metadata).transpose()?;
+    let change = match (&current, &open) {
+        (Ok(current), Some(open)) if same_file(current, open) => return Ok(()),
+        (Err(_), None) => return Ok(()),
+        (Ok(_), Some(_)) => "has been replaced;  following new file".to_string(),
+        (Ok(_), None) => "has appeared;  following new file".to_string(),
+        (Err(err), Some(_)) => format!("has become inaccessible: {err}"),
+    };
+
+    // The old file is dropped either way, even if this fails to read it
+    copy_new_data(followed, idx, last, opts, out, buf)?;
+    out.flush()?;
+    eprintln!("'{}' {change}", followed.name);
+    followed.file = None;
+    if current.is_ok() {
+        open_file(followed);
+    }
+    Ok(())
+}
+
+/// For --retry without -F: opens a file that was missing once it exists.
+fn open_if_appeared(followed: &mut Followed, out: &mut impl Write) -> Result<()> {
+    if fs::exists(&followed.name).unwrap_or(false) {
+        out.flush()?;
+        eprintln!("'{}' has appeared;  following new file", followed.name);
+        open_file(followed);
+    }
+    Ok(())
+}
+
+fn open_file(followed: &mut Followed) {
+    match File::open(&followed.name) {
+        Ok(file) => followed.file = Some(file),
+        Err(err) => eprintln!("{}: {err}", followed.name),
+    }
+}
+
+/// Starts again from the beginning of a file that has become shorter than
+/// what was already printed, as happens to a log truncated in place.
+/// Anything that isn't a regular file, like a pipe, is left alone.
+fn rewind_if_truncated(followed: &mut Followed, out: &mut impl Write) -> Result<()> {
+    let Some(file) = &mut followed.file else {
+        return Ok(());
+    };
+    let (Ok(meta), Ok(pos)) = (file.metadata(), file.stream_position()) else {
+        return Ok(());
+    };
+    if meta.is_file() && meta.len() < pos {
+        out.flush()?;
+        eprintln!("{}: file truncated", followed.name);
+        file.rewind()?;
+    }
+    Ok(())
+}
+
+/// Copies what `followed` has got since it was last read to `out`, after a
+/// header if the previous output came from another file. Returns `false` if
+/// the file couldn't be read, which has been reported; only failed writes
+/// are errors. A file that isn't open has nothing to copy. Counts the
+/// passes that find nothing new in `followed.unchanged`.
+fn copy_new_data(
+    followed: &mut Followed,
+    idx: usize,
+    last: &mut Option<usize>,
+    opts: &FollowOptions,
+    out: &mut impl Write,
+    buf: &mut [u8],
+) -> Result<bool> {
+    let Some(file) = &mut followed.file else {
+        return Ok(true);
+    };
+    let _span = trace_span!("write", path = %followed.name).entered();
+    let mut copied = false;
+    loop {
+        let bytes_read = match file.read(buf) {
+            Ok(0) => {
+                followed.unchanged = if copied {
+                    0
+                } else {
+                    followed.unchanged.saturating_add(1)
+                };
+                return Ok(true);
+            }
+            Ok(bytes_read) => bytes_read,
+            Err(err) => {
+                eprintln!("{}: {err}", followed.name);
+                return Ok(false);
+            }
+        };
+        if let Some(format) = opts.headers
+            && *last != Some(idx)
+        {
+            // Initial output came before, unless no file could be opened
+            learnr::write_header(out, format, crate::display_name(&followed.name), false)?;
+        }
+        *last = Some(idx);
+        copied = true;
+        trace!(bytes = bytes_read, "appended");
+        out.write_all(&buf[..bytes_read])?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_follow_pass_max_unchanged_stats() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let log = dir.path().join("log");
+        fs::write(&log, "one\n")?;
+        let mut file = File::open(&log)?;
+        file.seek(io::SeekFrom::End(0))?;
+        let mut files = vec![Followed {
+            name: log.to_str().unwrap().to_string(),
+            file: Some(file),
+            unchanged: 0,
+        }];
+        let opts = FollowOptions {
+            sleep_interval: Duration::ZERO,
+            use_polling: true,
+            headers: None,
+            max_unchanged_stats: 3,
+            by_name: true,
+            retry: false,
+            pid: None,
+            watch: None,
+        };
+        let new = dir.path().join("log.new");
+        fs::write(&new, "two\n")?;
+        fs::rename(&new, &log)?;
+
+        let mut last = Some(0);
+        let mut out = vec![];
+        let mut buf = [0; 16];
+        // The name is only looked up again after three passes with nothing new
+        for _ in 0..3 {
+            follow_pass(&mut files, &mut last, &opts, 3, &mut out, &mut buf)?;
+            assert_eq!(out, b"");
+        }
+        follow_pass(&mut files, &mut last, &opts, 3, &mut out, &mut buf)?;
+        assert_eq!(out, b"two\n");
+        Ok(())
+    }
+}
@@ -0,0 +1,1000 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::ffi::OsString;
+use std::fs::{self, File, Metadata};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::mem;
+use std::os::fd::AsFd;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use anyhow::{Result, anyhow, bail};
+use clap::{ArgGroup, CommandFactory, Parser};
+use learnr::{HeaderFormat, HeaderedFiles};
+use tracing::{debug, debug_span};
+
+use follow::{FollowOptions, Followed};
+use watch::WatchedDirs;
+
+mod follow;
+mod watch;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Pos {
+    FromStart(usize),
+    FromEnd(usize),
+}
+
+#[derive(Debug)]
+enum Mode {
+    Lines(Pos),
+    Bytes(Pos),
+}
+
+/// Rust version of ‘tail’
+#[derive(Debug, Parser)]
+#[command(about, author, version)]
+#[command(group(ArgGroup::new("following").args(["follow", "follow_name"]).multiple(true)))]
+struct CLIArgs {
+    /// Input file(s), ‘-’ for standard input
+    #[arg(value_name = "FILE", default_value = "-")]
+    files: Vec<String>,
+
+    /// Number of lines (suffixes like K or MiB multiply)
+    #[arg(short('n'), long, value_parser=parse_pos, default_value = "10")]
+    lines: Pos,
+
+    /// Number of bytes (suffixes like K or MiB multiply)
+    #[arg(short('c'), long, value_parser=parse_pos, conflicts_with("lines"))]
+    bytes: Option<Pos>,
+
+    /// Suppress headers
+    #[arg(short, long, overrides_with = "verbose")]
+    quiet: bool,
+
+    /// Print headers even for a single file
+    #[arg(short, long, overrides_with = "quiet")]
+    verbose: bool,
+
+    /// How headers name each file, for programs reading the output
+    #[arg(long, value_name = "FORMAT", value_enum, default_value_t)]
+    header_format: HeaderFormat,
+
+    /// Line delimiter is NUL, not newline
+    #[arg(short, long)]
+    zero_terminated: bool,
+
+    /// Scan at most SIZE bytes backwards when looking for lines
+    #[arg(long, value_name = "SIZE", value_parser = learnr::parse_size)]
+    max_scan_bytes: Option<u64>,
+
+    /// Pipe each file through the shell command CMD (e.g. ‘zcat’) and tail
+    /// its output instead
+    #[arg(long, value_name = "CMD")]
+    pre_filter: Option<String>,
+
+    /// Remember in PATH how far each file was read, and go on from there next
+    /// time instead of printing the last lines again. Files that were
+    /// rotated or truncated since are printed from their start.
+    #[arg(long, value_name = "PATH", conflicts_with = "pre_filter")]
+    state_file: Option<PathBuf>,
+
+    /// Tail the files in each directory FILE whose name matches PATTERN (like
+    /// ‘*.log’), and when following, also those that appear there later,
+    /// until they are removed
+    #[arg(long, value_name = "PATTERN")]
+    glob: Vec<String>,
+
+    /// With --glob, leave out files whose name matches PATTERN
+    #[arg(long, value_name = "PATTERN", requires = "glob")]
+    exclude: Vec<String>,
+
+    /// Output appended data as the files grow
+    #[arg(short, long, conflicts_with_all = ["pre_filter", "state_file"])]
+    follow: bool,
+
+    /// Follow each file by name and keep retrying, so that logs are picked
+    /// up again after rotation; like --follow --retry
+    #[arg(short('F'), conflicts_with_all = ["pre_filter", "state_file"])]
+    follow_name: bool,
+
+    /// Keep trying to open files that can't be opened, when following
+    #[arg(long)]
+    retry: bool,
+
+    /// With --follow, check for new data every --sleep-interval instead of
+    /// waiting for the filesystem to report changes
+    #[arg(long, requires = "following")]
+    use_polling: bool,
+
+    /// With --follow, stop after process PID exits
+    #[arg(long, value_name = "PID", value_parser = clap::value_parser!(i32).range(1..), requires = "following")]
+    pid: Option<i32>,
+
+    /// With --follow, wait N seconds between checks for new data when
+    /// polling, and at most that long for the filesystem to report changes
+    #[arg(
+        short,
+        long,
+        value_name = "N",
+        default_value = "1.0",
+        value_parser = parse_sleep_interval,
+        requires = "following"
+    )]
+    sleep_interval: Duration,
+
+    /// With -F and polling, check whether a file was replaced only after N
+    /// checks in a row found nothing new in it
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 5,
+        value_parser = clap::value_parser!(u32).range(1..),
+        requires = "follow_name"
+    )]
+    max_unchanged_stats: u32,
+
+    /// Explain on stderr where each file is read from and, when following,
+    /// what wakes tailr up (RUST_LOG allows finer filters)
+    #[arg(long)]
+    debug: bool,
+}
+
+#[derive(Debug)]
+struct Args {
+    files: Vec<String>,
+    /// How to print a header before each file, if at all
+    headers: Option<HeaderFormat>,
+    mode: Mode,
+    /// What ends a line for -n: a newline, or NUL with -z
+    delimiter: u8,
+    max_scan_bytes: Option<u64>,
+    pre_filter: Option<String>,
+    state_file: Option<PathBuf>,
+    /// Set with --follow
+    follow: Option<FollowOptions>,
+}
+
+/// How far a file was read by a previous run with --state-file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Checkpoint {
+    offset: u64,
+    /// The inode and the length of the file when it was read, so that a
+    /// rotated or truncated file is noticed
+    inode: u64,
+    size: u64,
+}
+
+impl Checkpoint {
+    /// Where to go on reading the file with `metadata`, if it is still the
+    /// one this checkpoint was taken of.
+    fn resume_offset(&self, metadata: &Metadata) -> Option<u64> {
+        (metadata.ino() == self.inode && metadata.len() >= self.size).then_some(self.offset)
+    }
+}
+
+/// The contents of a --state-file: one line per file with its offset, inode
+/// and size, and the name as given on the command line, separated by tabs.
+#[derive(Debug, Default, PartialEq)]
+struct State {
+    files: BTreeMap<String, Checkpoint>,
+}
+
+impl State {
+    /// A missing file is an empty state, as on the very first run.
+    fn load(path: &Path) -> Result<Self> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(State::default()),
+            Err(err) => bail!("{}: {err}", path.display()),
+        };
+        let mut files = BTreeMap::new();
+        for (num, line) in text.lines().enumerate() {
+            let parse = || -> Option<(String, Checkpoint)> {
+                let mut fields = line.splitn(4, '\t');
+                let offset = fields.next()?.parse().ok()?;
+                let inode = fields.next()?.parse().ok()?;
+                let size = fields.next()?.parse().ok()?;
+                let name = fields.next()?.to_string();
+                Some((
+                    name,
+                    Checkpoint {
+                        offset,
+                        inode,
+                        size,
+                    },
+                ))
+            };
+            let (name, checkpoint) =
+                parse().ok_or_else(|| anyhow!("{}:{}: invalid state", path.display(), num + 1))?;
+            files.insert(name, checkpoint);
+        }
+        Ok(State { files })
+    }
+
+    /// Replaces the file in one step, so that a crash never leaves a state
+    /// that is half written.
+    fn save(&self, path: &Path) -> Result<()> {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+        for (name, cp) in &self.files {
+            writeln!(tmp, "{}\t{}\t{}\t{name}", cp.offset, cp.inode, cp.size)?;
+        }
+        tmp.persist(path)
+            .map_err(|err| anyhow!("{}: {}", path.display(), err.error))?;
+        Ok(())
+    }
+}
+
+/// The command line that [`main`] parses, for generating the man page.
+pub fn command() -> clap::Command {
+    CLIArgs::command()
+}
+
+/// What the man page tells beyond --help.
+pub const MAN_DESCRIPTION: &str = "\
+Each FILE is printed from the given number of lines or bytes before its end, \
+or from the given number after its start when the count is prefixed with ‘+’. \
+Regular files are read backwards from the end, so even huge ones are quick; \
+standard input and pipes are read through and only their last part kept.\n\n\
+When following, tailr keeps printing whatever is appended and waits for the \
+filesystem to report changes where it can. With -F a file that is rotated, \
+truncated or recreated is picked up again, with a note on standard error.\n\n\
+A directory given with --glob stands for its files whose names match one of \
+the patterns and none of the --exclude ones. When following, files that \
+appear there later are followed from their start, and those deleted are \
+dropped.";
+
+pub fn main(args: impl IntoIterator<Item = OsString>) -> Result<()> {
+    let args = parse_args(args)?;
+    let mut headers = HeaderedFiles::new(args.headers);
+    let mut state = args.state_file.as_deref().map(State::load).transpose()?;
+    let mut followed = vec![];
+
+    let mut failed = false;
+
+    for file in &args.files {
+        let fh = match process_file(file, &args, &mut headers, state.as_mut()) {
+            // Pipes and the like have nothing more to come once read to the end
+            Ok(None) => continue,
+            Ok(fh) => fh,
+            Err(e) => {
+                learnr::exit_on_broken_pipe(&e);
+                eprintln!("{file}: {e}");
+                failed = true;
+                None
+            }
+        };
+        if fh.is_some() || args.follow.as_ref().is_some_and(|opts| opts.retry) {
+            followed.push(Followed {
+                name: file.to_string(),
+                file: fh,
+                unchanged: 0,
+            });
+        }
+    }
+
+    if let (Some(state), Some(path)) = (state, &args.state_file) {
+        state.save(path)?;
+    }
+    if let Some(opts) = &args.follow
+        && (failed || !followed.is_empty() || opts.watch.is_some())
+    {
+        // The last file printed is the last one that could be opened
+        let last = followed.iter().rposition(|f| f.file.is_some());
+        follow::follow(followed, last, opts)?;
+    }
+    Ok(())
+}
+
+/// Prints the tail of `file`, and returns it open right after what was
+/// printed. Anything that can't seek, like a pipe, is read to its end and
+/// not returned.
+fn process_file(
+    file: &str,
+    args: &Args,
+    headers: &mut HeaderedFiles,
+    state: Option<&mut State>,
+) -> Result<Option<File>> {
+    let _span = debug_span!("open", path = %file).entered();
+    let mut fh = if file == "-" {
+        File::from(io::stdin().as_fd().try_clone_to_owned()?)
+    } else {
+        File::open(file)?
+    };
+    if let Some(cmd) = &args.pre_filter {
+        fh = pre_filter(cmd, fh)?;
+    }
+
+    headers.start(&mut io::stdout(), display_name(file))?;
+
+    let metadata = fh.metadata()?;
+    if !metadata.is_file() {
+        debug!("not a regular file, reading it to the end");
+        tail(
+            &mut fh,
+            false,
+            &args.mode,
+            args.delimiter,
+            None,
+            &mut io::stdout(),
+        )?;
+        return Ok(None);
+    }
+    // A file seen before is new from the checkpoint on, or entirely so if it
+    // was replaced in the meantime
+    let resume = state
+        .as_ref()
+        .and_then(|state| state.files.get(file))
+        .map(|checkpoint| checkpoint.resume_offset(&metadata).unwrap_or(0));
+
+    if let Some(offset) = resume {
+        debug!(offset, "going on from the --state-file checkpoint");
+        copy_to_stdout(&mut fh, &SeekFrom::Start(offset))?;
+    } else if tail(
+        &mut fh,
+        true,
+        &args.mode,
+        args.delimiter,
+        args.max_scan_bytes,
+        &mut io::stdout(),
+    )? {
+        eprintln!(
+            "{file}: warning: stopped scanning after {} bytes, output is truncated",
+            args.max_scan_bytes.unwrap_or_default()
+        );
+    }
+
+    if let Some(state) = state {
+        let offset = fh.stream_position()?;
+        state.files.insert(
+            file.to_string(),
+            Checkpoint {
+                offset,
+                inode: metadata.ino(),
+                size: offset,
+            },
+        );
+    }
+    Ok(Some(fh))
+}
+
+/// How headers name `file`.
+fn display_name(file: &str) -> &str {
+    if file == "-" { "standard input" } else { file }
+}
+
+/// Prints the tail of `input` that `mode` asks for, counting lines that end
+/// with `delimiter`. Whatever follows the part skipped for ‘+N’ is copied as
+/// it comes. The last N lines or bytes for ‘-n N’ and ‘-c N’ are found by
+/// seeking back from the end when `input` is `seekable`, and otherwise by
+/// keeping only them while it is read to its end. Returns whether the
+/// backward scan was cut short by `max_scan_bytes`.
+fn tail(
+    input: &mut (impl Read + Seek),
+    seekable: bool,
+    mode: &Mode,
+    delimiter: u8,
+    max_scan_bytes: Option<u64>,
+    out: &mut impl Write,
+) -> Result<bool> {
+    let _span = debug_span!("seek").entered();
+    let mut truncated = false;
+    match mode {
+        Mode::Lines(Pos::FromStart(offset)) => {
+            let mut input = BufReader::new(input);
+            let mut line = vec![];
+            for _ in 0..*offset {
+                line.clear();
+                if input.read_until(delimiter, &mut line)? == 0 {
+                    break;
+                }
+            }
+            io::copy(&mut input, out)?;
+        }
+        Mode::Bytes(pos) if seekable => {
+            let seek_pos = bytes_seek_pos(pos, input)?;
+            debug!(?seek_pos, "output starts");
+            input.seek(seek_pos)?;
+            io::copy(input, out)?;
+        }
+        Mode::Bytes(Pos::FromStart(offset)) => {
+            io::copy(&mut input.by_ref().take(*offset as u64), &mut io::sink())?;
+            io::copy(input, out)?;
+        }
+        Mode::Lines(Pos::FromEnd(count)) if seekable => {
+            let seek_pos;
+            (seek_pos, truncated) = lines_seek_pos(*count, input, delimiter, max_scan_bytes)?;
+            debug!(?seek_pos, "output starts");
+            input.seek(seek_pos)?;
+            io::copy(input, out)?;
+        }
+        Mode::Bytes(Pos::FromEnd(count)) => last_bytes(input, *count, out)?,
+        Mode::Lines(Pos::FromEnd(count)) => last_lines(input, *count, delimiter, out)?,
+    }
+    Ok(truncated)
+}
+
+/// Prints the last `count` bytes of `input`, keeping no more than them as it
+/// is read to its end.
+fn last_bytes(input: impl Read, count: usize, out: &mut impl Write) -> Result<()> {
+    let mut input = BufReader::new(input);
+    let mut last = VecDeque::with_capacity(count.min(BUF_SIZE));
+    let mut buf = [0_u8; 4096];
+    loop {
+        let bytes_read = input.read(&mut buf)?;
+        if bytes_read == 0 {
+            break;
+        }
+        // Only the end of a chunk larger than the whole tail matters
+        let chunk = &buf[bytes_read.saturating_sub(count)..bytes_read];
+        let overflow = (last.len() + chunk.len()).saturating_sub(count);
+        last.drain(..overflow);
+        last.extend(chunk);
+    }
+    let (front, back) = last.as_slices();
+    out.write_all(front)?;
+    out.write_all(back)?;
+    Ok(())
+}
+
+/// Prints the last `count` lines of `input`, keeping no more than them as it
+/// is read to its end.
+fn last_lines(input: impl Read, count: usize, delimiter: u8, out: &mut impl Write) -> Result<()> {
+    if count == 0 {
+        return Ok(());
+    }
+    let mut input = BufReader::new(input);
+    let mut last: VecDeque<Vec<u8>> = VecDeque::with_capacity(count.min(BUF_SIZE));
+    let mut line = vec![];
+    loop {
+        line.clear();
+        if input.read_until(delimiter, &mut line)? == 0 {
+            break;
+        }
+        // The line dropped from the front is reused for the next one
+        let spare = if last.len() == count {
+            last.pop_front().unwrap_or_default()
+        } else {
+            vec![]
+        };
+        last.push_back(mem::replace(&mut line, spare));
+    }
+    for line in last {
+        out.write_all(&line)?;
+    }
+    Ok(())
+}
+
+/// Runs `cmd` through the shell with `input` as its standard input and
+/// spools the output into an anonymous temporary file, so that the rest of
+/// tailr can seek in it just like in the original file.
+fn pre_filter(cmd: &str, input: File) -> Result<File> {
+    let mut child = Command::new("sh")
+        .args(["-c", cmd])
+        .stdin(input)
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|err| anyhow!("{cmd}: {err}"))?;
+
+    let mut spool = tempfile::tempfile()?;
+    let copied = io::copy(child.stdout.as_mut().expect("stdout is piped"), &mut spool);
+    // Always reap the child, even if the copy failed halfway
+    let status = child.wait()?;
+    copied?;
+    if !status.success() {
+        bail!("pre-filter '{cmd}' failed: {status}");
+    }
+
+    spool.rewind()?;
+    Ok(spool)
+}
+
+fn bytes_seek_pos(pos: &Pos, fh: &mut impl Seek) -> Result<SeekFrom> {
+    fh.seek(SeekFrom::End(0))?;
+
+    let len: usize = fh.stream_position()?.try_into()?;
+
+    // NOTE: SeekFrom::Start(u64), but SeekFrom::End(i64)
+    match pos {
+        Pos::FromStart(offset) => Ok(SeekFrom::Start(std::cmp::min(len, *offset).try_into()?)),
+        Pos::FromEnd(offset) => Ok(SeekFrom::End(-std::cmp::min(len, *offset).try_into()?)),
+    }
+}
+
+/// Where the last `count` lines start, counting lines that end with
+/// `delimiter`. Also tells whether the backward scan was cut short by
+/// `max_scan_bytes` before all of them were found.
+fn lines_seek_pos(
+    count: usize,
+    fh: &mut (impl Read + Seek),
+    delimiter: u8,
+    max_scan_bytes: Option<u64>,
+) -> Result<(SeekFrom, bool)> {
+    if count == 0 {
+        return Ok((SeekFrom::End(0), false));
+    }
+    let mut scanner = BackScanner::new(fh)?;
+    let mut need_bytes: i64 = 0;
+    let max_bytes: Option<i64> = max_scan_bytes.map(|max| max.try_into()).transpose()?;
+    let mut truncated = false;
+
+    let mut rem = count;
+
+    if scanner.peek() == Some(delimiter) {
+        // to show last line -> we need to find 2nd delimiter from end
+        rem += 1;
+    }
+
+    for byte in scanner {
+        let byte = byte?;
+        if byte == delimiter {
+            rem -= 1;
+            if rem == 0 {
+                break;
+            }
+        }
+        if max_bytes.is_some_and(|max| need_bytes >= max) {
+            truncated = true;
+            break;
+        }
+        need_bytes += 1;
+    }
+
+    Ok((SeekFrom::End(-need_bytes), truncated))
+}
+
+const BUF_SIZE: usize = if cfg!(test) { 10 } else { 4_096 };
+
+struct BackScanner<'a, FH> {
+    fh: &'a mut FH,
+    buf: [u8; BUF_SIZE],
+    buf_pos: usize,
+    buf_offset_in_file: usize,
+}
+
+impl<'a, FH: Seek + Read> BackScanner<'a, FH> {
+    fn new(fh: &'a mut FH) -> Result<Self> {
+        fh.seek(SeekFrom::End(0))?;
+        let file_len: usize = fh.stream_position()?.try_into()?;
+
+        let mut last_chunk_len = file_len % BUF_SIZE;
+
+        if last_chunk_len == 0 && file_len >= BUF_SIZE {
+            last_chunk_len = BUF_SIZE;
+        }
+
+        let buf_offset_in_file: usize = file_len.saturating_sub(last_chunk_len);
+        let buf = [0_u8; BUF_SIZE];
+
+        let mut scanner = BackScanner {
+            fh,
+            buf,
+            buf_pos: BUF_SIZE,
+            buf_offset_in_file,
+        };
+
+        Self::fill_buf(&mut scanner)?;
+
+        Ok(scanner)
+    }
+
+    fn fill_buf(&mut self) -> Result<()> {
+        let mut buf_target: usize = 0;
+        self.fh
+            .seek(SeekFrom::Start(self.buf_offset_in_file.try_into()?))?;
+        loop {
+            let bytes_read = self.fh.read(&mut self.buf[buf_target..])?;
+            buf_target += bytes_read;
+            if buf_target == BUF_SIZE || bytes_read == 0 {
+                break;
+            }
+        }
+        self.buf_pos = buf_target;
+        Ok(())
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        if self.buf_pos > 0 {
+            Some(self.buf[self.buf_pos - 1])
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, FH: Seek + Read> Iterator for BackScanner<'a, FH> {
+    type Item = Result<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buf_pos == 0 {
+            if self.buf_offset_in_file == 0 {
+                return None;
+            }
+
+            self.buf_offset_in_file -= BUF_SIZE;
+            assert!(self.buf_offset_in_file.is_multiple_of(BUF_SIZE));
+
+            if let Err(e) = self.fill_buf() {
+                return Some(Err(e));
+            }
+        }
+
+        self.buf_pos -= 1;
+
+        Some(Ok(self.buf[self.buf_pos]))
+    }
+}
+
+fn copy_to_stdout(fh: &mut File, seek: &SeekFrom) -> Result<()> {
+    fh.seek(*seek)?;
+
+    let mut output = std::io::stdout();
+
+    let mut buf = [0_u8; 4096];
+    loop {
+        let bytes_read = fh.read(&mut buf)?;
+        if bytes_read == 0 {
+            break;
+        }
+        output.write_all(&buf[0..bytes_read])?;
+    }
+
+    Ok(())
+}
+
+fn parse_args(args: impl IntoIterator<Item = OsString>) -> Result<Args> {
+    let CLIArgs {
+        files,
+        lines,
+        bytes,
+        quiet,
+        verbose,
+        header_format,
+        zero_terminated,
+        max_scan_bytes,
+        pre_filter,
+        state_file,
+        glob,
+        exclude,
+        follow,
+        follow_name,
+        retry,
+        use_polling,
+        pid,
+        sleep_interval,
+        max_unchanged_stats,
+        debug,
+    } = CLIArgs::parse_from(args);
+    learnr::init_tracing(debug);
+
+    // Directories are replaced by the files picked in them, which may yet
+    // change when following
+    let (files, watch) = if glob.is_empty() {
+        (files, None)
+    } else {
+        let (dirs, mut files): (Vec<_>, Vec<_>) =
+            files.into_iter().partition(|file| Path::new(file).is_dir());
+        let watch = WatchedDirs::new(
+            dirs.into_iter().map(PathBuf::from).collect(),
+            &glob,
+            &exclude,
+        )?;
+        files.extend(watch.scan());
+        (files, Some(watch))
+    };
+    let headers =
+        (verbose || (!quiet && (files.len() > 1 || watch.is_some()))).then_some(header_format);
+    let mode = if let Some(bytes) = bytes {
+        Mode::Bytes(bytes)
+    } else {
+        Mode::Lines(lines)
+    };
+
+    if retry && !follow && !follow_name {
+        eprintln!("warning: --retry ignored; --retry is useful only when following");
+    }
+    let follow = (follow || follow_name).then_some(FollowOptions {
+        sleep_interval,
+        use_polling,
+        headers,
+        max_unchanged_stats,
+        by_name: follow_name,
+        retry: retry || follow_name,
+        pid,
+        watch,
+    });
+
+    Ok(Args {
+        files,
+        headers,
+        mode,
+        delimiter: if zero_terminated { b'\0' } else { b'\n' },
+        max_scan_bytes,
+        pre_filter,
+        state_file,
+        follow,
+    })
+}
+
+fn parse_sleep_interval(arg: &str) -> Result<Duration> {
+    let secs: f64 = arg
+        .parse()
+        .map_err(|_| anyhow!("invalid number of seconds: '{arg}'"))?;
+    Duration::try_from_secs_f64(secs).map_err(|_| anyhow!("invalid number of seconds: '{arg}'"))
+}
+
+fn parse_pos(arg: &str) -> Result<Pos> {
+    if arg.is_empty() {
+        return Err(anyhow!("Position arg can't be empty"));
+    }
+    let (from_start, num) = match arg.chars().next() {
+        Some('+') => (true, &arg[1..]),
+        Some('-') => (false, &arg[1..]),
+        _ => (false, arg),
+    };
+    // As with ‘tail -c 5M’ or ‘tail -n +2k’
+    let num = learnr::parse_count(num)
+        .and_then(|num| usize::try_from(num).map_err(|_| anyhow!("count '{num}' is too large")))
+        .map_err(|err| anyhow!("{arg}: {err}"))?;
+
+    match from_start {
+        true => Ok(Pos::FromStart(if num > 0 { num - 1 } else { 0 })), // ‘+n’ are one-base indexed (and ‘+0’ is an exception)
+        false => Ok(Pos::FromEnd(num)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assertables::*;
+    use learnr::assert_err_str_contains;
+
+    use super::Pos::*;
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_state_roundtrip() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("state");
+        assert_eq!(State::load(&path)?, State::default());
+
+        let mut state = State::default();
+        let checkpoint = Checkpoint {
+            offset: 42,
+            inode: 7,
+            size: 42,
+        };
+        state
+            .files
+            .insert("logs/with\ttab.log".to_string(), checkpoint);
+        state.files.insert(
+            "b.log".to_string(),
+            Checkpoint {
+                offset: 0,
+                ..checkpoint
+            },
+        );
+        state.save(&path)?;
+        assert_eq!(
+            fs::read_to_string(&path)?,
+            "0\t7\t42\tb.log\n42\t7\t42\tlogs/with\ttab.log\n"
+        );
+        assert_eq!(State::load(&path)?, state);
+
+        fs::write(&path, "1\t2\n")?;
+        assert_err_str_contains!(State::load(&path), "state:1: invalid state");
+        Ok(())
+    }
+
+    #[test]
+    fn test_checkpoint_resume_offset() -> Result<()> {
+        let metadata = fs::metadata("tests/inputs/twelve.txt")?;
+        let checkpoint = Checkpoint {
+            offset: 10,
+            inode: metadata.ino(),
+            size: 10,
+        };
+        assert_eq!(checkpoint.resume_offset(&metadata), Some(10));
+
+        let rotated = Checkpoint {
+            inode: metadata.ino() + 1,
+            ..checkpoint
+        };
+        assert_eq!(rotated.resume_offset(&metadata), None);
+
+        let truncated = Checkpoint {
+            offset: 1 << 20,
+            size: 1 << 20,
+            ..checkpoint
+        };
+        assert_eq!(truncated.resume_offset(&metadata), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_pos() {
+        // no prefix -> from end
+        assert_ok_eq_x!(parse_pos("3"), FromEnd(3));
+
+        // leading "+"
+        assert_ok_eq_x!(parse_pos("+3"), FromStart(2));
+
+        // An explicit "-" prefix is the same as no prefix
+        assert_ok_eq_x!(parse_pos("-3"), FromEnd(3));
+
+        // Zero is zero
+        assert_ok_eq_x!(parse_pos("0"), FromEnd(0));
+
+        // Plus zero is special
+        assert_ok_eq_x!(parse_pos("+0"), FromStart(0));
+
+        // Test boundaries
+        assert_ok_eq_x!(
+            parse_pos(format!("+{}", usize::MAX).as_str()),
+            FromStart(usize::MAX - 1)
+        );
+
+        // Multiplier suffixes, with either prefix
+        assert_ok_eq_x!(parse_pos("2k"), FromEnd(2048));
+        assert_ok_eq_x!(parse_pos("-5M"), FromEnd(5 << 20));
+        assert_ok_eq_x!(parse_pos("+1kB"), FromStart(999));
+        assert_ok_eq_x!(parse_pos("+2b"), FromStart(1023));
+        assert_err_str_contains!(parse_pos("16E"), "16E: count '16E' is too large");
+        assert_err_str_contains!(parse_pos("3X"), "3X: invalid suffix in count '3X'");
+
+        // A floating-point value is invalid
+        assert_err_str_contains!(parse_pos("3.14"), "invalid suffix in count '3.14'");
+
+        // Any non-integer string is invalid
+        assert_err_str_contains!(parse_pos("foo"), "foo: invalid count 'foo'");
+    }
+
+    #[test]
+    fn test_lines_seek_pos_max_scan_bytes() -> Result<()> {
+        let mut fh = Cursor::new("one\ntwo\nthree\n");
+        assert_eq!(
+            lines_seek_pos(2, &mut fh, b'\n', None)?,
+            (SeekFrom::End(-10), false)
+        );
+        assert_eq!(
+            lines_seek_pos(2, &mut fh, b'\n', Some(10))?,
+            (SeekFrom::End(-10), false)
+        );
+        assert_eq!(
+            lines_seek_pos(2, &mut fh, b'\n', Some(4))?,
+            (SeekFrom::End(-4), true)
+        );
+        // Reaching the start of the file is not a truncation
+        assert_eq!(
+            lines_seek_pos(5, &mut fh, b'\n', Some(14))?,
+            (SeekFrom::End(-14), false)
+        );
+        // Only backward scans are limited
+        let mut out = vec![];
+        let mut fh = Cursor::new("one\ntwo\nthree\n");
+        let truncated = tail(
+            &mut fh,
+            true,
+            &Mode::Lines(FromStart(2)),
+            b'\n',
+            Some(1),
+            &mut out,
+        )?;
+        assert_eq!((out, truncated), (b"three\n".to_vec(), false));
+        Ok(())
+    }
+
+    #[test]
+    fn test_lines_seek_pos_zero_terminated() -> Result<()> {
+        let mut fh = Cursor::new("one\0two\nlines\0three\0");
+        assert_eq!(
+            lines_seek_pos(2, &mut fh, b'\0', None)?,
+            (SeekFrom::End(-16), false)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn backscanner_empty_file() -> Result<()> {
+        let mut fh = Cursor::new("");
+        assert_eq!(None, BackScanner::new(&mut fh)?.peek());
+        if BackScanner::new(&mut fh)?.next().is_some() {
+            panic!("Should never get here");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn backscanner_small_file() -> Result<()> {
+        let mut fh = Cursor::new("abcdef");
+        assert_eq!(
+            "fedcba".to_string(),
+            BackScanner::new(&mut fh)?
+                .map(|r| -> char { r.unwrap().into() })
+                .collect::<String>()
+        );
+        Ok(())
+    }
+    #[test]
+
+    // big -> more that BUF_SIZE
+    fn backscanner_big_file() -> Result<()> {
+        let contents = "012345678901234567890123456789XXX".to_string();
+        let mut fh = Cursor::new(&contents);
+        assert_eq!(
+            contents.chars().rev().collect::<String>(),
+            BackScanner::new(&mut fh)?
+                .map(|r| -> char { r.unwrap().into() })
+                .collect::<String>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_tail() -> Result<()> {
+        // Seeking or not, the tail is the same
+        let tail_of = |mode: Mode, input: &str| -> Result<String> {
+            let mut tails = vec![];
+            for seekable in [true, false] {
+                let mut out = vec![];
+                tail(
+                    &mut Cursor::new(input),
+                    seekable,
+                    &mode,
+                    b'\n',
+                    None,
+                    &mut out,
+                )?;
+                tails.push(String::from_utf8(out)?);
+            }
+            assert_eq!(tails[0], tails[1], "{mode:?}");
+            Ok(tails.swap_remove(0))
+        };
+        let input = "one\ntwo\nthree\nfour";
+        assert_eq!(tail_of(Mode::Lines(FromEnd(2)), input)?, "three\nfour");
+        assert_eq!(tail_of(Mode::Lines(FromEnd(9)), input)?, input);
+        assert_eq!(tail_of(Mode::Lines(FromEnd(0)), input)?, "");
+        assert_eq!(tail_of(Mode::Lines(FromStart(2)), input)?, "three\nfour");
+        assert_eq!(tail_of(Mode::Lines(FromStart(9)), input)?, "");
+        assert_eq!(tail_of(Mode::Bytes(FromEnd(6)), input)?, "e\nfour");
+        assert_eq!(tail_of(Mode::Bytes(FromEnd(0)), input)?, "");
+        assert_eq!(tail_of(Mode::Bytes(FromEnd(99)), input)?, input);
+        assert_eq!(tail_of(Mode::Bytes(FromStart(14)), input)?, "four");
+        assert_eq!(tail_of(Mode::Bytes(FromStart(99)), input)?, "");
+
+        for seekable in [true, false] {
+            let mut out = vec![];
+            let mut input = Cursor::new("a\0b\nc\0d");
+            tail(
+                &mut input,
+                seekable,
+                &Mode::Lines(FromEnd(2)),
+                b'\0',
+                None,
+                &mut out,
+            )?;
+            assert_eq!(out, b"b\nc\0d");
+            let mut out = vec![];
+            let mut input = Cursor::new("one\0two\nlines\0three\0");
+            tail(
+                &mut input,
+                seekable,
+                &Mode::Lines(FromStart(2)),
+                b'\0',
+                None,
+                &mut out,
+            )?;
+            assert_eq!(out, b"three\0");
+        }
+
+        // The kept bytes span several reads
+        let long = "x".repeat(10_000) + "end";
+        assert_eq!(tail_of(Mode::Bytes(FromEnd(5000)), &long)?, long[5003..]);
+        Ok(())
+    }
+}
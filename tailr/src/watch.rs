@@ -0,0 +1,98 @@
+//! Following every file of some directories whose name matches --glob, as
+//! files come and go.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, anyhow};
+use glob::Pattern;
+
+/// Directories whose files are followed, picked by their names.
+#[derive(Debug)]
+pub struct WatchedDirs {
+    pub dirs: Vec<PathBuf>,
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl WatchedDirs {
+    /// Files are picked if their name matches one of `include` and none of
+    /// `exclude`, which are patterns like ‘*.log’.
+    pub fn new(dirs: Vec<PathBuf>, include: &[String], exclude: &[String]) -> Result<Self> {
+        let compile = |patterns: &[String]| -> Result<Vec<Pattern>> {
+            patterns
+                .iter()
+                .map(|pattern| Pattern::new(pattern).map_err(|err| anyhow!("'{pattern}': {err}")))
+                .collect()
+        };
+        Ok(WatchedDirs {
+            dirs,
+            include: compile(include)?,
+            exclude: compile(exclude)?,
+        })
+    }
+
+    /// Whether `path` is, or would be, one of the files picked.
+    pub fn contains(&self, path: &str) -> bool {
+        let path = Path::new(path);
+        let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+            return false;
+        };
+        let Some(name) = name.to_str() else {
+            return false;
+        };
+        self.dirs.iter().any(|watched| watched == dir)
+            && self.include.iter().any(|pattern| pattern.matches(name))
+            && !self.exclude.iter().any(|pattern| pattern.matches(name))
+    }
+
+    /// The regular files picked, directory by directory and by name within
+    /// each. Directories that can't be read have nothing in them.
+    pub fn scan(&self) -> Vec<String> {
+        let mut found = vec![];
+        for dir in &self.dirs {
+            let Ok(entries) = fs::read_dir(dir) else {
+                continue;
+            };
+            let mut names: Vec<String> = entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_file()))
+                .filter_map(|entry| entry.path().to_str().map(String::from))
+                .filter(|path| self.contains(path))
+                .collect();
+            names.sort();
+            found.append(&mut names);
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        for name in ["b.log", "a.log", "c.txt", "old.log"] {
+            fs::write(dir.path().join(name), "")?;
+        }
+        fs::create_dir(dir.path().join("d.log"))?;
+        let watched = WatchedDirs::new(
+            vec![dir.path().to_path_buf()],
+            &["*.log".to_string()],
+            &["old.*".to_string()],
+        )?;
+        let path = |name| dir.path().join(name).to_str().unwrap().to_string();
+        assert_eq!(watched.scan(), [path("a.log"), path("b.log")]);
+        assert!(watched.contains(&path("new.log")));
+        assert!(!watched.contains(&path("old.log")));
+        assert!(!watched.contains("elsewhere/a.log"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_bad_pattern() {
+        learnr::assert_err_str_contains!(WatchedDirs::new(vec![], &["[".to_string()], &[]), "'['");
+    }
+}
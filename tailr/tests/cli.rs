@@ -4,7 +4,7 @@ use predicates::prelude::*;
 use pretty_assertions::assert_eq;
 use rand::{Rng, distributions::Alphanumeric};
 use std::fs::{self, File};
-use std::io::Read;
+use std::io::{Read, Write};
 const EMPTY: &str = "tests/inputs/empty.txt";
 const ONE: &str = "tests/inputs/one.txt";
 const TWO: &str = "tests/inputs/two.txt";
@@ -32,12 +32,14 @@ fn gen_bad_file() -> String {
 
 // --------------------------------------------------
 #[test]
-fn dies_no_args() -> Result<()> {
+fn stdin_by_default() -> Result<()> {
+    let input = fs::read_to_string(TWELVE)?;
+    let expected = fs::read_to_string("tests/expected/twelve.txt.out")?;
     cargo_bin_cmd!()
+        .write_stdin(input)
         .assert()
-        .failure()
-        .stderr(predicate::str::contains("Usage"));
-
+        .success()
+        .stdout(expected);
     Ok(())
 }
 
@@ -45,7 +47,7 @@ fn dies_no_args() -> Result<()> {
 #[test]
 fn dies_bad_bytes() -> Result<()> {
     let bad = random_string();
-    let expected = format!("--bytes.*{bad}: invalid digit found in string");
+    let expected = format!("--bytes.*{bad}: invalid (suffix in )?count '{bad}'");
     cargo_bin_cmd!()
         .args(["-c", &bad, EMPTY])
         .assert()
@@ -59,12 +61,12 @@ fn dies_bad_bytes() -> Result<()> {
 #[test]
 fn dies_bad_lines() -> Result<()> {
     let bad = random_string();
-    let expected = format!("{bad}: invalid digit found in string");
+    let expected = format!("{bad}: invalid (suffix in )?count '{bad}'");
     cargo_bin_cmd!()
         .args(["-n", &bad, EMPTY])
         .assert()
         .failure()
-        .stderr(predicate::str::contains(expected));
+        .stderr(predicate::str::is_match(expected).unwrap());
 
     Ok(())
 }
@@ -84,6 +86,62 @@ fn dies_bytes_and_lines() -> Result<()> {
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+fn count_suffixes() -> Result<()> {
+    let input: String = (0..3000).map(|i| format!("{i}\n")).collect();
+    let output = cargo_bin_cmd!()
+        .args(["-c", "1K"])
+        .write_stdin(input.as_str())
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(output.stdout, input.as_bytes()[input.len() - 1024..]);
+
+    let expected: String = (1999..3000).map(|i| format!("{i}\n")).collect();
+    cargo_bin_cmd!()
+        .args(["-n", "+2kB"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn debug_explains_seek() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--debug", "-n", "1", ONE])
+        .env_remove("RUST_LOG")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(format!(
+            "open{{path={ONE}}}:seek: output starts seek_pos=End(-24)"
+        )));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn zero_terminated() -> Result<()> {
+    let input = "one\0two\nlines\0three\0";
+    let file = tempfile::NamedTempFile::new()?;
+    fs::write(&file, input)?;
+    cargo_bin_cmd!()
+        .args(["-z", "-n", "2"])
+        .arg(file.path())
+        .assert()
+        .success()
+        .stdout("two\nlines\0three\0");
+    cargo_bin_cmd!()
+        .args(["--zero-terminated", "-n", "+3"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("three\0");
+    Ok(())
+}
+
 // --------------------------------------------------
 #[test]
 fn skips_bad_file() -> Result<()> {
@@ -784,6 +842,61 @@ fn multiple_files_n1_quiet() -> Result<()> {
     )
 }
 
+#[test]
+fn header_format() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-n", "1", "--header-format", "comment", ONE, TWO])
+        .assert()
+        .success()
+        .stdout(format!(
+            "# {ONE}\nÖne line, four wordś.\n# {TWO}\nFour words.\n"
+        ));
+    cargo_bin_cmd!()
+        .args(["-v", "-n", "1", "--header-format=json"])
+        .write_stdin("one\ntwo\n")
+        .assert()
+        .success()
+        .stdout("{\"file\":\"standard input\"}\ntwo\n");
+    Ok(())
+}
+
+#[test]
+fn verbose_single_file() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-v", "-n", "1", TWO])
+        .assert()
+        .success()
+        .stdout(format!("==> {TWO} <==\nFour words.\n"));
+    cargo_bin_cmd!()
+        .args(["--verbose", "-n", "1"])
+        .write_stdin("one\ntwo\n")
+        .assert()
+        .success()
+        .stdout("==> standard input <==\ntwo\n");
+    Ok(())
+}
+
+#[test]
+fn last_of_quiet_and_verbose_wins() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-v", "-q", "-n", "1", TWO])
+        .assert()
+        .success()
+        .stdout("Four words.\n");
+    run!(
+        "tests/expected/all.n1.q.out",
+        "-n",
+        "1",
+        "-v",
+        "-q",
+        TWELVE,
+        EMPTY,
+        ONE,
+        THREE,
+        TWO
+    )
+}
+
 #[test]
 fn multiple_files_n_minus_1() -> Result<()> {
     run!(
@@ -906,3 +1019,710 @@ fn multiple_files_c_plus_3() -> Result<()> {
         TWO
     )
 }
+
+// --------------------------------------------------
+#[test]
+fn max_scan_bytes_truncates() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-n", "3", "--max-scan-bytes", "10", TWELVE])
+        .assert()
+        .success()
+        .stdout("en\ntwelve\n")
+        .stderr(predicate::str::contains(
+            "tests/inputs/twelve.txt: warning: stopped scanning after 10 bytes",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn max_scan_bytes_not_reached() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-n", "2", "--max-scan-bytes", "1K", TWELVE])
+        .assert()
+        .success()
+        .stdout("eleven\ntwelve\n")
+        .stderr("");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn pre_filter() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-n", "2", "--pre-filter", "tr a-z A-Z", TWELVE])
+        .assert()
+        .success()
+        .stdout("ELEVEN\nTWELVE\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn pre_filter_bytes_from_start() -> Result<()> {
+    // Selection applies to the filtered output, not to the original file
+    cargo_bin_cmd!()
+        .args(["-c", "+3", "--pre-filter", "grep e$", TWELVE])
+        .assert()
+        .success()
+        .stdout("e\nthree\nfive\nnine\ntwelve\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn pre_filter_fails() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--pre-filter", "exit 3", ONE, TWO])
+        .assert()
+        .stderr(predicate::str::contains(
+            "tests/inputs/one.txt: pre-filter 'exit 3' failed: exit status: 3",
+        ))
+        .stderr(predicate::str::contains("tests/inputs/two.txt: pre-filter"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn state_file_resumes() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("app.log");
+    let state = dir.path().join("tailr.state");
+    let log_arg = log.to_str().unwrap();
+    let state_arg = state.to_str().unwrap();
+    let tail = |expected: &str| {
+        cargo_bin_cmd!()
+            .args(["-n", "2", "--state-file", state_arg, log_arg])
+            .assert()
+            .success()
+            .stdout(expected.to_string());
+    };
+
+    // The first run has nothing to go on from, so it is a plain tail
+    fs::write(&log, "one\ntwo\nthree\n")?;
+    tail("two\nthree\n");
+    tail("");
+
+    let mut fh = fs::OpenOptions::new().append(true).open(&log)?;
+    fh.write_all(b"four\nfive\nsix\n")?;
+    tail("four\nfive\nsix\n");
+
+    // A new file in place of the old one is printed from its start
+    fs::remove_file(&log)?;
+    fs::write(&log, "seven\neight\nnine\n")?;
+    tail("seven\neight\nnine\n");
+
+    // And so is a truncated one
+    fs::write(&log, "ten\n")?;
+    tail("ten\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_state_file() -> Result<()> {
+    let state = tempfile::NamedTempFile::new()?;
+    fs::write(state.path(), "garbage\n")?;
+    cargo_bin_cmd!()
+        .args(["--state-file", state.path().to_str().unwrap(), ONE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(":1: invalid state"));
+    Ok(())
+}
+
+/// A `tailr --follow` running in the background, killed when dropped.
+struct Follower {
+    child: std::process::Child,
+    stdout: std::sync::mpsc::Receiver<u8>,
+    stderr: std::sync::mpsc::Receiver<u8>,
+}
+
+/// Sends everything read from `pipe` to the returned channel, byte by byte.
+fn drain(mut pipe: impl Read + Send + 'static) -> std::sync::mpsc::Receiver<u8> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let mut buf = [0_u8; 1];
+        while let Ok(1) = pipe.read(&mut buf) {
+            if tx.send(buf[0]).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+impl Follower {
+    /// Runs tailr with `args`, which should include -f or -F, polling often
+    /// if it polls at all.
+    fn spawn(args: &[&str]) -> Result<Self> {
+        Self::spawn_sleeping("0.05", args)
+    }
+
+    fn spawn_sleeping(sleep_interval: &str, args: &[&str]) -> Result<Self> {
+        let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin!())
+            .args(["--sleep-interval", sleep_interval])
+            .args(args)
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()?;
+        let stdout = drain(child.stdout.take().unwrap());
+        let stderr = drain(child.stderr.take().unwrap());
+        Ok(Follower {
+            child,
+            stdout,
+            stderr,
+        })
+    }
+
+    /// Waits for exactly `expected` to be printed.
+    fn expect(&self, expected: &str) {
+        let mut got = vec![];
+        while got.len() < expected.len() {
+            match self.stdout.recv_timeout(std::time::Duration::from_secs(5)) {
+                Ok(byte) => got.push(byte),
+                Err(_) => break,
+            }
+        }
+        assert_eq!(String::from_utf8_lossy(&got), expected);
+    }
+
+    /// Waits for a diagnostic ending with `expected` on stderr, skipping
+    /// anything before it.
+    fn expect_err(&self, expected: &str) {
+        let mut got = vec![];
+        while !got.ends_with(expected.as_bytes()) {
+            match self.stderr.recv_timeout(std::time::Duration::from_secs(5)) {
+                Ok(byte) => got.push(byte),
+                Err(_) => break,
+            }
+        }
+        assert!(
+            got.ends_with(expected.as_bytes()),
+            "{expected:?} not in {:?}",
+            String::from_utf8_lossy(&got)
+        );
+    }
+}
+
+impl Follower {
+    /// Waits for tailr to exit by itself.
+    fn wait_exit(&mut self) -> Result<std::process::ExitStatus> {
+        for _ in 0..100 {
+            if let Some(status) = self.child.try_wait()? {
+                return Ok(status);
+            }
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+        anyhow::bail!("tailr is still running");
+    }
+}
+
+impl Drop for Follower {
+    fn drop(&mut self) {
+        _ = self.child.kill();
+        _ = self.child.wait();
+    }
+}
+
+fn append(path: &std::path::Path, data: &str) -> Result<()> {
+    let mut fh = fs::OpenOptions::new().append(true).open(path)?;
+    fh.write_all(data.as_bytes())?;
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_one_file() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("log");
+    fs::write(&log, "one\ntwo\nthree\n")?;
+
+    let follower = Follower::spawn(&["-f", "-n", "2", log.to_str().unwrap()])?;
+    follower.expect("two\nthree\n");
+    append(&log, "four\n")?;
+    follower.expect("four\n");
+    append(&log, "fi")?;
+    follower.expect("fi");
+    append(&log, "ve\n")?;
+    follower.expect("ve\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_multiple_files() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let a = dir.path().join("a");
+    let b = dir.path().join("b");
+    fs::write(&a, "a1\n")?;
+    fs::write(&b, "b1\n")?;
+    let (a_arg, b_arg) = (a.to_str().unwrap(), b.to_str().unwrap());
+
+    let follower = Follower::spawn(&["-f", a_arg, b_arg])?;
+    follower.expect(&format!("==> {a_arg} <==\na1\n\n==> {b_arg} <==\nb1\n"));
+
+    // No header while the output stays with the same file
+    append(&b, "b2\n")?;
+    follower.expect("b2\n");
+    append(&a, "a2\n")?;
+    follower.expect(&format!("\n==> {a_arg} <==\na2\n"));
+    append(&a, "a3\n")?;
+    follower.expect("a3\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_header_format() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let a = dir.path().join("a");
+    let b = dir.path().join("b");
+    fs::write(&a, "a1\n")?;
+    fs::write(&b, "b1\n")?;
+    let (a_arg, b_arg) = (a.to_str().unwrap(), b.to_str().unwrap());
+
+    let follower = Follower::spawn(&["-f", "--header-format", "comment", a_arg, b_arg])?;
+    follower.expect(&format!("# {a_arg}\na1\n# {b_arg}\nb1\n"));
+    append(&a, "a2\n")?;
+    follower.expect(&format!("# {a_arg}\na2\n"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_quiet() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let a = dir.path().join("a");
+    let b = dir.path().join("b");
+    fs::write(&a, "a1\n")?;
+    fs::write(&b, "b1\n")?;
+
+    let follower = Follower::spawn(&["-f", "-q", a.to_str().unwrap(), b.to_str().unwrap()])?;
+    follower.expect("a1\nb1\n");
+    append(&a, "a2\n")?;
+    follower.expect("a2\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_skips_bad_file() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("log");
+    fs::write(&log, "one\n")?;
+    let bad = gen_bad_file();
+
+    let follower = Follower::spawn(&["-f", &bad, log.to_str().unwrap()])?;
+    follower.expect(&format!("==> {} <==\none\n", log.display()));
+    append(&log, "two\n")?;
+    follower.expect("two\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_follow_no_files_remaining() -> Result<()> {
+    let bad = gen_bad_file();
+    cargo_bin_cmd!()
+        .args(["-f", &bad])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no files remaining"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_sleep_interval_without_follow() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-s", "2", ONE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--follow"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_sleep_interval() -> Result<()> {
+    for bad in ["-1", "soon"] {
+        cargo_bin_cmd!()
+            .args(["-f", &format!("--sleep-interval={bad}"), ONE])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(format!(
+                "invalid number of seconds: '{bad}'"
+            )));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_follow_and_state_file() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-f", "--state-file", "state", ONE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_truncated() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("log");
+    fs::write(&log, "one\ntwo\nthree\n")?;
+    let log_arg = log.to_str().unwrap();
+
+    let follower = Follower::spawn(&["-f", "-n", "1", log_arg])?;
+    follower.expect("three\n");
+    fs::write(&log, "new\n")?;
+    follower.expect_err(&format!("{log_arg}: file truncated\n"));
+    follower.expect("new\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_name_replaced() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("log");
+    let rotated = dir.path().join("log.new");
+    fs::write(&log, "one\n")?;
+    let log_arg = log.to_str().unwrap();
+
+    let follower = Follower::spawn(&["-F", log_arg])?;
+    follower.expect("one\n");
+    append(&log, "two\n")?;
+    follower.expect("two\n");
+
+    // Rotation by renaming another file over it
+    fs::write(&rotated, "three\n")?;
+    fs::rename(&rotated, &log)?;
+    follower.expect_err(&format!(
+        "'{log_arg}' has been replaced;  following new file\n"
+    ));
+    follower.expect("three\n");
+    append(&log, "four\n")?;
+    follower.expect("four\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_name_max_unchanged_stats() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("log");
+    let rotated = dir.path().join("log.new");
+    fs::write(&log, "one\n")?;
+    let log_arg = log.to_str().unwrap();
+
+    let follower =
+        Follower::spawn(&["-F", "--use-polling", "--max-unchanged-stats", "3", log_arg])?;
+    follower.expect("one\n");
+    fs::write(&rotated, "two\n")?;
+    fs::rename(&rotated, &log)?;
+    follower.expect_err(&format!(
+        "'{log_arg}' has been replaced;  following new file\n"
+    ));
+    follower.expect("two\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_max_unchanged_stats_without_follow_name() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-f", "--max-unchanged-stats", "3", ONE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("-F"));
+    cargo_bin_cmd!()
+        .args(["-F", "--max-unchanged-stats", "0", ONE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "invalid value '0' for '--max-unchanged-stats <N>'",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+/// A directory with ‘a.log’, ‘b.log’, ‘c.txt’ and ‘old.log’.
+fn log_dir() -> Result<tempfile::TempDir> {
+    let dir = tempfile::tempdir()?;
+    for name in ["a.log", "b.log", "c.txt", "old.log"] {
+        fs::write(dir.path().join(name), format!("{name} 1\n{name} 2\n"))?;
+    }
+    Ok(dir)
+}
+
+// --------------------------------------------------
+#[test]
+fn glob() -> Result<()> {
+    let dir = log_dir()?;
+    let dir_arg = dir.path().to_str().unwrap();
+    cargo_bin_cmd!()
+        .args(["-n", "1", "--glob", "*.log", "--exclude", "old.*", dir_arg])
+        .assert()
+        .success()
+        .stdout(format!(
+            "==> {dir_arg}/a.log <==\na.log 2\n\n==> {dir_arg}/b.log <==\nb.log 2\n"
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_glob() -> Result<()> {
+    let dir = log_dir()?;
+    let dir_arg = dir.path().to_str().unwrap();
+    let follower = Follower::spawn(&["-f", "-n", "1", "--glob", "a*", "--glob", "new.*", dir_arg])?;
+    follower.expect(&format!("==> {dir_arg}/a.log <==\na.log 2\n"));
+
+    let new = dir.path().join("new.log");
+    fs::write(&new, "new 1\n")?;
+    follower.expect_err(&format!(
+        "'{dir_arg}/new.log' has appeared;  following new file\n"
+    ));
+    follower.expect(&format!("\n==> {dir_arg}/new.log <==\nnew 1\n"));
+
+    fs::remove_file(dir.path().join("a.log"))?;
+    follower.expect_err(&format!(
+        "'{dir_arg}/a.log' has been removed;  no longer following\n"
+    ));
+    append(&new, "new 2\n")?;
+    follower.expect("new 2\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_exclude_without_glob() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--exclude", "*.gz", ONE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--glob"));
+    cargo_bin_cmd!()
+        .args(["--glob", "[", ONE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("'['"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_name_inaccessible() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("log");
+    fs::write(&log, "one\n")?;
+    let log_arg = log.to_str().unwrap();
+
+    let follower = Follower::spawn(&["-F", log_arg])?;
+    follower.expect("one\n");
+    fs::remove_file(&log)?;
+    follower.expect_err(&format!(
+        "'{log_arg}' has become inaccessible: No such file or directory (os error 2)\n"
+    ));
+    fs::write(&log, "two\n")?;
+    follower.expect_err(&format!("'{log_arg}' has appeared;  following new file\n"));
+    follower.expect("two\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_name_appears_with_header() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let a = dir.path().join("a");
+    let b = dir.path().join("b");
+    fs::write(&a, "a1\n")?;
+    let (a_arg, b_arg) = (a.to_str().unwrap(), b.to_str().unwrap());
+
+    let follower = Follower::spawn(&["-F", a_arg, b_arg])?;
+    follower.expect_err(&format!(
+        "{b_arg}: No such file or directory (os error 2)\n"
+    ));
+    follower.expect(&format!("==> {a_arg} <==\na1\n"));
+    fs::write(&b, "b1\n")?;
+    follower.expect_err(&format!("'{b_arg}' has appeared;  following new file\n"));
+    follower.expect(&format!("\n==> {b_arg} <==\nb1\n"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_retry() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("log");
+    let log_arg = log.to_str().unwrap();
+
+    let follower = Follower::spawn(&["-f", "--retry", log_arg])?;
+    follower.expect_err(&format!(
+        "{log_arg}: No such file or directory (os error 2)\n"
+    ));
+    fs::write(&log, "one\n")?;
+    follower.expect_err(&format!("'{log_arg}' has appeared;  following new file\n"));
+    follower.expect("one\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn retry_without_follow() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--retry", ONE])
+        .assert()
+        .success()
+        .stdout("Öne line, four wordś.\n")
+        .stderr("warning: --retry ignored; --retry is useful only when following\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_notified() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("log");
+    fs::write(&log, "one\n")?;
+
+    // Polling would take far longer than the timeout
+    let follower = Follower::spawn_sleeping("600", &["-F", log.to_str().unwrap()])?;
+    follower.expect("one\n");
+    append(&log, "two\n")?;
+    follower.expect("two\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_use_polling() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("log");
+    fs::write(&log, "one\n")?;
+
+    let follower = Follower::spawn(&["-f", "--use-polling", log.to_str().unwrap()])?;
+    follower.expect("one\n");
+    append(&log, "two\n")?;
+    follower.expect("two\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_use_polling_without_follow() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--use-polling", ONE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--follow"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn stdin_dash() -> Result<()> {
+    let input = fs::read_to_string(TWELVE)?;
+    for args in [
+        ["-n", "3"],
+        ["-n", "+11"],
+        ["-n", "0"],
+        ["-c", "5"],
+        ["-c", "+40"],
+    ] {
+        let expected = cargo_bin_cmd!().args(args).arg(TWELVE).output()?.stdout;
+        cargo_bin_cmd!()
+            .args(args)
+            .arg("-")
+            .write_stdin(input.clone())
+            .assert()
+            .success()
+            .stdout(expected);
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn stdin_header() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-n", "1", "-", ONE])
+        .write_stdin("a\nb\n")
+        .assert()
+        .success()
+        .stdout(
+            "==> standard input <==\nb\n\n==> tests/inputs/one.txt <==\nÖne line, four wordś.\n",
+        );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_stdin_ends() -> Result<()> {
+    // There is nothing to follow in a pipe once it is closed
+    cargo_bin_cmd!()
+        .args(["-f", "-n", "1"])
+        .write_stdin("a\nb\n")
+        .timeout(std::time::Duration::from_secs(5))
+        .assert()
+        .success()
+        .stdout("b\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_pid() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("log");
+    fs::write(&log, "one\n")?;
+
+    let mut writer = std::process::Command::new("sleep").arg("1").spawn()?;
+    let pid = writer.id().to_string();
+    let mut follower = Follower::spawn(&["-F", "--pid", &pid, log.to_str().unwrap()])?;
+    follower.expect("one\n");
+    append(&log, "two\n")?;
+    follower.expect("two\n");
+
+    writer.wait()?;
+    assert!(follower.wait_exit()?.success());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_pid_already_gone() -> Result<()> {
+    let mut gone = std::process::Command::new("true").spawn()?;
+    gone.wait()?;
+    let pid = gone.id().to_string();
+
+    let mut follower = Follower::spawn(&["-f", "--use-polling", "--pid", &pid, ONE])?;
+    follower.expect("Öne line, four wordś.\n");
+    assert!(follower.wait_exit()?.success());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_pid() -> Result<()> {
+    for bad in ["0", "-5", "me"] {
+        cargo_bin_cmd!()
+            .args(["-f", &format!("--pid={bad}"), ONE])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(format!(
+                "invalid value '{bad}' for '--pid <PID>'"
+            )));
+    }
+    cargo_bin_cmd!()
+        .args(["--pid", "1", ONE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--follow"));
+    Ok(())
+}
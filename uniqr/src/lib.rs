@@ -0,0 +1,642 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet, VecDeque},
+    ffi::OsString,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    num::NonZeroUsize,
+};
+
+use anyhow::{Result, anyhow};
+
+use clap::{CommandFactory, Parser, ValueEnum, builder::NonEmptyStringValueParser};
+
+// As in GNU uniq
+const COUNT_FIELD_WIDTH: usize = 7;
+
+/// ‘uniq’ in Rust - omit repeated lines
+#[derive(Debug, Parser)]
+#[command(author, version, about)]
+struct Args {
+    #[arg(value_name("INPUT"), default_value = "-")]
+    in_file: String,
+
+    #[arg(value_name("OUTPUT"))]
+    out_file: Option<String>,
+
+    /// prefix lines by the number of occurences
+    #[arg(short, long)]
+    count: bool,
+
+    /// ignore trailing whitespace when comparing lines
+    #[arg(long)]
+    ignore_trailing_space: bool,
+
+    /// treat all lines consisting only of whitespace as equal
+    #[arg(long)]
+    blank_lines_equal: bool,
+
+    /// avoid comparing the first N fields
+    #[arg(short = 'f', long, value_name = "N", default_value_t = 0)]
+    skip_fields: usize,
+
+    /// avoid comparing the first N characters, after any skipped fields
+    #[arg(short = 's', long, value_name = "N", default_value_t = 0)]
+    skip_chars: usize,
+
+    /// compare no more than N characters of what is left
+    #[arg(short = 'w', long, value_name = "N")]
+    check_chars: Option<usize>,
+
+    /// ignore differences in case when comparing lines
+    #[arg(short, long)]
+    ignore_case: bool,
+
+    /// line delimiter is NUL, not newline
+    #[arg(short, long)]
+    zero_terminated: bool,
+
+    /// only print duplicate lines, one for each group
+    #[arg(short = 'd', long)]
+    repeated: bool,
+
+    /// only print unique lines
+    #[arg(short, long)]
+    unique: bool,
+
+    /// print all duplicate lines, delimiting groups as METHOD says
+    #[arg(
+        short = 'D',
+        long,
+        value_name = "METHOD",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "none",
+        conflicts_with_all = ["count", "unique"],
+    )]
+    all_repeated: Option<Delimit>,
+
+    /// print all lines, separating groups with an empty line as METHOD says
+    #[arg(
+        long,
+        value_name = "METHOD",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "separate",
+        conflicts_with_all = ["count", "repeated", "unique", "all_repeated"],
+    )]
+    group: Option<GroupMethod>,
+
+    /// also omit lines equal to any of the previous N lines, not just to the
+    /// adjacent one
+    #[arg(
+        long,
+        value_name = "N",
+        conflicts_with_all = ["count", "repeated", "unique", "all_repeated", "group"],
+    )]
+    within: Option<NonZeroUsize>,
+
+    /// omit lines equal to any other line of the input, adjacent or not;
+    /// every distinct line is kept in memory
+    #[arg(
+        long,
+        conflicts_with_all = ["count", "repeated", "unique", "all_repeated", "group", "within"],
+    )]
+    global: bool,
+
+    /// which of the equal lines --global prints, in the order of the input
+    #[arg(long, value_name = "WHICH", value_enum, default_value_t = Keep::First, requires = "global")]
+    keep: Keep,
+
+    /// edit INPUT in place, instead of printing to OUTPUT; with SUFFIX, keep
+    /// the original as INPUT followed by SUFFIX
+    #[arg(
+        long,
+        value_name = "SUFFIX",
+        num_args = 0..=1,
+        require_equals = true,
+        value_parser = NonEmptyStringValueParser::new(),
+        conflicts_with = "out_file"
+    )]
+    in_place: Option<Option<String>>,
+}
+
+/// Which of the equal lines ‘--global’ keeps.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Keep {
+    /// The first occurrence
+    First,
+    /// The last occurrence
+    Last,
+}
+
+/// How ‘--all-repeated’ separates groups of duplicate lines.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Delimit {
+    /// Don't delimit groups
+    None,
+    /// Print an empty line before each group
+    Prepend,
+    /// Print an empty line between groups
+    Separate,
+}
+
+/// Where ‘--group’ puts the empty lines around groups.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum GroupMethod {
+    /// Between groups
+    Separate,
+    /// Before each group
+    Prepend,
+    /// After each group
+    Append,
+    /// Before and after each group
+    Both,
+}
+
+/// Builds the part of a line that is used to decide whether adjacent lines
+/// are duplicates; the first line of each group is still printed unchanged.
+#[derive(Debug, Default, Clone, Copy)]
+struct KeyOptions {
+    ignore_trailing_space: bool,
+    blank_lines_equal: bool,
+    skip_fields: usize,
+    skip_chars: usize,
+    check_chars: Option<usize>,
+    ignore_case: bool,
+    /// Lines end with a newline, which a carriage return may precede
+    crlf: bool,
+}
+
+impl KeyOptions {
+    fn from_args(args: &Args) -> Self {
+        KeyOptions {
+            ignore_trailing_space: args.ignore_trailing_space,
+            blank_lines_equal: args.blank_lines_equal,
+            skip_fields: args.skip_fields,
+            skip_chars: args.skip_chars,
+            check_chars: args.check_chars,
+            ignore_case: args.ignore_case,
+            crlf: !args.zero_terminated,
+        }
+    }
+
+    fn key<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        let line = match self.crlf {
+            true => line.strip_suffix('\r').unwrap_or(line),
+            false => line,
+        };
+        if self.blank_lines_equal && line.trim().is_empty() {
+            return Cow::Borrowed("");
+        }
+        let mut key =
+            &line[learnr::key_start(line.as_bytes(), self.skip_fields, self.skip_chars)..];
+        if self.ignore_trailing_space {
+            key = key.trim_end();
+        }
+        if let Some(n) = self.check_chars
+            && let Some((end, _)) = key.char_indices().nth(n)
+        {
+            key = &key[..end];
+        }
+        if self.ignore_case {
+            Cow::Owned(key.to_lowercase())
+        } else {
+            Cow::Borrowed(key)
+        }
+    }
+}
+
+/// The command line that [`main`] parses, for generating the man page.
+pub fn command() -> clap::Command {
+    Args::command()
+}
+
+/// What the man page tells beyond --help.
+pub const MAN_DESCRIPTION: &str = "\
+Only adjacent lines are compared, so input is usually sorted first. INPUT \
+defaults to standard input and OUTPUT to standard output.\n\n\
+A field is a run of blanks followed by non-blanks. With --skip-fields, \
+--skip-chars and --check-chars only part of each line is compared, but the \
+first line of every group is printed whole.\n\n\
+With --in-place the result goes to a new file next to INPUT, which replaces \
+INPUT only once it is complete and on disk, so INPUT is left as it was if \
+anything goes wrong. -i is --ignore-case, as in GNU uniq, so this option has \
+no short form.";
+
+pub fn main(args: impl IntoIterator<Item = OsString>) -> Result<()> {
+    run(Args::parse_from(args))
+}
+
+fn write_line(
+    out: &mut dyn std::io::Write,
+    line: &str,
+    count: usize,
+    show_count: bool,
+    eol: u8,
+) -> Result<()> {
+    if show_count {
+        write!(out, "{count:>width$} {line}", width = COUNT_FIELD_WIDTH)?;
+    } else {
+        out.write_all(line.as_bytes())?;
+    }
+    out.write_all(&[eol])?;
+    Ok(())
+}
+
+/// Splits the input into records ending with `eol`, which isn't part of them.
+/// A carriage return before a newline is kept, so that it is printed again;
+/// only [`KeyOptions::key`] leaves it out.
+fn records(file: Box<dyn BufRead>, eol: u8) -> impl Iterator<Item = Result<String>> {
+    file.split(eol)
+        .map(|record| Ok(String::from_utf8(record?)?))
+}
+
+fn run(args: Args) -> Result<()> {
+    let file = open_input_file(&args.in_file).map_err(|err| anyhow!("{}: {err}", args.in_file))?;
+    let Some(suffix) = &args.in_place else {
+        let mut out = open_output_file(&args.out_file)?;
+        return uniq(&args, file, out.as_mut());
+    };
+
+    if args.in_file == "-" {
+        return Err(anyhow!(
+            "--in-place needs an INPUT file, not standard input"
+        ));
+    }
+    let failed = |err| anyhow!("{}: {err}", args.in_file);
+    let mut out = BufWriter::new(learnr::AtomicFile::create(&args.in_file).map_err(failed)?);
+    // The input is only replaced once all of it was read and written
+    uniq(&args, file, &mut out)?;
+    let replacement = out.into_inner().map_err(|err| failed(err.into_error()))?;
+    replacement.commit(suffix.as_deref()).map_err(failed)
+}
+
+fn uniq(args: &Args, file: Box<dyn BufRead>, out: &mut dyn Write) -> Result<()> {
+    let keys = KeyOptions::from_args(args);
+    let eol = if args.zero_terminated { b'\0' } else { b'\n' };
+    if let Some(window) = args.within {
+        return dedup_within(file, out, keys, window, eol);
+    }
+    if args.global {
+        return dedup_global(file, out, keys, args.keep, eol);
+    }
+
+    let mut emitter: Box<dyn Emitter> = match (args.all_repeated, args.group) {
+        (Some(delimit), _) => Box::new(AllRepeated {
+            delimit,
+            eol,
+            groups: 0,
+        }),
+        (None, Some(method)) => Box::new(Grouped {
+            method,
+            eol,
+            groups: 0,
+        }),
+        (None, None) => Box::new(Uniq {
+            show_count: args.count,
+            repeated: args.repeated,
+            unique: args.unique,
+            eol,
+        }),
+    };
+
+    // The first line of the group of equal lines being read, and its size
+    let mut group: Option<(String, usize)> = None;
+
+    for line_result in records(file, eol) {
+        let line = line_result?;
+
+        if let Some((first, count)) = &mut group
+            && keys.key(first) == keys.key(&line)
+        {
+            *count += 1;
+            emitter.repeat(out, first, &line, *count)?;
+            continue;
+        }
+        if let Some((first, count)) = group.take() {
+            emitter.finish(out, &first, count)?;
+        }
+        emitter.start(out, &line)?;
+        group = Some((line, 1));
+    }
+
+    if let Some((first, count)) = group {
+        emitter.finish(out, &first, count)?;
+    }
+    emitter.done(out)?;
+
+    // Dropping the writer would silently lose a failed final write
+    out.flush()?;
+    Ok(())
+}
+
+/// Decides what is printed for the groups of equal adjacent lines. The
+/// input loop reports each line either as the start of a new group or as a
+/// repeat within the current one, and says when a group has ended.
+trait Emitter {
+    /// `line` starts a new group
+    fn start(&mut self, _out: &mut dyn Write, _line: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// `line` is the `count`th one of the group that starts with `first`
+    fn repeat(
+        &mut self,
+        _out: &mut dyn Write,
+        _first: &str,
+        _line: &str,
+        _count: usize,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// The group that starts with `first` ended after `count` lines
+    fn finish(&mut self, _out: &mut dyn Write, _first: &str, _count: usize) -> Result<()> {
+        Ok(())
+    }
+
+    /// There is no more input
+    fn done(&mut self, _out: &mut dyn Write) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// One line per group, optionally only the unique or the repeated ones.
+struct Uniq {
+    show_count: bool,
+    repeated: bool,
+    unique: bool,
+    eol: u8,
+}
+
+impl Emitter for Uniq {
+    fn finish(&mut self, out: &mut dyn Write, first: &str, count: usize) -> Result<()> {
+        let is_repeated = count > 1;
+        if (self.repeated && !is_repeated) || (self.unique && is_repeated) {
+            return Ok(());
+        }
+        write_line(out, first, count, self.show_count, self.eol)
+    }
+}
+
+/// ‘--all-repeated’: every line of the groups with more than one line.
+struct AllRepeated {
+    delimit: Delimit,
+    eol: u8,
+    /// Groups printed so far
+    groups: usize,
+}
+
+impl Emitter for AllRepeated {
+    fn repeat(&mut self, out: &mut dyn Write, first: &str, line: &str, count: usize) -> Result<()> {
+        // Only now is it clear that the group is printed, so the first line
+        // had to wait; later ones can go out as they come
+        if count == 2 {
+            if self.delimit == Delimit::Prepend
+                || (self.delimit == Delimit::Separate && self.groups > 0)
+            {
+                out.write_all(&[self.eol])?;
+            }
+            self.groups += 1;
+            write_line(out, first, 1, false, self.eol)?;
+        }
+        write_line(out, line, 1, false, self.eol)
+    }
+}
+
+/// ‘--group’: every line, with empty lines around the groups.
+struct Grouped {
+    method: GroupMethod,
+    eol: u8,
+    /// Groups started so far
+    groups: usize,
+}
+
+impl Emitter for Grouped {
+    fn start(&mut self, out: &mut dyn Write, line: &str) -> Result<()> {
+        // Between two groups there is never more than one empty line
+        if self.groups > 0 || matches!(self.method, GroupMethod::Prepend | GroupMethod::Both) {
+            out.write_all(&[self.eol])?;
+        }
+        self.groups += 1;
+        write_line(out, line, 1, false, self.eol)
+    }
+
+    fn repeat(
+        &mut self,
+        out: &mut dyn Write,
+        _first: &str,
+        line: &str,
+        _count: usize,
+    ) -> Result<()> {
+        write_line(out, line, 1, false, self.eol)
+    }
+
+    fn done(&mut self, out: &mut dyn Write) -> Result<()> {
+        if self.groups > 0 && matches!(self.method, GroupMethod::Append | GroupMethod::Both) {
+            out.write_all(&[self.eol])?;
+        }
+        Ok(())
+    }
+}
+
+/// Prints each line whose key isn't among the keys of the `window` lines
+/// before it, counting the omitted lines too. Only the window is kept in
+/// memory: its keys in order, plus how often each of them occurs.
+fn dedup_within(
+    file: Box<dyn BufRead>,
+    out: &mut dyn Write,
+    keys: KeyOptions,
+    window: NonZeroUsize,
+    eol: u8,
+) -> Result<()> {
+    let mut recent: VecDeque<String> = VecDeque::with_capacity(window.get());
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    for line_result in records(file, eol) {
+        let line = line_result?;
+        let key = keys.key(&line).into_owned();
+
+        if !seen.contains_key(&key) {
+            write_line(out, &line, 1, false, eol)?;
+        }
+
+        *seen.entry(key.clone()).or_default() += 1;
+        recent.push_back(key);
+        if recent.len() > window.get()
+            && let Some(old) = recent.pop_front()
+            && let Some(n) = seen.get_mut(&old)
+        {
+            *n -= 1;
+            if *n == 0 {
+                seen.remove(&old);
+            }
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Prints one line for each distinct key, where it first or last occurs as
+/// `keep` says, keeping the order of the input.
+///
+/// Keeping the first line streams: each line is printed as soon as its key
+/// turns out to be new, and only the keys seen so far are held. Keeping the
+/// last one has to wait for the end of the input, and holds the latest line
+/// for each key along with its position; either way memory grows with the
+/// number of distinct lines, not with the length of the input.
+fn dedup_global(
+    file: Box<dyn BufRead>,
+    out: &mut dyn Write,
+    keys: KeyOptions,
+    keep: Keep,
+    eol: u8,
+) -> Result<()> {
+    match keep {
+        Keep::First => {
+            let mut seen: HashSet<String> = HashSet::new();
+            for line_result in records(file, eol) {
+                let line = line_result?;
+                if seen.insert(keys.key(&line).into_owned()) {
+                    write_line(out, &line, 1, false, eol)?;
+                }
+            }
+        }
+        Keep::Last => {
+            let mut latest: HashMap<String, (usize, String)> = HashMap::new();
+            for (idx, line_result) in records(file, eol).enumerate() {
+                let line = line_result?;
+                latest.insert(keys.key(&line).into_owned(), (idx, line));
+            }
+            let mut lines: Vec<_> = latest.into_values().collect();
+            lines.sort_unstable_by_key(|(idx, _)| *idx);
+            for (_, line) in lines {
+                write_line(out, &line, 1, false, eol)?;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn open_output_file(out_file: &Option<String>) -> Result<Box<dyn Write>> {
+    match out_file {
+        Some(filename) => Ok(Box::new(BufWriter::new(
+            File::create(filename).map_err(|err| anyhow!("{filename}: {err}"))?,
+        ))),
+        None => Ok(Box::new(BufWriter::new(std::io::stdout()))),
+    }
+}
+
+fn open_input_file(filename: &str) -> Result<Box<dyn BufRead>> {
+    match filename {
+        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
+        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key() {
+        let keys = KeyOptions::default();
+        assert_eq!(keys.key("  a b  "), "  a b  ");
+
+        let keys = KeyOptions {
+            skip_fields: 1,
+            ..KeyOptions::default()
+        };
+        assert_eq!(keys.key("10:00 start"), " start");
+        assert_eq!(keys.key("10:00"), "");
+
+        let keys = KeyOptions {
+            skip_fields: 1,
+            skip_chars: 1,
+            check_chars: Some(3),
+            ..KeyOptions::default()
+        };
+        assert_eq!(keys.key("10:00 started"), "sta");
+        assert_eq!(keys.key("10:00 st"), "st");
+
+        // Characters, not bytes
+        let keys = KeyOptions {
+            skip_chars: 1,
+            check_chars: Some(2),
+            ..KeyOptions::default()
+        };
+        assert_eq!(keys.key("éété"), "ét");
+
+        // A carriage return ending a line is part of its newline
+        let keys = KeyOptions {
+            crlf: true,
+            ..KeyOptions::default()
+        };
+        assert_eq!(keys.key("a\r"), "a");
+        assert_eq!(keys.key("a\rb"), "a\rb");
+        assert_eq!(KeyOptions::default().key("a\r"), "a\r");
+    }
+
+    #[test]
+    fn test_key_whitespace() {
+        let keys = KeyOptions {
+            ignore_trailing_space: true,
+            check_chars: Some(4),
+            ..KeyOptions::default()
+        };
+        assert_eq!(keys.key("ab  \t"), "ab");
+
+        let keys = KeyOptions {
+            blank_lines_equal: true,
+            skip_chars: 1,
+            ..KeyOptions::default()
+        };
+        assert_eq!(keys.key(" \t "), "");
+        assert_eq!(keys.key(" x "), "x ");
+    }
+
+    #[test]
+    fn test_key_ignore_case() {
+        let keys = KeyOptions {
+            ignore_case: true,
+            skip_chars: 1,
+            check_chars: Some(3),
+            ..KeyOptions::default()
+        };
+        assert_eq!(keys.key("xÉCOLE"), "éco");
+        assert_eq!(keys.key("XÉcole"), keys.key("yéCOle"));
+    }
+
+    fn global(input: &str, keys: KeyOptions, keep: Keep) -> String {
+        let file: Box<dyn BufRead> = Box::new(io::Cursor::new(input.to_string()));
+        let mut out = Vec::new();
+        dedup_global(file, &mut out, keys, keep, b'\n').unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_dedup_global() {
+        let input = "b\na\nb\nc\na\n";
+        assert_eq!(
+            global(input, KeyOptions::default(), Keep::First),
+            "b\na\nc\n"
+        );
+        assert_eq!(
+            global(input, KeyOptions::default(), Keep::Last),
+            "b\nc\na\n"
+        );
+        assert_eq!(global("", KeyOptions::default(), Keep::Last), "");
+
+        // The line that is kept is printed as it was, not its key
+        let keys = KeyOptions {
+            ignore_case: true,
+            ..KeyOptions::default()
+        };
+        assert_eq!(global("A\nb\na\n", keys, Keep::First), "A\nb\n");
+        assert_eq!(global("A\nb\na\n", keys, Keep::Last), "b\na\n");
+    }
+}
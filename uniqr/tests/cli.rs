@@ -6,6 +6,13 @@ use rand::{Rng, distributions::Alphanumeric};
 use std::fs;
 use tempfile::NamedTempFile;
 
+const SPACES: &str = "tests/inputs/spaces.txt";
+const INTERLEAVED: &str = "tests/inputs/interleaved.txt";
+const GROUPS: &str = "tests/inputs/groups.txt";
+const LOG: &str = "tests/inputs/log.txt";
+const CASE: &str = "tests/inputs/case.txt";
+const ZERO: &str = "tests/inputs/zero.txt";
+
 struct Test {
     input: &'static str,
     out: &'static str,
@@ -609,3 +616,430 @@ fn t6_outfile_count() -> Result<()> {
 fn t6_stdin_outfile_count() -> Result<()> {
     run_stdin_outfile_count(&T6)
 }
+
+// --------------------------------------------------
+fn run_args(input: &str, args: &[&str], expected_file: &str) -> Result<()> {
+    let expected = fs::read_to_string(expected_file)?;
+    cargo_bin_cmd!()
+        .args(args)
+        .arg(input)
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn spaces() -> Result<()> {
+    run_args(SPACES, &[], "tests/expected/spaces.txt.out")
+}
+
+#[test]
+fn spaces_ignore_trailing_space() -> Result<()> {
+    run_args(
+        SPACES,
+        &["-c", "--ignore-trailing-space"],
+        "tests/expected/spaces.txt.trailing.c.out",
+    )
+}
+
+#[test]
+fn spaces_blank_lines_equal() -> Result<()> {
+    run_args(
+        SPACES,
+        &["-c", "--blank-lines-equal"],
+        "tests/expected/spaces.txt.blank.c.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn interleaved_within_1() -> Result<()> {
+    // A window of one line is plain uniq
+    run_args(
+        INTERLEAVED,
+        &["--within", "1"],
+        "tests/expected/interleaved.txt.within1.out",
+    )
+}
+
+#[test]
+fn interleaved_within_2() -> Result<()> {
+    run_args(
+        INTERLEAVED,
+        &["--within", "2"],
+        "tests/expected/interleaved.txt.within2.out",
+    )
+}
+
+#[test]
+fn interleaved_within_10() -> Result<()> {
+    run_args(
+        INTERLEAVED,
+        &["--within=10"],
+        "tests/expected/interleaved.txt.within10.out",
+    )
+}
+
+#[test]
+fn dies_within_zero() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--within", "0", INTERLEAVED])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "invalid value '0' for '--within <N>'",
+        ));
+    Ok(())
+}
+
+#[test]
+fn dies_within_and_count() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--within", "2", "-c", INTERLEAVED])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_outfile() -> Result<()> {
+    let bad = format!("{}/out.txt", gen_bad_file());
+    cargo_bin_cmd!()
+        .args([ONE.input, &bad])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "{bad}: No such file or directory"
+        )));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn groups_repeated() -> Result<()> {
+    run_args(GROUPS, &["-d"], "tests/expected/groups.txt.d.out")?;
+    run_args(
+        GROUPS,
+        &["--repeated", "-c"],
+        "tests/expected/groups.txt.d.c.out",
+    )
+}
+
+#[test]
+fn groups_unique() -> Result<()> {
+    run_args(GROUPS, &["-u"], "tests/expected/groups.txt.u.out")?;
+    // Every group is either unique or repeated
+    run_args(GROUPS, &["-u", "-d"], "tests/expected/empty.txt.out")
+}
+
+#[test]
+fn groups_all_repeated() -> Result<()> {
+    run_args(GROUPS, &["-D"], "tests/expected/groups.txt.D.out")?;
+    run_args(
+        GROUPS,
+        &["--all-repeated"],
+        "tests/expected/groups.txt.D.out",
+    )?;
+    run_args(
+        GROUPS,
+        &["--all-repeated=none"],
+        "tests/expected/groups.txt.D.out",
+    )
+}
+
+#[test]
+fn groups_all_repeated_delimited() -> Result<()> {
+    run_args(
+        GROUPS,
+        &["--all-repeated=prepend"],
+        "tests/expected/groups.txt.D-prepend.out",
+    )?;
+    run_args(
+        GROUPS,
+        &["--all-repeated=separate"],
+        "tests/expected/groups.txt.D-separate.out",
+    )
+}
+
+#[test]
+fn dies_all_repeated_and_count() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-D", "-c", GROUPS])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn log_skip_fields() -> Result<()> {
+    run_args(LOG, &["-f", "1"], "tests/expected/log.txt.f1.out")
+}
+
+#[test]
+fn log_skip_fields_check_chars_count() -> Result<()> {
+    run_args(
+        LOG,
+        &["--skip-fields=1", "--check-chars=6", "-c"],
+        "tests/expected/log.txt.f1.w6.c.out",
+    )
+}
+
+#[test]
+fn log_skip_chars_check_chars() -> Result<()> {
+    run_args(
+        LOG,
+        &["-s", "6", "-w", "3"],
+        "tests/expected/log.txt.s6.w3.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn case_ignore_case_count() -> Result<()> {
+    run_args(CASE, &["-i", "-c"], "tests/expected/case.txt.i.c.out")?;
+    run_args(
+        CASE,
+        &["--ignore-case", "--count"],
+        "tests/expected/case.txt.i.c.out",
+    )
+}
+
+#[test]
+fn zero_terminated() -> Result<()> {
+    // Newlines are just characters within the records
+    run_args(ZERO, &["-z"], "tests/expected/zero.txt.z.out")?;
+    run_args(
+        ZERO,
+        &["--zero-terminated", "-c"],
+        "tests/expected/zero.txt.z.c.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn groups_group() -> Result<()> {
+    run_args(
+        GROUPS,
+        &["--group"],
+        "tests/expected/groups.txt.group-separate.out",
+    )?;
+    for method in ["separate", "prepend", "append", "both"] {
+        run_args(
+            GROUPS,
+            &[&format!("--group={method}")],
+            &format!("tests/expected/groups.txt.group-{method}.out"),
+        )?;
+    }
+    Ok(())
+}
+
+#[test]
+fn empty_group_both() -> Result<()> {
+    run_args(EMPTY.input, &["--group=both"], EMPTY.out)
+}
+
+#[test]
+fn dies_group_and_count() -> Result<()> {
+    for flag in ["-c", "-d", "-u", "-D"] {
+        cargo_bin_cmd!()
+            .args(["--group", flag, GROUPS])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("cannot be used with"));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn interleaved_global() -> Result<()> {
+    run_args(
+        INTERLEAVED,
+        &["--global"],
+        "tests/expected/interleaved.txt.global.out",
+    )?;
+    run_args(
+        INTERLEAVED,
+        &["--global", "--keep", "first"],
+        "tests/expected/interleaved.txt.global.out",
+    )
+}
+
+#[test]
+fn interleaved_global_keep_last() -> Result<()> {
+    run_args(
+        INTERLEAVED,
+        &["--global", "--keep=last"],
+        "tests/expected/interleaved.txt.global-last.out",
+    )
+}
+
+/// 200,000 lines with 1,000 distinct values: counting up in the first half
+/// and down in the second, so that the first and the last occurrences come
+/// in opposite orders.
+fn large_input() -> String {
+    (0..200_000)
+        .map(|i| {
+            let value = if i < 100_000 {
+                i % 1000
+            } else {
+                999 - i % 1000
+            };
+            format!("value {value}\n")
+        })
+        .collect()
+}
+
+#[test]
+fn large_input_global() -> Result<()> {
+    let expected: String = (0..1000).map(|value| format!("value {value}\n")).collect();
+    cargo_bin_cmd!()
+        .arg("--global")
+        .write_stdin(large_input())
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+#[test]
+fn large_input_global_keep_last() -> Result<()> {
+    let expected: String = (0..1000)
+        .rev()
+        .map(|value| format!("value {value}\n"))
+        .collect();
+    cargo_bin_cmd!()
+        .args(["--global", "--keep", "last"])
+        .write_stdin(large_input())
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+#[test]
+fn dies_keep_without_global() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--keep", "last", INTERLEAVED])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--global"));
+    Ok(())
+}
+
+#[test]
+fn dies_global_and_count() -> Result<()> {
+    for flag in ["-c", "-d", "-u", "-D", "--group", "--within=2"] {
+        cargo_bin_cmd!()
+            .args(["--global", flag, INTERLEAVED])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("cannot be used with"));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn in_place() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("input.txt");
+    fs::write(&path, "a\na\nb\na\n")?;
+    cargo_bin_cmd!()
+        .args(["--in-place", path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout("");
+    assert_eq!(fs::read_to_string(&path)?, "a\nb\na\n");
+    // Nothing is left behind in the directory
+    assert_eq!(fs::read_dir(dir.path())?.count(), 1);
+    Ok(())
+}
+
+#[test]
+fn in_place_crlf() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("input.txt");
+    // Line endings are kept, and don't tell lines apart
+    fs::write(&path, "a\r\nb\r\nb\r\nb\nc\r\n")?;
+    cargo_bin_cmd!()
+        .args(["--in-place", path.to_str().unwrap()])
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&path)?, "a\r\nb\r\nc\r\n");
+    Ok(())
+}
+
+#[test]
+fn in_place_backup() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("input.txt");
+    fs::write(&path, "a\na\nb\n")?;
+    cargo_bin_cmd!()
+        .args(["-c", "--in-place=.orig", path.to_str().unwrap()])
+        .assert()
+        .success();
+    assert_eq!(fs::read_to_string(&path)?, "      2 a\n      1 b\n");
+    assert_eq!(
+        fs::read_to_string(dir.path().join("input.txt.orig"))?,
+        "a\na\nb\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn in_place_failure_keeps_input() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("input.txt");
+    fs::write(&path, b"a\n\xff\n")?;
+    cargo_bin_cmd!()
+        .args(["--in-place", path.to_str().unwrap()])
+        .assert()
+        .failure();
+    assert_eq!(fs::read(&path)?, b"a\n\xff\n");
+    assert_eq!(fs::read_dir(dir.path())?.count(), 1);
+    Ok(())
+}
+
+#[test]
+fn dies_in_place_empty_suffix() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("input.txt");
+    fs::write(&path, "a\na\n")?;
+    cargo_bin_cmd!()
+        .args(["--in-place=", path.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--in-place"));
+    assert_eq!(fs::read_to_string(&path)?, "a\na\n");
+    assert_eq!(fs::read_dir(dir.path())?.count(), 1);
+    Ok(())
+}
+
+#[test]
+fn dies_in_place_stdin() -> Result<()> {
+    cargo_bin_cmd!()
+        .arg("--in-place")
+        .write_stdin("a\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("standard input"));
+    Ok(())
+}
+
+#[test]
+fn dies_in_place_with_output() -> Result<()> {
+    let outfile = NamedTempFile::new()?;
+    cargo_bin_cmd!()
+        .args(["--in-place", INTERLEAVED, outfile.path().to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
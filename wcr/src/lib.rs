@@ -0,0 +1,1008 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashSet},
+    ffi::OsString,
+    fs::File,
+    io::{self, BufRead, BufReader, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+};
+
+use anyhow::{Result, anyhow, bail};
+use clap::{CommandFactory, Parser, ValueEnum};
+use serde::Serialize;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+#[derive(Debug, Parser)]
+#[command(author, version, about)]
+/// ‘wc’ in Rust
+struct Args {
+    #[arg(value_name = "FILE")]
+    /// filenames (or ‘-’ for stdin, the default without any files)
+    files: Vec<String>,
+
+    #[arg(short, long, default_value_t = false)]
+    /// print the newline counts
+    lines: bool,
+
+    #[arg(short, long, default_value_t = false)]
+    /// print the word count
+    words: bool,
+
+    #[arg(short('c'), long, default_value_t = false)]
+    /// print the bytes count
+    bytes: bool,
+
+    #[arg(short('m'), long, default_value_t = false, conflicts_with("bytes"))]
+    /// print the characters count
+    chars: bool,
+
+    #[arg(long, default_value_t = false)]
+    /// print the count of user-perceived characters (extended grapheme
+    /// clusters), e.g. ‘é’ written as ‘e’ plus a combining accent is one
+    graphemes: bool,
+
+    #[arg(long, default_value_t = false)]
+    /// print the display width of the widest line in terminal columns, with
+    /// tabs stopping every 8 columns
+    width: bool,
+
+    #[arg(long, default_value_t = false)]
+    /// print the number of different words
+    unique_words: bool,
+
+    #[arg(long, default_value_t = false, requires("unique_words"))]
+    /// with --unique-words, count words that only differ in case as one
+    lowercase: bool,
+
+    #[arg(long, value_name = "FILE", conflicts_with("files"))]
+    /// read the names of the input files from FILE, separated by NUL
+    /// characters (or from stdin if FILE is ‘-’)
+    files0_from: Option<String>,
+
+    #[arg(long, value_name = "FILE")]
+    /// also count the files named in FILE, one per line
+    args_file: Option<String>,
+
+    #[arg(long, value_name = "NAME")]
+    /// show NAME instead of ‘-’ as the name of stdin
+    label: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 1,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        conflicts_with("histogram")
+    )]
+    /// count up to N files at the same time; the output keeps the order of
+    /// the arguments
+    threads: usize,
+
+    #[arg(long, value_name = "KIND", value_enum)]
+    /// print a distribution of line or word lengths (in characters) over all
+    /// inputs instead of the counts
+    histogram: Option<HistogramKind>,
+
+    #[arg(long, value_enum, default_value_t = Format::Text, conflicts_with("histogram"))]
+    /// output format; json and tsv always include every count and the totals
+    format: Format,
+
+    #[arg(
+        long,
+        num_args = 2,
+        value_names = ["A", "B"],
+        conflicts_with_all = ["files", "files0_from", "args_file", "histogram"]
+    )]
+    /// print the counts of A and B, then how much B differs from A instead of
+    /// the totals
+    diff: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Format {
+    /// Aligned columns of the selected counts, like GNU wc
+    Text,
+    /// One JSON object per line for each file, then one for the totals
+    Json,
+    /// Tab-separated values with a header row
+    Tsv,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum HistogramKind {
+    Lines,
+    Words,
+}
+
+/// Maximum number of buckets a histogram is split into
+const HISTOGRAM_BUCKETS: usize = 10;
+
+/// Width of the longest bar in a histogram
+const HISTOGRAM_BAR_WIDTH: usize = 40;
+
+/// How often each line or word length was seen
+#[derive(Debug, PartialEq)]
+struct Histogram {
+    kind: HistogramKind,
+    lengths: BTreeMap<usize, u64>,
+}
+
+impl Histogram {
+    fn new(kind: HistogramKind) -> Self {
+        Histogram {
+            kind,
+            lengths: BTreeMap::new(),
+        }
+    }
+
+    fn add_line(&mut self, line: &str) {
+        match self.kind {
+            HistogramKind::Lines => {
+                let line = line.strip_suffix('\n').unwrap_or(line);
+                let line = line.strip_suffix('\r').unwrap_or(line);
+                self.add(line.chars().count());
+            }
+            HistogramKind::Words => line
+                .split_whitespace()
+                .for_each(|word| self.add(word.chars().count())),
+        }
+    }
+
+    fn add(&mut self, len: usize) {
+        *self.lengths.entry(len).or_default() += 1;
+    }
+
+    /// Groups the lengths into at most `HISTOGRAM_BUCKETS` buckets of equal
+    /// width, starting from zero, and returns `(first, last, count)` for each.
+    fn buckets(&self) -> Vec<(usize, usize, u64)> {
+        let Some(&max_len) = self.lengths.keys().last() else {
+            return vec![];
+        };
+        let width = (max_len + 1).div_ceil(HISTOGRAM_BUCKETS);
+        let mut buckets: Vec<_> = (0..=max_len / width)
+            .map(|i| (i * width, (i + 1) * width - 1, 0))
+            .collect();
+        for (len, count) in &self.lengths {
+            buckets[len / width].2 += count;
+        }
+        buckets
+    }
+
+    fn render(&self) -> String {
+        let buckets = self.buckets();
+        let labels: Vec<String> = buckets
+            .iter()
+            .map(|(first, last, _)| {
+                if first == last {
+                    first.to_string()
+                } else {
+                    format!("{first}-{last}")
+                }
+            })
+            .collect();
+        let label_width = labels.iter().map(String::len).max().unwrap_or(0);
+        let max_count = buckets.iter().map(|b| b.2).max().unwrap_or(0);
+
+        let mut ret = String::new();
+        for (label, (_, _, count)) in labels.iter().zip(&buckets) {
+            let bar = learnr::render_bar(*count, max_count, HISTOGRAM_BAR_WIDTH);
+            ret += &format!("{label:>label_width$} |{bar:<HISTOGRAM_BAR_WIDTH$} {count}\n");
+        }
+        ret
+    }
+}
+
+/// The counts that take more than a look at each character, which [`count`]
+/// leaves at zero unless asked for them.
+#[derive(Debug, Default, Copy, Clone)]
+struct Costly {
+    graphemes: bool,
+    width: bool,
+}
+
+impl Costly {
+    /// Those that the output asked for by `args` shows: every count, for
+    /// JSON and TSV.
+    fn from_args(args: &Args) -> Self {
+        let all = args.format != Format::Text;
+        Costly {
+            graphemes: args.graphemes || all,
+            width: args.width || all,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Default, Copy, Clone, Serialize)]
+struct FileInfo {
+    #[serde(rename = "lines")]
+    num_lines: usize,
+    #[serde(rename = "words")]
+    num_words: usize,
+    #[serde(rename = "bytes")]
+    num_bytes: usize,
+    #[serde(rename = "chars")]
+    num_chars: usize,
+    #[serde(rename = "graphemes")]
+    num_graphemes: usize,
+    #[serde(rename = "max_line_length")]
+    max_width: usize,
+    /// Only counted with --unique-words
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unique_words: Option<usize>,
+}
+
+/// At most this many different words are remembered for each input, and
+/// for the total
+const VOCABULARY_CAP: usize = 1_000_000;
+
+/// The different words seen, for --unique-words.
+#[derive(Debug, Default)]
+struct Vocabulary {
+    words: HashSet<String>,
+    /// Fold words to lowercase first
+    lowercase: bool,
+    /// More than `VOCABULARY_CAP` different words were seen, so that the
+    /// count falls short
+    overflow: bool,
+}
+
+impl Vocabulary {
+    fn new(lowercase: bool) -> Self {
+        Vocabulary {
+            lowercase,
+            ..Vocabulary::default()
+        }
+    }
+
+    fn add(&mut self, word: &str) {
+        if self.lowercase {
+            self.insert(word.to_lowercase());
+        } else if !self.words.contains(word) {
+            self.insert(word.to_string());
+        }
+    }
+
+    fn insert(&mut self, word: String) {
+        if self.words.len() < VOCABULARY_CAP {
+            self.words.insert(word);
+        } else if !self.words.contains(&word) {
+            self.overflow = true;
+        }
+    }
+
+    /// Adds the words of another input, for the total.
+    fn extend(&mut self, other: Vocabulary) {
+        self.overflow |= other.overflow;
+        other.words.into_iter().for_each(|word| self.insert(word));
+    }
+}
+
+/// How much each count of one file grew from another, for --diff.
+#[derive(Debug, PartialEq, Default, Copy, Clone, Serialize)]
+struct Delta {
+    lines: i64,
+    words: i64,
+    bytes: i64,
+    chars: i64,
+    graphemes: i64,
+    max_line_length: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unique_words: Option<i64>,
+}
+
+impl Delta {
+    fn between(from: &FileInfo, to: &FileInfo) -> Self {
+        let diff = |from: usize, to: usize| to as i64 - from as i64;
+        Delta {
+            lines: diff(from.num_lines, to.num_lines),
+            words: diff(from.num_words, to.num_words),
+            bytes: diff(from.num_bytes, to.num_bytes),
+            chars: diff(from.num_chars, to.num_chars),
+            graphemes: diff(from.num_graphemes, to.num_graphemes),
+            max_line_length: diff(from.max_width, to.max_width),
+            unique_words: from
+                .unique_words
+                .zip(to.unique_words)
+                .map(|(from, to)| diff(from, to)),
+        }
+    }
+}
+
+/// Turns the counts into output lines, so that all formats share the same
+/// `FileInfo` data.
+trait Formatter {
+    fn header(&self) -> Option<String> {
+        None
+    }
+
+    fn file(&self, name: &str, fi: &FileInfo) -> String;
+
+    fn total(&self, fi: &FileInfo) -> Option<String>;
+
+    fn delta(&self, delta: &Delta) -> String;
+}
+
+struct TextFormatter<'a> {
+    args: &'a Args,
+}
+
+impl Formatter for TextFormatter<'_> {
+    fn file(&self, name: &str, fi: &FileInfo) -> String {
+        // A lone stdin has no name, unless --label gives it one
+        if name == "-" && self.args.files.len() == 1 {
+            render_file_info(fi, self.args)
+        } else {
+            format!("{} {name}", render_file_info(fi, self.args))
+        }
+    }
+
+    fn total(&self, fi: &FileInfo) -> Option<String> {
+        (self.args.files.len() > 1).then(|| format!("{} total", render_file_info(fi, self.args)))
+    }
+
+    fn delta(&self, delta: &Delta) -> String {
+        let counts = [
+            delta.lines,
+            delta.words,
+            delta.unique_words.unwrap_or_default(),
+            delta.chars,
+            delta.graphemes,
+            delta.bytes,
+            delta.max_line_length,
+        ];
+        format!(
+            "{} delta",
+            render_columns(counts.map(|count| format!("{count:+}")), self.args)
+        )
+    }
+}
+
+struct JsonFormatter;
+
+#[derive(Serialize)]
+struct JsonRecord<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<&'a str>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    total: bool,
+    #[serde(flatten)]
+    counts: &'a FileInfo,
+}
+
+impl JsonFormatter {
+    fn record(file: Option<&str>, counts: &FileInfo) -> String {
+        let record = JsonRecord {
+            file,
+            total: file.is_none(),
+            counts,
+        };
+        serde_json::to_string(&record).expect("counts always serialize")
+    }
+}
+
+#[derive(Serialize)]
+struct JsonDelta<'a> {
+    delta: bool,
+    #[serde(flatten)]
+    counts: &'a Delta,
+}
+
+impl Formatter for JsonFormatter {
+    fn file(&self, name: &str, fi: &FileInfo) -> String {
+        JsonFormatter::record(Some(name), fi)
+    }
+
+    fn total(&self, fi: &FileInfo) -> Option<String> {
+        Some(JsonFormatter::record(None, fi))
+    }
+
+    fn delta(&self, delta: &Delta) -> String {
+        let record = JsonDelta {
+            delta: true,
+            counts: delta,
+        };
+        serde_json::to_string(&record).expect("counts always serialize")
+    }
+}
+
+/// Rows end with a column of --unique-words, when it is counted.
+struct TsvFormatter {
+    unique_words: bool,
+}
+
+impl TsvFormatter {
+    fn row(name: &str, fi: &FileInfo) -> String {
+        format!(
+            "{name}\t{}\t{}\t{}\t{}\t{}\t{}{}",
+            fi.num_lines,
+            fi.num_words,
+            fi.num_chars,
+            fi.num_bytes,
+            fi.num_graphemes,
+            fi.max_width,
+            unique_words_column(fi.unique_words)
+        )
+    }
+}
+
+fn unique_words_column(count: Option<impl std::fmt::Display>) -> String {
+    count.map(|count| format!("\t{count}")).unwrap_or_default()
+}
+
+impl Formatter for TsvFormatter {
+    fn header(&self) -> Option<String> {
+        Some(format!(
+            "file\tlines\twords\tchars\tbytes\tgraphemes\tmax_line_length{}",
+            if self.unique_words {
+                "\tunique_words"
+            } else {
+                ""
+            }
+        ))
+    }
+
+    fn file(&self, name: &str, fi: &FileInfo) -> String {
+        TsvFormatter::row(name, fi)
+    }
+
+    fn total(&self, fi: &FileInfo) -> Option<String> {
+        Some(TsvFormatter::row("total", fi))
+    }
+
+    fn delta(&self, delta: &Delta) -> String {
+        format!(
+            "delta\t{}\t{}\t{}\t{}\t{}\t{}{}",
+            delta.lines,
+            delta.words,
+            delta.chars,
+            delta.bytes,
+            delta.graphemes,
+            delta.max_line_length,
+            unique_words_column(delta.unique_words)
+        )
+    }
+}
+
+/// The command line that [`main`] parses, for generating the man page.
+pub fn command() -> clap::Command {
+    Args::command()
+}
+
+/// What the man page tells beyond --help.
+pub const MAN_DESCRIPTION: &str = "\
+Without options, the counts of lines, words and bytes are printed for each \
+FILE, followed by their totals when there are several. Counts always come in \
+the order lines, words, characters and bytes, whatever the order of the \
+options.\n\n\
+A word is a run of characters other than whitespace. With --unique-words, \
+up to a million different words are remembered for each FILE and for the \
+total, which counts words found in several files once; beyond that a warning \
+tells that the count falls short. An input that can't be \
+read is reported and the others are still counted.";
+
+pub fn main(args: impl IntoIterator<Item = OsString>) {
+    parse_args(args).and_then(run).unwrap_or_else(|err| {
+        learnr::exit_on_broken_pipe(&err);
+        eprintln!("{err}");
+        std::process::exit(1);
+    });
+}
+
+fn run(args: Args) -> Result<()> {
+    let mut out = io::stdout().lock();
+    if let Some(kind) = args.histogram {
+        let mut histogram = Histogram::new(kind);
+        for filename in &args.files {
+            open(filename)
+                .and_then(|file| count(file, Costly::default(), Some(&mut histogram), None))
+                .map(|_| ())
+                .unwrap_or_else(|err| eprintln!("{filename}: {err}"));
+        }
+        write!(out, "{}", histogram.render())?;
+        return Ok(());
+    }
+
+    let formatter: Box<dyn Formatter> = match args.format {
+        Format::Text => Box::new(TextFormatter { args: &args }),
+        Format::Json => Box::new(JsonFormatter),
+        Format::Tsv => Box::new(TsvFormatter {
+            unique_words: args.unique_words,
+        }),
+    };
+    if let Some(header) = formatter.header() {
+        writeln!(out, "{header}")?;
+    }
+
+    let mut totals = FileInfo::default();
+    let mut vocabulary = Vocabulary::new(args.lowercase);
+    let mut counted = Vec::new();
+
+    let new_vocabulary = || args.unique_words.then(|| Vocabulary::new(args.lowercase));
+    let costly = Costly::from_args(&args);
+    for_each_count(
+        &args.files,
+        args.threads,
+        costly,
+        new_vocabulary,
+        |filename, fi| {
+            let name = match (filename, &args.label) {
+                ("-", Some(label)) => label,
+                _ => filename,
+            };
+            match fi {
+                Ok((fi, file_vocabulary)) => {
+                    if let Some(file_vocabulary) = file_vocabulary {
+                        warn_if_overflow(name, &file_vocabulary);
+                        vocabulary.extend(file_vocabulary);
+                    }
+                    totals.num_lines += fi.num_lines;
+                    totals.num_words += fi.num_words;
+                    totals.num_bytes += fi.num_bytes;
+                    totals.num_chars += fi.num_chars;
+                    totals.num_graphemes += fi.num_graphemes;
+                    totals.max_width = totals.max_width.max(fi.max_width);
+                    counted.push(fi);
+                    writeln!(out, "{}", formatter.file(name, &fi))
+                }
+                Err(err) => {
+                    eprintln!("{name}: {err}");
+                    Ok(())
+                }
+            }
+        },
+    )?;
+    if args.unique_words {
+        totals.unique_words = Some(vocabulary.words.len());
+        if args.files.len() > 1 {
+            warn_if_overflow("total", &vocabulary);
+        }
+    }
+    if args.diff.is_some() {
+        // Without both counts there is nothing to compare, and the error
+        // has been reported already
+        if let [from, to] = counted[..] {
+            writeln!(out, "{}", formatter.delta(&Delta::between(&from, &to)))?;
+        }
+    } else if let Some(total) = formatter.total(&totals) {
+        writeln!(out, "{total}")?;
+    }
+    Ok(())
+}
+
+fn warn_if_overflow(name: &str, vocabulary: &Vocabulary) {
+    if vocabulary.overflow {
+        eprintln!(
+            "{name}: warning: more than {VOCABULARY_CAP} different words, \
+             --unique-words only counts that many"
+        );
+    }
+}
+
+/// Counts `files` on up to `threads` worker threads and hands each result to
+/// `report` in the order of `files`, as soon as it and all earlier ones are
+/// done, along with its words when `new_vocabulary` starts collecting them.
+/// The first error of `report`, a failed write, stops the counting.
+fn for_each_count(
+    files: &[String],
+    threads: usize,
+    costly: Costly,
+    new_vocabulary: impl Fn() -> Option<Vocabulary> + Sync,
+    mut report: impl FnMut(&str, Result<(FileInfo, Option<Vocabulary>)>) -> io::Result<()>,
+) -> io::Result<()> {
+    let count_file = |filename: &str| {
+        let mut vocabulary = new_vocabulary();
+        let fi = open(filename).and_then(|file| count(file, costly, None, vocabulary.as_mut()))?;
+        Ok((fi, vocabulary))
+    };
+    if threads == 1 {
+        return files
+            .iter()
+            .try_for_each(|filename| report(filename, count_file(filename)));
+    }
+
+    let next = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..threads.min(files.len()) {
+            let tx = tx.clone();
+            let next = &next;
+            scope.spawn(move || {
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(filename) = files.get(i) else {
+                        break;
+                    };
+                    if tx.send((i, count_file(filename))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        // Results arrive in any order, hold back those that are early
+        let mut pending = BTreeMap::new();
+        let mut reported = 0;
+        for (i, fi) in rx {
+            pending.insert(i, fi);
+            while let Some(fi) = pending.remove(&reported) {
+                // Returning drops the receiver, which stops the workers
+                report(&files[reported], fi)?;
+                reported += 1;
+            }
+        }
+        Ok(())
+    })
+}
+
+fn render_file_info(fi: &FileInfo, args: &Args) -> String {
+    render_columns(
+        [
+            fi.num_lines,
+            fi.num_words,
+            fi.unique_words.unwrap_or_default(),
+            fi.num_chars,
+            fi.num_graphemes,
+            fi.num_bytes,
+            fi.max_width,
+        ],
+        args,
+    )
+}
+
+/// Lines up the selected ones of `counts`, given in the order lines, words,
+/// unique words, chars, graphemes, bytes and width.
+fn render_columns(counts: [impl std::fmt::Display; 7], args: &Args) -> String {
+    let selected = [
+        args.lines,
+        args.words,
+        args.unique_words,
+        args.chars,
+        args.graphemes,
+        args.bytes,
+        args.width,
+    ];
+    let mut ret = " ".to_string();
+    for (count, selected) in counts.iter().zip(selected) {
+        if selected {
+            ret += &format!("{count:>7} ");
+        }
+    }
+    ret.trim_end().to_string()
+}
+
+fn count(
+    mut file: impl BufRead,
+    costly: Costly,
+    mut histogram: Option<&mut Histogram>,
+    mut vocabulary: Option<&mut Vocabulary>,
+) -> Result<FileInfo> {
+    let mut num_lines = 0;
+    let mut num_words = 0;
+    let mut num_chars = 0;
+    let mut num_bytes = 0;
+    let mut num_graphemes = 0;
+    let mut max_width = 0;
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        let bytes_read = file.read_until(b'\n', &mut buf)?;
+        if bytes_read == 0 {
+            break;
+        }
+        let line = valid_text(&buf);
+        if let Some(histogram) = histogram.as_deref_mut() {
+            histogram.add_line(&line);
+        }
+        num_words += line.split_whitespace().count();
+        if let Some(vocabulary) = vocabulary.as_deref_mut() {
+            line.split_whitespace()
+                .for_each(|word| vocabulary.add(word));
+        }
+        if buf.last() == Some(&b'\n') {
+            num_lines += 1;
+        }
+        num_chars += line.chars().count();
+        if costly.graphemes {
+            num_graphemes += line.graphemes(true).count();
+        }
+        if costly.width {
+            max_width = max_width.max(display_width(&line));
+        }
+        num_bytes += bytes_read;
+    }
+    Ok(FileInfo {
+        num_lines,
+        num_words,
+        num_chars,
+        num_bytes,
+        num_graphemes,
+        max_width,
+        unique_words: vocabulary.map(|vocabulary| vocabulary.words.len()),
+    })
+}
+
+/// `bytes` as the text GNU wc counts in them: the valid UTF-8, leaving out
+/// the bytes that aren't any, which are neither characters nor words.
+fn valid_text(bytes: &[u8]) -> Cow<'_, str> {
+    match str::from_utf8(bytes) {
+        Ok(text) => Cow::Borrowed(text),
+        Err(_) => Cow::Owned(bytes.utf8_chunks().map(|chunk| chunk.valid()).collect()),
+    }
+}
+
+/// Terminal columns taken by `line` without its terminator.
+fn display_width(line: &str) -> usize {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    let mut segments = line.split('\t');
+    let mut width = segments.next().map_or(0, UnicodeWidthStr::width);
+    for segment in segments {
+        width = (width / 8 + 1) * 8 + segment.width();
+    }
+    width
+}
+
+fn parse_args(args: impl IntoIterator<Item = OsString>) -> Result<Args> {
+    let mut args = Args::parse_from(args);
+
+    if let Some(diff) = &args.diff {
+        args.files = diff.clone();
+    }
+    if let Some(list) = &args.files0_from {
+        args.files = read_files0(list)?;
+    }
+    if let Some(list) = &args.args_file {
+        for line in open(list)?.lines() {
+            let name = line.map_err(|err| anyhow!("{list}: {err}"))?;
+            if !name.is_empty() {
+                args.files.push(name);
+            }
+        }
+    }
+    if args.files.is_empty() && args.files0_from.is_none() && args.args_file.is_none() {
+        args.files.push("-".to_string());
+    }
+
+    // none of the explicit args is present
+    if [
+        args.lines,
+        args.words,
+        args.bytes,
+        args.chars,
+        args.graphemes,
+        args.width,
+        args.unique_words,
+    ]
+    .iter()
+    .all(|v| !v)
+    {
+        args.lines = true;
+        args.words = true;
+        args.bytes = true;
+    }
+    Ok(args)
+}
+
+/// Reads the NUL-separated file names for --files0-from; a final NUL is
+/// optional, as in ‘find -print0’ output. An empty name is reported with
+/// its place in the list, like GNU wc does, and the others are still read.
+fn read_files0(list: &str) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    for (idx, name) in open(list)?.split(b'\0').enumerate() {
+        let name = String::from_utf8(name?).map_err(|err| anyhow!("{list}: {err}"))?;
+        if name.is_empty() {
+            eprintln!("{list}:{}: invalid zero-length file name", idx + 1);
+            continue;
+        }
+        if list == "-" && name == "-" {
+            bail!("when reading file names from stdin, no file name of '-' allowed");
+        }
+        names.push(name);
+    }
+    Ok(names)
+}
+
+fn open(filename: &str) -> Result<Box<dyn BufRead>> {
+    match filename {
+        "-" => Ok(Box::new(BufReader::new(std::io::stdin()))),
+        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assertables::*;
+
+    use super::{
+        Costly, Delta, FileInfo, Histogram, HistogramKind, Vocabulary, count, display_width,
+    };
+    use std::io::Cursor;
+
+    const ALL: Costly = Costly {
+        graphemes: true,
+        width: true,
+    };
+
+    fn assert_count_string(
+        s: &str,
+        num_lines: usize,
+        num_words: usize,
+        num_chars: usize,
+        num_bytes: usize,
+    ) {
+        let fi = count(Cursor::new(s), ALL, None, None).unwrap();
+        assert_eq!(
+            (fi.num_lines, fi.num_words, fi.num_chars, fi.num_bytes),
+            (num_lines, num_words, num_chars, num_bytes)
+        );
+    }
+
+    #[test]
+    fn test_count_empty() {
+        assert_count_string("", 0, 0, 0, 0);
+    }
+
+    #[test]
+    fn test_count() {
+        assert_count_string(
+            "I don't want the world.\nI just want your half.\r\n",
+            2,
+            10,
+            48,
+            48,
+        );
+    }
+
+    #[test]
+    fn test_count_no_trailing_newline() {
+        // Like GNU wc, only newlines are counted as lines
+        assert_count_string("one\ntwo", 1, 2, 7, 7);
+    }
+
+    #[test]
+    fn test_count_invalid_utf8() {
+        // Like GNU wc, bytes that aren't UTF-8 are only counted as bytes
+        let expected = FileInfo {
+            num_lines: 1,
+            num_words: 2,
+            num_chars: 7,
+            num_bytes: 8,
+            num_graphemes: 7,
+            max_width: 6,
+            unique_words: None,
+        };
+        assert_ok_eq_x!(
+            count(Cursor::new(b"caf\xe9 ok\n"), ALL, None, None),
+            expected
+        );
+        assert_eq!(
+            count(Cursor::new(b"\xff a\n"), ALL, None, None)
+                .unwrap()
+                .num_words,
+            1
+        );
+    }
+
+    #[test]
+    fn test_count_graphemes_and_width() {
+        // ‘e’ + combining acute, a family emoji joined by ZWJs, and CJK
+        let text = "cafe\u{301}\n👨\u{200d}👩\u{200d}👧\n日本語\n";
+        let fi = count(Cursor::new(text), ALL, None, None).unwrap();
+        assert_eq!(fi.num_chars, 16);
+        assert_eq!(fi.num_graphemes, 11);
+        assert_eq!(fi.max_width, 6);
+
+        // Only what is asked for is counted
+        let width = Costly {
+            graphemes: false,
+            width: true,
+        };
+        let fi = count(Cursor::new(text), width, None, None).unwrap();
+        assert_eq!((fi.num_graphemes, fi.max_width), (0, 6));
+    }
+
+    #[test]
+    fn test_count_unique_words() {
+        let text = "The cat saw the\ncat. The end\n";
+        let mut vocabulary = Vocabulary::new(false);
+        let fi = count(Cursor::new(text), ALL, None, Some(&mut vocabulary)).unwrap();
+        assert_eq!(fi.unique_words, Some(6));
+        let mut vocabulary = Vocabulary::new(true);
+        let fi = count(Cursor::new(text), ALL, None, Some(&mut vocabulary)).unwrap();
+        assert_eq!(fi.unique_words, Some(5));
+
+        let mut total = Vocabulary::new(false);
+        let mut other = Vocabulary::new(false);
+        other.add("dog");
+        other.add("cat");
+        total.add("cat");
+        total.extend(other);
+        assert_eq!(total.words.len(), 2);
+        assert!(!total.overflow);
+    }
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width(""), 0);
+        assert_eq!(display_width("abc\r\n"), 3);
+        assert_eq!(display_width("\tx"), 9);
+        assert_eq!(display_width("abc\tx\t"), 16);
+        assert_eq!(display_width("日本\n"), 4);
+    }
+
+    #[test]
+    fn test_histogram_lines() {
+        let mut histogram = Histogram::new(HistogramKind::Lines);
+        let text = "abc\n\nabcdefghijkl\r\nabcd\nab";
+        count(Cursor::new(text), ALL, Some(&mut histogram), None).unwrap();
+        // 13 possible lengths (0..=12) make buckets two wide
+        assert_eq!(
+            histogram.buckets(),
+            vec![
+                (0, 1, 1),
+                (2, 3, 2),
+                (4, 5, 1),
+                (6, 7, 0),
+                (8, 9, 0),
+                (10, 11, 0),
+                (12, 13, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_histogram_words() {
+        let mut histogram = Histogram::new(HistogramKind::Words);
+        count(
+            Cursor::new("a bb ccc\nbb  a\n"),
+            ALL,
+            Some(&mut histogram),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            histogram.buckets(),
+            vec![(0, 0, 0), (1, 1, 2), (2, 2, 2), (3, 3, 1)]
+        );
+        assert_eq!(
+            histogram.render(),
+            format!(
+                "0 |{}0\n1 |{}2\n2 |{}2\n3 |{}1\n",
+                " ".repeat(41),
+                "#".repeat(40) + " ",
+                "#".repeat(40) + " ",
+                "#".repeat(20) + &" ".repeat(21),
+            )
+        );
+    }
+
+    #[test]
+    fn test_histogram_empty() {
+        let mut histogram = Histogram::new(HistogramKind::Words);
+        count(Cursor::new(""), ALL, Some(&mut histogram), None).unwrap();
+        assert_eq!(histogram.render(), "");
+    }
+
+    #[test]
+    fn test_delta_between() {
+        let from = count(Cursor::new("one two\nthree\n"), ALL, None, None).unwrap();
+        let to = count(Cursor::new("one\n"), ALL, None, None).unwrap();
+        let expected = Delta {
+            lines: -1,
+            words: -2,
+            bytes: -10,
+            chars: -10,
+            graphemes: -10,
+            max_line_length: -4,
+            unique_words: None,
+        };
+        assert_eq!(Delta::between(&from, &to), expected);
+        assert_eq!(Delta::between(&to, &to), Delta::default());
+    }
+}
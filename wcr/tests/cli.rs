@@ -8,6 +8,8 @@ use std::fs;
 const EMPTY: &str = "tests/inputs/empty.txt";
 const FOX: &str = "tests/inputs/fox.txt";
 const ATLAMAL: &str = "tests/inputs/atlamal.txt";
+const FILES0: &str = "tests/inputs/all.files0";
+const ARGS_FILE: &str = "tests/inputs/files.list";
 
 // --------------------------------------------------
 fn gen_bad_file() -> String {
@@ -213,3 +215,342 @@ fn test_all_words_lines() -> Result<()> {
 fn test_all_bytes_lines() -> Result<()> {
     run(&["-cl", EMPTY, FOX, ATLAMAL], "tests/expected/all.cl.out")
 }
+
+// --------------------------------------------------
+#[test]
+fn test_atlamal_histogram_lines() -> Result<()> {
+    run(
+        &["--histogram", "lines", ATLAMAL],
+        "tests/expected/atlamal.txt.histogram-lines.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_histogram_words() -> Result<()> {
+    run(
+        &["--histogram=words", EMPTY, FOX, ATLAMAL],
+        "tests/expected/all.histogram-words.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn test_empty_histogram() -> Result<()> {
+    run(
+        &["--histogram", "lines", EMPTY],
+        "tests/expected/empty.txt.histogram.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_histogram() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--histogram", "bytes", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "invalid value 'bytes' for '--histogram <KIND>'",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from() -> Result<()> {
+    run(&["--files0-from", FILES0], "tests/expected/all.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from_stdin() -> Result<()> {
+    let expected = fs::read_to_string("tests/expected/all.out")?;
+    cargo_bin_cmd!()
+        .args(["--files0-from=-"])
+        .write_stdin(format!("{EMPTY}\0{FOX}\0{ATLAMAL}"))
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_files0_from_and_files() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--files0-from", FILES0, FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from_empty_name() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--files0-from=-"])
+        .write_stdin(format!("{FOX}\0\0{EMPTY}"))
+        .assert()
+        .success()
+        .stdout(format!(
+            "       1       9      48 {FOX}\n       0       0       0 {EMPTY}\n       1       9      48 total\n"
+        ))
+        .stderr("-:2: invalid zero-length file name\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_files0_from_stdin_dash() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--files0-from=-"])
+        .write_stdin("-\0")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no file name of '-' allowed"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn all_threads() -> Result<()> {
+    run(
+        &["--threads", "2", EMPTY, FOX, ATLAMAL],
+        "tests/expected/all.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn many_files_threads() -> Result<()> {
+    // More files than threads, results must still come out in order
+    let files: Vec<&str> = [EMPTY, FOX, ATLAMAL].repeat(20);
+    let serial = cargo_bin_cmd!().args(&files).output()?;
+    let parallel = cargo_bin_cmd!()
+        .args(["--threads", "4"])
+        .args(&files)
+        .output()?;
+    assert!(parallel.status.success());
+    assert_eq!(
+        String::from_utf8(parallel.stdout)?,
+        String::from_utf8(serial.stdout)?
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_zero_threads() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--threads", "0", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("0 is not in 1.."));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn atlamal_stdin_label() -> Result<()> {
+    let input = fs::read_to_string(ATLAMAL)?;
+    cargo_bin_cmd!()
+        .args(["--label", "atlamal", "-l"])
+        .write_stdin(input)
+        .assert()
+        .success()
+        .stdout("       4 atlamal\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn stdin_label_with_files() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--label=<stdin>", "-l", FOX, "-"])
+        .write_stdin("a\nb\n")
+        .assert()
+        .success()
+        .stdout("       1 tests/inputs/fox.txt\n       2 <stdin>\n       3 total\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn args_file() -> Result<()> {
+    // The named files come after the positional ones
+    run(&["--args-file", ARGS_FILE, EMPTY], "tests/expected/all.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn args_file_without_files() -> Result<()> {
+    // Stdin is only the default when no files are named at all
+    cargo_bin_cmd!()
+        .args(["-l", "--args-file", ARGS_FILE])
+        .write_stdin("never read\n")
+        .assert()
+        .success()
+        .stdout(
+            "       1 tests/inputs/fox.txt\n       4 tests/inputs/atlamal.txt\n       5 total\n",
+        );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn graphemes_and_width() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["-m", "--graphemes", "--width", "-", FOX])
+        .write_stdin("cafe\u{301}\n日本語\n")
+        .assert()
+        .success()
+        .stdout("      10       9       6 -\n      48      48      50 tests/inputs/fox.txt\n      58      57      50 total\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn format_json() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--format", "json", EMPTY, FOX])
+        .assert()
+        .success()
+        .stdout(concat!(
+            r#"{"file":"tests/inputs/empty.txt","lines":0,"words":0,"bytes":0,"chars":0,"graphemes":0,"max_line_length":0}"#,
+            "\n",
+            r#"{"file":"tests/inputs/fox.txt","lines":1,"words":9,"bytes":48,"chars":48,"graphemes":48,"max_line_length":50}"#,
+            "\n",
+            r#"{"total":true,"lines":1,"words":9,"bytes":48,"chars":48,"graphemes":48,"max_line_length":50}"#,
+            "\n",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn format_tsv_stdin() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--format=tsv", "-l"])
+        .write_stdin("a b\n")
+        .assert()
+        .success()
+        .stdout("file\tlines\twords\tchars\tbytes\tgraphemes\tmax_line_length\n-\t1\t2\t4\t4\t4\t3\ntotal\t1\t2\t4\t4\t4\t3\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn diff() -> Result<()> {
+    run(
+        &["--diff", FOX, ATLAMAL],
+        "tests/expected/fox.txt.atlamal.txt.diff.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn diff_shrinking_lm() -> Result<()> {
+    run(
+        &["-lm", "--diff", ATLAMAL, FOX],
+        "tests/expected/atlamal.txt.fox.txt.diff.lm.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn diff_format_json() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--format=json", "--diff", FOX, FOX])
+        .assert()
+        .success()
+        .stdout(predicate::str::ends_with(
+            r#"{"delta":true,"lines":0,"words":0,"bytes":0,"chars":0,"graphemes":0,"max_line_length":0}"#
+                .to_string()
+                + "\n",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn diff_missing_file() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--diff", FOX, "tests/inputs/missing.txt"])
+        .assert()
+        .stdout("       1       9      48 tests/inputs/fox.txt\n")
+        .stderr(predicate::str::contains("tests/inputs/missing.txt: "));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_diff_with_files() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--diff", FOX, ATLAMAL, EMPTY])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn unique_words() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--unique-words", FOX])
+        .assert()
+        .success()
+        .stdout("       9 tests/inputs/fox.txt\n");
+    cargo_bin_cmd!()
+        .args(["-w", "--unique-words", "--lowercase", FOX])
+        .assert()
+        .success()
+        .stdout("       9       8 tests/inputs/fox.txt\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn unique_words_total() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let a = dir.path().join("a");
+    let b = dir.path().join("b");
+    fs::write(&a, "one two two\n")?;
+    fs::write(&b, "two three\n")?;
+    let (a, b) = (a.to_str().unwrap(), b.to_str().unwrap());
+    // Words in both files count once in the total
+    cargo_bin_cmd!()
+        .args(["--unique-words", a, b])
+        .assert()
+        .success()
+        .stdout(format!("       2 {a}\n       2 {b}\n       3 total\n"));
+    cargo_bin_cmd!()
+        .args(["--unique-words", "--format", "tsv", a, b])
+        .assert()
+        .success()
+        .stdout(format!(
+            "file\tlines\twords\tchars\tbytes\tgraphemes\tmax_line_length\tunique_words\n\
+             {a}\t1\t3\t12\t12\t12\t11\t2\n\
+             {b}\t1\t2\t10\t10\t10\t9\t2\n\
+             total\t2\t5\t22\t22\t22\t11\t3\n"
+        ));
+    cargo_bin_cmd!()
+        .args(["--unique-words", "--format", "json", b])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"unique_words\":2}"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_lowercase_without_unique_words() -> Result<()> {
+    cargo_bin_cmd!()
+        .args(["--lowercase", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--unique-words"));
+    Ok(())
+}
@@ -0,0 +1,34 @@
+//! wcr against GNU wc on generated input, where GNU wc is installed.
+
+use std::sync::LazyLock;
+
+use learnr::compare::{Reference, text};
+use proptest::{prelude::*, sample::subsequence};
+
+static WC: LazyLock<Option<Reference>> =
+    LazyLock::new(|| Reference::find(assert_cmd::cargo::cargo_bin!("wcr"), "wc"));
+
+/// Column widths differ from GNU wc's, which depend on the input size.
+fn columns(stdout: &str) -> String {
+    stdout
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+proptest! {
+    #[test]
+    fn counts_same_as_wc(
+        input in text(),
+        // wcr takes either --bytes or --chars, GNU wc both at once
+        flags in subsequence(vec!["-l", "-w", "-c", "-m"], 0..=4)
+            .prop_filter("both -c and -m", |flags| {
+                !(flags.contains(&"-c") && flags.contains(&"-m"))
+            }),
+    ) {
+        if let Some(wc) = &*WC {
+            wc.check_with(&flags, &input, columns)?;
+        }
+    }
+}